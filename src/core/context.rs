@@ -28,10 +28,12 @@ impl RenderContext {
             .map_err(|_| RenderError::AdapterNotFound)?;
 
         // 3. Device & Queue: Command channel
+        // 16-bit normalized formats are optional; enable them for HDR sinks when available.
+        let optional_features = wgpu::Features::TEXTURE_FORMAT_16BIT_NORM;
         let device_result = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("videomti_main_device"),
-                required_features: wgpu::Features::empty(),
+                required_features: adapter.features() & optional_features,
                 required_limits: wgpu::Limits::default(),
                 memory_hints: wgpu::MemoryHints::default(),
                 ..Default::default()
@@ -8,4 +8,24 @@ pub enum RenderError {
     DeviceCreationFailed(#[from] wgpu::RequestDeviceError),
     #[error("Surface error: {0}")]
     SurfaceError(#[from] wgpu::SurfaceError),
+    #[error("Invalid upload: {0}")]
+    InvalidUpload(String),
+    #[error("Failed to map readback buffer: {0}")]
+    BufferMapFailed(#[from] wgpu::BufferAsyncError),
+    #[error("Readback was dropped before the GPU finished")]
+    ReadbackAborted,
+    #[error("Failed to decode image: {0}")]
+    ImageDecode(#[from] image::ImageError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid source: {0}")]
+    InvalidSource(String),
+    #[error("Frame {0} is missing from the sequence")]
+    MissingFrame(u32),
+    #[error("Invalid output: {0}")]
+    InvalidOutput(String),
+    #[error("Encoding failed: {0}")]
+    EncodeFailed(String),
+    #[error("Output process exited with {0}")]
+    ProcessExited(std::process::ExitStatus),
 }
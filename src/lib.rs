@@ -4,3 +4,4 @@ pub mod outputs;
 pub mod pipeline;
 pub mod renderer;
 pub mod resources; // Added renderer module
+pub mod sources;
@@ -0,0 +1,318 @@
+use glam::{Mat3, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// RGB primaries of a color space. All supported gamuts use a D65 white point,
+/// so conversions between them need no chromatic adaptation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ColorPrimaries {
+    /// ITU-R BT.709, shared by sRGB.
+    Rec709,
+    /// ITU-R BT.2020 / BT.2100.
+    Rec2020,
+    /// Display P3 (DCI-P3 primaries with a D65 white point).
+    DisplayP3,
+    /// Sony S-Gamut3.Cine, paired with S-Log3.
+    SGamut3Cine,
+    /// ARRI Wide Gamut 3, paired with LogC3.
+    ArriWideGamut3,
+}
+
+impl ColorPrimaries {
+    /// CIE xy chromaticities of the red, green and blue primaries.
+    pub fn chromaticities(self) -> [[f32; 2]; 3] {
+        match self {
+            Self::Rec709 => [[0.640, 0.330], [0.300, 0.600], [0.150, 0.060]],
+            Self::Rec2020 => [[0.708, 0.292], [0.170, 0.797], [0.131, 0.046]],
+            Self::DisplayP3 => [[0.680, 0.320], [0.265, 0.690], [0.150, 0.060]],
+            Self::SGamut3Cine => [[0.766, 0.275], [0.225, 0.800], [0.089, -0.087]],
+            Self::ArriWideGamut3 => [[0.684, 0.313], [0.221, 0.848], [0.0861, -0.102]],
+        }
+    }
+
+    /// Linear RGB -> CIE XYZ matrix (D65).
+    pub fn to_xyz(self) -> Mat3 {
+        const D65: [f32; 2] = [0.3127, 0.3290];
+        let xyz = |[x, y]: [f32; 2]| Vec3::new(x / y, 1.0, (1.0 - x - y) / y);
+
+        let [r, g, b] = self.chromaticities();
+        let m = Mat3::from_cols(xyz(r), xyz(g), xyz(b));
+        let s = m.inverse() * xyz(D65);
+        m * Mat3::from_diagonal(s)
+    }
+
+    /// Coefficients giving relative luminance (Y) from linear RGB.
+    pub fn luma_coefficients(self) -> Vec3 {
+        self.to_xyz().row(1)
+    }
+
+    /// Matrix converting linear RGB in `self` to linear RGB in `target`.
+    pub fn conversion_to(self, target: ColorPrimaries) -> Mat3 {
+        if self == target {
+            return Mat3::IDENTITY;
+        }
+        target.to_xyz().inverse() * self.to_xyz()
+    }
+}
+
+/// Transfer function (OETF / inverse EOTF) used to encode the stored values.
+///
+/// The discriminants are shared with the `TF_*` constants in `color.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u32)]
+pub enum TransferFunction {
+    Linear = 0,
+    /// IEC 61966-2-1 piecewise sRGB curve.
+    Srgb = 1,
+    /// ITU-R BT.709 / BT.2020 OETF.
+    Rec709 = 2,
+    /// Sony S-Log3.
+    SLog3 = 3,
+    /// ARRI LogC3 (EI 800).
+    LogC3 = 4,
+    /// SMPTE ST 2084 perceptual quantizer. Linear 1.0 is 10 000 nits.
+    Pq = 5,
+    /// ARIB STD-B67 hybrid log-gamma OETF. Linear 1.0 is the nominal peak
+    /// (1000 nits after the BT.2100 OOTF).
+    Hlg = 6,
+}
+
+impl TransferFunction {
+    /// Identifier used by the shaders.
+    pub fn shader_id(self) -> u32 {
+        self as u32
+    }
+
+    /// Whether the curve carries absolute HDR luminance rather than values
+    /// relative to reference white.
+    pub fn is_hdr(self) -> bool {
+        matches!(self, Self::Pq | Self::Hlg)
+    }
+
+    /// Whether the curve encodes display-referred SDR video or graphics.
+    pub fn is_sdr(self) -> bool {
+        matches!(self, Self::Srgb | Self::Rec709)
+    }
+
+    /// Decodes an encoded value to scene/display linear light.
+    pub fn to_linear(self, v: f32) -> f32 {
+        match self {
+            Self::Linear => v,
+            Self::Srgb => {
+                if v <= 0.04045 {
+                    v / 12.92
+                } else {
+                    ((v + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            Self::Rec709 => {
+                if v < 0.081 {
+                    v / 4.5
+                } else {
+                    ((v + 0.099) / 1.099).powf(1.0 / 0.45)
+                }
+            }
+            Self::SLog3 => {
+                if v >= 171.210_3 / 1023.0 {
+                    10f32.powf((v * 1023.0 - 420.0) / 261.5) * 0.19 - 0.01
+                } else {
+                    (v * 1023.0 - 95.0) * 0.01125 / (171.210_3 - 95.0)
+                }
+            }
+            Self::LogC3 => {
+                let (cut, a, b, c, d, e, f) = LOGC3;
+                if v > e * cut + f {
+                    (10f32.powf((v - d) / c) - b) / a
+                } else {
+                    (v - f) / e
+                }
+            }
+            Self::Pq => {
+                let (m1, m2, c1, c2, c3) = PQ;
+                let p = v.max(0.0).powf(1.0 / m2);
+                ((p - c1).max(0.0) / (c2 - c3 * p)).powf(1.0 / m1)
+            }
+            Self::Hlg => {
+                let (a, b, c) = HLG;
+                if v <= 0.5 {
+                    v * v / 3.0
+                } else {
+                    (((v - c) / a).exp() + b) / 12.0
+                }
+            }
+        }
+    }
+
+    /// Encodes a linear value with this transfer function.
+    pub fn from_linear(self, l: f32) -> f32 {
+        match self {
+            Self::Linear => l,
+            Self::Srgb => {
+                if l <= 0.003_130_8 {
+                    l * 12.92
+                } else {
+                    1.055 * l.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            Self::Rec709 => {
+                if l < 0.018 {
+                    l * 4.5
+                } else {
+                    1.099 * l.powf(0.45) - 0.099
+                }
+            }
+            Self::SLog3 => {
+                if l >= 0.01125 {
+                    (420.0 + ((l + 0.01) / 0.19).log10() * 261.5) / 1023.0
+                } else {
+                    (l * (171.210_3 - 95.0) / 0.01125 + 95.0) / 1023.0
+                }
+            }
+            Self::LogC3 => {
+                let (cut, a, b, c, d, e, f) = LOGC3;
+                if l > cut {
+                    c * (a * l + b).log10() + d
+                } else {
+                    e * l + f
+                }
+            }
+            Self::Pq => {
+                let (m1, m2, c1, c2, c3) = PQ;
+                let y = l.max(0.0).powf(m1);
+                ((c1 + c2 * y) / (1.0 + c3 * y)).powf(m2)
+            }
+            Self::Hlg => {
+                let (a, b, c) = HLG;
+                let l = l.max(0.0);
+                if l <= 1.0 / 12.0 {
+                    (3.0 * l).sqrt()
+                } else {
+                    a * (12.0 * l - b).ln() + c
+                }
+            }
+        }
+    }
+}
+
+// ARRI LogC3 EI 800 parameters: (cut, a, b, c, d, e, f).
+const LOGC3: (f32, f32, f32, f32, f32, f32, f32) = (
+    0.010_591, 5.555_556, 0.052_272, 0.247_190, 0.385_537, 5.367_655, 0.092_809,
+);
+
+// SMPTE ST 2084 constants: (m1, m2, c1, c2, c3).
+const PQ: (f32, f32, f32, f32, f32) = (0.159_301_76, 78.843_75, 0.835_937_5, 18.851_562, 18.687_5);
+
+// ARIB STD-B67 constants: (a, b, c).
+const HLG: (f32, f32, f32) = (0.178_832_77, 0.284_668_92, 0.559_910_7);
+
+/// Nominal diffuse white for HDR graphics and SDR content, per ITU-R BT.2408.
+pub const DEFAULT_REFERENCE_WHITE_NITS: f32 = 203.0;
+
+/// Expands SDR sources into HDR headroom. Luminance up to `knee` (relative to
+/// reference white) is left alone; the rest of the SDR range is stretched so
+/// SDR peak white lands on `peak_nits`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InverseToneMapping {
+    pub peak_nits: f32,
+    pub knee: f32,
+}
+
+impl Default for InverseToneMapping {
+    fn default() -> Self {
+        Self {
+            peak_nits: 1000.0,
+            knee: 0.6,
+        }
+    }
+}
+
+/// Curve used to fit HDR or float highlights into a smaller output range.
+///
+/// The discriminants are shared with the `TM_*` constants in `output.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u32)]
+pub enum ToneMapOperator {
+    /// Hard clip at the target peak.
+    Clip = 1,
+    /// Extended Reinhard with the source peak as white point.
+    Reinhard = 2,
+    /// John Hable's filmic curve (Uncharted 2).
+    Hable = 3,
+    /// Krzysztof Narkowicz's ACES filmic fit.
+    Aces = 4,
+    /// ITU-R BT.2390 EETF, a hermite roll-off in the PQ domain.
+    Bt2390 = 5,
+}
+
+/// Output-stage tone mapping applied before a sink's transfer encoding.
+/// Curves work on max(R, G, B), so hue is preserved.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ToneMapping {
+    pub operator: ToneMapOperator,
+    /// Brightest luminance expected in the source content, in nits.
+    pub source_peak_nits: f32,
+    /// Peak luminance of the output, in nits. `None` uses the composition's
+    /// reference white, which is what SDR outputs want.
+    #[serde(default)]
+    pub target_peak_nits: Option<f32>,
+    /// Pulls colors outside the output gamut back in before the curve.
+    #[serde(default)]
+    pub gamut_compression: bool,
+}
+
+impl ToneMapping {
+    pub fn new(operator: ToneMapOperator, source_peak_nits: f32) -> Self {
+        Self {
+            operator,
+            source_peak_nits,
+            target_peak_nits: None,
+            gamut_compression: false,
+        }
+    }
+}
+
+/// A color space: gamut plus transfer function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ColorSpace {
+    pub primaries: ColorPrimaries,
+    pub transfer: TransferFunction,
+}
+
+impl ColorSpace {
+    pub const SRGB: Self = Self::new(ColorPrimaries::Rec709, TransferFunction::Srgb);
+    pub const REC709: Self = Self::new(ColorPrimaries::Rec709, TransferFunction::Rec709);
+    pub const REC2020: Self = Self::new(ColorPrimaries::Rec2020, TransferFunction::Rec709);
+    pub const DISPLAY_P3: Self = Self::new(ColorPrimaries::DisplayP3, TransferFunction::Srgb);
+    pub const LINEAR_REC709: Self = Self::new(ColorPrimaries::Rec709, TransferFunction::Linear);
+    pub const LINEAR_REC2020: Self = Self::new(ColorPrimaries::Rec2020, TransferFunction::Linear);
+    pub const SLOG3: Self = Self::new(ColorPrimaries::SGamut3Cine, TransferFunction::SLog3);
+    pub const LOGC3: Self = Self::new(ColorPrimaries::ArriWideGamut3, TransferFunction::LogC3);
+    pub const REC2100_PQ: Self = Self::new(ColorPrimaries::Rec2020, TransferFunction::Pq);
+    pub const REC2100_HLG: Self = Self::new(ColorPrimaries::Rec2020, TransferFunction::Hlg);
+
+    pub const fn new(primaries: ColorPrimaries, transfer: TransferFunction) -> Self {
+        Self {
+            primaries,
+            transfer,
+        }
+    }
+
+    /// Same gamut, linear transfer.
+    pub fn linear(self) -> Self {
+        Self::new(self.primaries, TransferFunction::Linear)
+    }
+
+    /// Converts an encoded RGB triplet from `self` into `target` on the CPU.
+    /// Mirrors what the shaders do per pixel, except that HDR curves are taken
+    /// at their normalized signal range without reference white scaling.
+    pub fn convert_rgb(self, target: ColorSpace, rgb: [f32; 3]) -> [f32; 3] {
+        let linear = Vec3::from_array(rgb.map(|c| self.transfer.to_linear(c)));
+        let converted = self.primaries.conversion_to(target.primaries) * linear;
+        converted.to_array().map(|c| target.transfer.from_linear(c))
+    }
+}
+
+impl Default for ColorSpace {
+    fn default() -> Self {
+        Self::SRGB
+    }
+}
@@ -1,3 +1,4 @@
+use super::color::{ColorSpace, DEFAULT_REFERENCE_WHITE_NITS, InverseToneMapping};
 use super::layer::Layer;
 use serde::{Deserialize, Serialize};
 
@@ -6,7 +7,31 @@ use serde::{Deserialize, Serialize};
 pub struct FrameDescription {
     pub dimensions: (u32, u32),
     pub layers: Vec<Layer>,
+    /// Straight-alpha sRGB color, converted to the working space before clearing.
     pub background_color: [f32; 4],
+    /// Color space layers are blended in. Inputs are converted into it and
+    /// each sink converts out of it.
+    #[serde(default = "default_working_space")]
+    pub working_space: ColorSpace,
+    /// Luminance in nits that linear 1.0 (SDR white) maps to in HDR outputs.
+    #[serde(default = "default_reference_white")]
+    pub reference_white_nits: f32,
+    /// Optional highlight expansion for SDR sources in HDR deliverables.
+    /// When `None`, SDR white simply sits at reference white.
+    #[serde(default)]
+    pub inverse_tone_mapping: Option<InverseToneMapping>,
+    /// Presentation time in seconds. Frame providers use it to pick the
+    /// frame of each video layer.
+    #[serde(default)]
+    pub time: f64,
+}
+
+fn default_working_space() -> ColorSpace {
+    ColorSpace::LINEAR_REC709
+}
+
+fn default_reference_white() -> f32 {
+    DEFAULT_REFERENCE_WHITE_NITS
 }
 
 impl FrameDescription {
@@ -15,6 +40,10 @@ impl FrameDescription {
             dimensions: (width, height),
             layers: vec![],
             background_color: bg_color,
+            working_space: default_working_space(),
+            reference_white_nits: DEFAULT_REFERENCE_WHITE_NITS,
+            inverse_tone_mapping: None,
+            time: 0.0,
         }
     }
 }
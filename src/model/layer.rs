@@ -1,5 +1,5 @@
 use super::transform::LayerTransform;
-use super::types::BlendMode;
+use super::types::{BlendMode, ResamplingQuality};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -19,6 +19,8 @@ pub struct Layer {
     pub transform: LayerTransform,
     pub opacity: f32,
     pub blend_mode: BlendMode,
+    #[serde(default)]
+    pub resampling: ResamplingQuality,
     // Effect stack placeholder for now
     #[serde(default)]
     pub effect_stack: Vec<String>,
@@ -32,6 +34,7 @@ impl Layer {
             transform: LayerTransform::default(),
             opacity: 1.0,
             blend_mode: BlendMode::Normal,
+            resampling: ResamplingQuality::default(),
             effect_stack: vec![],
         }
     }
@@ -1,8 +1,10 @@
+pub mod color;
 pub mod composition;
 pub mod layer;
 pub mod transform;
 pub mod types;
 
+pub use color::*;
 pub use composition::*;
 pub use layer::*;
 pub use transform::*;
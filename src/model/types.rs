@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum BlendMode {
+    #[default]
     Normal,
     Multiply,
     Screen,
@@ -12,8 +13,18 @@ pub enum BlendMode {
     // Add more as needed
 }
 
-impl Default for BlendMode {
-    fn default() -> Self {
-        Self::Normal
-    }
+/// Filter used when a layer's texture is drawn at a different size.
+///
+/// The discriminants are shared with the `RESAMPLE_*` constants in `shader.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[repr(u32)]
+pub enum ResamplingQuality {
+    Nearest = 0,
+    /// Hardware bilinear, trilinear and anisotropic when the resource has mips.
+    #[default]
+    Bilinear = 1,
+    /// Catmull-Rom cubic.
+    Bicubic = 2,
+    /// Three-lobe Lanczos. Sharpest, and the most expensive.
+    Lanczos = 3,
 }
@@ -0,0 +1,478 @@
+use super::{BufferSink, RenderSink};
+use crate::core::{RenderContext, RenderError};
+use crate::model::{ColorSpace, ToneMapping};
+use crate::sources::LoopCount;
+use color_quant::NeuQuant;
+use std::io::Write;
+use wgpu::{TextureFormat, TextureView};
+
+/// Palette index GIF frames use for transparent and unchanged pixels.
+const TRANSPARENT: u8 = 255;
+
+/// File format written by an `AnimatedImageSink`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    /// GIF with one 255-color palette for the whole animation and 1-bit
+    /// alpha. Delays are rounded to centiseconds.
+    Gif,
+    /// Animated PNG, lossless 8-bit RGBA.
+    Apng,
+    /// Animated WebP, lossless.
+    WebP,
+}
+
+/// A run of identical frames, stored as the region that changed since the
+/// previous run.
+#[derive(Debug, Clone, Copy)]
+struct Delta {
+    /// First frame of the run.
+    index: usize,
+    frames: usize,
+    rect: Rect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Headless sink collecting frames into an animated GIF, APNG or animated
+/// WebP for short clips and previews.
+///
+/// Render into it and call `write_frame` for each frame. Frames are kept in
+/// memory until `finish` encodes the animation: a frame identical to the one
+/// before it extends that frame's delay, and the others are cropped to the
+/// region that changed. Nothing is written if the sink is dropped instead.
+pub struct AnimatedImageSink {
+    pub buffer: BufferSink,
+    pub format: AnimationFormat,
+    /// Frame rate the delays are derived from, as a fraction.
+    pub frame_rate: (u32, u32),
+    pub loop_count: LoopCount,
+    /// Floyd-Steinberg dithering against the GIF palette.
+    pub dither: bool,
+    output: Box<dyn Write + Send>,
+    frames: Vec<Vec<u8>>,
+}
+
+impl AnimatedImageSink {
+    /// Collects frames for `output`, looping forever, with GIF dithering on.
+    pub fn new(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        format: AnimationFormat,
+        frame_rate: (u32, u32),
+        output: impl Write + Send + 'static,
+    ) -> Result<Self, RenderError> {
+        if frame_rate.0 == 0 || frame_rate.1 == 0 {
+            return Err(RenderError::InvalidOutput(format!(
+                "bad frame rate {}:{}",
+                frame_rate.0, frame_rate.1
+            )));
+        }
+        let max_size = match format {
+            AnimationFormat::Gif => u16::MAX as u32,
+            AnimationFormat::Apng => i32::MAX as u32,
+            AnimationFormat::WebP => 16384,
+        };
+        if width == 0 || height == 0 || width > max_size || height > max_size {
+            return Err(RenderError::InvalidOutput(format!(
+                "{:?} can't store {}x{} frames",
+                format, width, height
+            )));
+        }
+        Ok(Self {
+            buffer: BufferSink::new(ctx, width, height),
+            format,
+            frame_rate,
+            loop_count: LoopCount::Infinite,
+            dither: true,
+            output: Box::new(output),
+            frames: Vec::new(),
+        })
+    }
+
+    pub fn with_loop_count(mut self, loop_count: LoopCount) -> Self {
+        self.loop_count = loop_count;
+        self
+    }
+
+    pub fn with_dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Frames collected so far.
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Reads back the last presented frame and adds it to the animation.
+    pub async fn write_frame(&mut self, ctx: &RenderContext) -> Result<(), RenderError> {
+        let pixels = self.buffer.read_pixels(ctx).await?;
+        self.frames.push(pixels);
+        Ok(())
+    }
+
+    /// Encodes the collected frames into the output. Returns the number of
+    /// frames stored in the file, after merging repeats.
+    pub fn finish(mut self) -> Result<usize, RenderError> {
+        if self.frames.is_empty() {
+            return Err(RenderError::InvalidOutput("no frames to encode".into()));
+        }
+        // GIF can't make a kept pixel transparent again, so animations with
+        // transparency redraw the whole canvas every frame
+        let crop = self.format != AnimationFormat::Gif
+            || self
+                .frames
+                .iter()
+                .flatten()
+                .skip(3)
+                .step_by(4)
+                .all(|&a| a >= 128);
+        let deltas = self.deltas(crop);
+        let mut output = std::mem::replace(&mut self.output, Box::new(std::io::sink()));
+        match self.format {
+            AnimationFormat::Gif => self.write_gif(&mut output, &deltas, crop)?,
+            AnimationFormat::Apng => self.write_apng(&mut output, &deltas)?,
+            AnimationFormat::WebP => self.write_webp(&mut output, &deltas)?,
+        }
+        output.flush()?;
+        Ok(deltas.len())
+    }
+
+    fn deltas(&self, crop: bool) -> Vec<Delta> {
+        let (width, height) = self.buffer.dimensions;
+        let full = Rect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        let mut deltas: Vec<Delta> = vec![];
+        for (index, frame) in self.frames.iter().enumerate() {
+            let changed = match deltas.last() {
+                Some(last) => changed_rect(&self.frames[last.index], frame, width),
+                None => Some(full),
+            };
+            match (changed, deltas.last_mut()) {
+                (None, Some(last)) => last.frames += 1,
+                (Some(rect), _) => deltas.push(Delta {
+                    index,
+                    frames: 1,
+                    rect: match crop {
+                        // WebP frame offsets are stored halved
+                        true if self.format == AnimationFormat::WebP => Rect {
+                            x: rect.x & !1,
+                            y: rect.y & !1,
+                            width: rect.width + (rect.x & 1),
+                            height: rect.height + (rect.y & 1),
+                        },
+                        true => rect,
+                        false => full,
+                    },
+                }),
+                (None, None) => unreachable!("the first frame is always a change"),
+            }
+        }
+        deltas
+    }
+
+    /// Length of a delta in `units` per second, rounded so that delays
+    /// don't drift from the frame rate over the animation.
+    fn duration(&self, delta: &Delta, units: u64) -> u64 {
+        let (num, den) = (self.frame_rate.0 as u64, self.frame_rate.1 as u64);
+        let time = |frame: usize| (frame as u64 * den * units * 2 + num) / (num * 2);
+        time(delta.index + delta.frames) - time(delta.index)
+    }
+
+    fn write_gif(
+        &self,
+        output: &mut impl Write,
+        deltas: &[Delta],
+        cropped: bool,
+    ) -> Result<(), RenderError> {
+        let (width, height) = self.buffer.dimensions;
+        let quant = self.palette();
+        let mut palette = quant.color_map_rgb();
+        palette.extend_from_slice(&[0, 0, 0]);
+
+        let failed = |e: gif::EncodingError| RenderError::EncodeFailed(e.to_string());
+        let mut encoder =
+            gif::Encoder::new(output, width as u16, height as u16, &palette).map_err(failed)?;
+        let repeat = match self.loop_count {
+            LoopCount::Infinite => Some(gif::Repeat::Infinite),
+            LoopCount::Finite(plays) if plays > 1 => {
+                Some(gif::Repeat::Finite((plays - 1).min(u16::MAX as u32) as u16))
+            }
+            // Without a loop extension GIFs play once
+            LoopCount::Finite(_) => None,
+        };
+        if let Some(repeat) = repeat {
+            encoder.set_repeat(repeat).map_err(failed)?;
+        }
+
+        for (i, delta) in deltas.iter().enumerate() {
+            let previous = match (cropped, i) {
+                (true, 1..) => Some(self.frames[deltas[i - 1].index].as_slice()),
+                _ => None,
+            };
+            let rect = delta.rect;
+            let indices = quantize(
+                &quant,
+                &palette,
+                &self.frames[delta.index],
+                previous,
+                width,
+                rect,
+                self.dither,
+            );
+            let frame = gif::Frame {
+                delay: self.duration(delta, 100).min(u16::MAX as u64) as u16,
+                dispose: match cropped {
+                    true => gif::DisposalMethod::Keep,
+                    false => gif::DisposalMethod::Background,
+                },
+                transparent: Some(TRANSPARENT),
+                left: rect.x as u16,
+                top: rect.y as u16,
+                width: rect.width as u16,
+                height: rect.height as u16,
+                buffer: indices.into(),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame).map_err(failed)?;
+        }
+        encoder.into_inner().map_err(failed)?;
+        Ok(())
+    }
+
+    /// Trains a 255-color palette on opaque pixels sampled from every frame,
+    /// so colors don't shift between frames.
+    fn palette(&self) -> NeuQuant {
+        const MAX_SAMPLES: usize = 1 << 19;
+        let (width, height) = self.buffer.dimensions;
+        let total = self.frames.len() * (width * height) as usize;
+        let mut samples: Vec<u8> = self
+            .frames
+            .iter()
+            .flat_map(|frame| frame.chunks_exact(4))
+            .step_by(total.div_ceil(MAX_SAMPLES))
+            .filter(|pixel| pixel[3] >= 128)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+            .collect();
+        if samples.is_empty() {
+            samples.extend_from_slice(&[0, 0, 0, 255]);
+        }
+        NeuQuant::new(10, TRANSPARENT as usize, &samples)
+    }
+
+    fn write_apng(&self, output: &mut impl Write, deltas: &[Delta]) -> Result<(), RenderError> {
+        let (width, height) = self.buffer.dimensions;
+        let failed = |e: png::EncodingError| RenderError::EncodeFailed(e.to_string());
+        let mut encoder = png::Encoder::new(output, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let plays = match self.loop_count {
+            LoopCount::Infinite => 0,
+            LoopCount::Finite(plays) => plays.max(1),
+        };
+        encoder
+            .set_animated(deltas.len() as u32, plays)
+            .map_err(failed)?;
+        let mut writer = encoder.write_header().map_err(failed)?;
+
+        for delta in deltas {
+            let rect = delta.rect;
+            let millis = self.duration(delta, 1000).min(u16::MAX as u64) as u16;
+            writer.set_frame_delay(millis, 1000).map_err(failed)?;
+            // Move to the origin first so the new size always fits
+            writer.set_frame_position(0, 0).map_err(failed)?;
+            writer
+                .set_frame_dimension(rect.width, rect.height)
+                .map_err(failed)?;
+            writer.set_frame_position(rect.x, rect.y).map_err(failed)?;
+            writer.set_blend_op(png::BlendOp::Source).map_err(failed)?;
+            writer
+                .set_dispose_op(png::DisposeOp::None)
+                .map_err(failed)?;
+            writer
+                .write_image_data(&crop(&self.frames[delta.index], width, rect))
+                .map_err(failed)?;
+        }
+        writer.finish().map_err(failed)
+    }
+
+    fn write_webp(&self, output: &mut impl Write, deltas: &[Delta]) -> Result<(), RenderError> {
+        let (width, height) = self.buffer.dimensions;
+        let failed = |e: image_webp::EncodingError| RenderError::EncodeFailed(e.to_string());
+        let mut chunks = Vec::new();
+
+        // Canvas with the animation and alpha flags
+        let mut vp8x = vec![0x12, 0, 0, 0];
+        vp8x.extend_from_slice(&u24(width - 1));
+        vp8x.extend_from_slice(&u24(height - 1));
+        riff_chunk(&mut chunks, b"VP8X", &vp8x);
+
+        // Transparent background, then plays with 0 meaning forever
+        let plays = match self.loop_count {
+            LoopCount::Infinite => 0,
+            LoopCount::Finite(plays) => plays.clamp(1, u16::MAX as u32) as u16,
+        };
+        let mut anim = vec![0; 4];
+        anim.extend_from_slice(&plays.to_le_bytes());
+        riff_chunk(&mut chunks, b"ANIM", &anim);
+
+        for delta in deltas {
+            let rect = delta.rect;
+            let mut still = Vec::new();
+            image_webp::WebPEncoder::new(&mut still)
+                .encode(
+                    &crop(&self.frames[delta.index], width, rect),
+                    rect.width,
+                    rect.height,
+                    image_webp::ColorType::Rgba8,
+                )
+                .map_err(failed)?;
+
+            let mut anmf = Vec::with_capacity(16 + still.len());
+            anmf.extend_from_slice(&u24(rect.x / 2));
+            anmf.extend_from_slice(&u24(rect.y / 2));
+            anmf.extend_from_slice(&u24(rect.width - 1));
+            anmf.extend_from_slice(&u24(rect.height - 1));
+            anmf.extend_from_slice(&u24(self.duration(delta, 1000).min(0xff_ffff) as u32));
+            // Replace the region instead of blending, keep it afterwards
+            anmf.push(0b10);
+            // A simple-format still is the RIFF header followed by its VP8L chunk
+            anmf.extend_from_slice(&still[12..]);
+            riff_chunk(&mut chunks, b"ANMF", &anmf);
+        }
+
+        output.write_all(b"RIFF")?;
+        output.write_all(&(4 + chunks.len() as u32).to_le_bytes())?;
+        output.write_all(b"WEBP")?;
+        output.write_all(&chunks)?;
+        Ok(())
+    }
+}
+
+impl RenderSink for AnimatedImageSink {
+    fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
+        self.buffer.prepare_frame()
+    }
+
+    fn present(&mut self, ctx: &RenderContext) {
+        self.buffer.present(ctx)
+    }
+
+    fn format(&self) -> TextureFormat {
+        self.buffer.format()
+    }
+
+    fn output_color_space(&self) -> ColorSpace {
+        self.buffer.output_color_space()
+    }
+
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        self.buffer.tone_mapping()
+    }
+}
+
+/// Bounding box of the pixels that differ between two frames.
+fn changed_rect(a: &[u8], b: &[u8], width: u32) -> Option<Rect> {
+    let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+    for (i, (pa, pb)) in a.chunks_exact(4).zip(b.chunks_exact(4)).enumerate() {
+        if pa != pb {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+    }
+    (x0 != u32::MAX).then(|| Rect {
+        x: x0,
+        y: y0,
+        width: x1 - x0 + 1,
+        height: y1 - y0 + 1,
+    })
+}
+
+fn crop(frame: &[u8], width: u32, rect: Rect) -> Vec<u8> {
+    let row_bytes = rect.width as usize * 4;
+    let mut out = Vec::with_capacity(row_bytes * rect.height as usize);
+    for y in rect.y..rect.y + rect.height {
+        let start = (y * width + rect.x) as usize * 4;
+        out.extend_from_slice(&frame[start..start + row_bytes]);
+    }
+    out
+}
+
+/// Maps a region of `frame` to palette indices. Transparent pixels, and
+/// pixels unchanged from `previous`, use the transparent index so the
+/// pixel already on screen shows through.
+fn quantize(
+    quant: &NeuQuant,
+    palette: &[u8],
+    frame: &[u8],
+    previous: Option<&[u8]>,
+    width: u32,
+    rect: Rect,
+    dither: bool,
+) -> Vec<u8> {
+    let w = rect.width as usize;
+    let mut indices = Vec::with_capacity(w * rect.height as usize);
+    // Error carried into this row and the next, with a pixel of margin
+    let mut errors = vec![[0.0f32; 3]; w + 2];
+    let mut next_errors = vec![[0.0f32; 3]; w + 2];
+
+    for y in rect.y..rect.y + rect.height {
+        for x in 0..w {
+            let offset = (y * width + rect.x) as usize * 4 + x * 4;
+            let pixel = &frame[offset..offset + 4];
+            let unchanged = previous.is_some_and(|p| &p[offset..offset + 4] == pixel);
+            if pixel[3] < 128 || unchanged {
+                indices.push(TRANSPARENT);
+                continue;
+            }
+            let carried = errors[x + 1];
+            let color: [f32; 3] =
+                std::array::from_fn(|c| (pixel[c] as f32 + carried[c]).clamp(0.0, 255.0));
+            let rgba = [color[0] as u8, color[1] as u8, color[2] as u8, 255];
+            let index = quant.index_of(&rgba);
+            indices.push(index as u8);
+
+            if dither {
+                let chosen = &palette[index * 3..index * 3 + 3];
+                for c in 0..3 {
+                    let error = color[c] - chosen[c] as f32;
+                    errors[x + 2][c] += error * 7.0 / 16.0;
+                    next_errors[x][c] += error * 3.0 / 16.0;
+                    next_errors[x + 1][c] += error * 5.0 / 16.0;
+                    next_errors[x + 2][c] += error / 16.0;
+                }
+            }
+        }
+        std::mem::swap(&mut errors, &mut next_errors);
+        next_errors.fill([0.0; 3]);
+    }
+    indices
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let [a, b, c, _] = value.to_le_bytes();
+    [a, b, c]
+}
+
+fn riff_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
@@ -0,0 +1,319 @@
+use super::mux::{Av1Packet, IvfWriter, Mp4Writer, Muxer};
+use super::{RenderSink, YuvFrame, YuvSink};
+use crate::core::{RenderContext, RenderError};
+use crate::model::{ColorPrimaries, ColorSpace, ToneMapping, TransferFunction};
+use crate::resources::yuv::{ChromaSiting, YuvConversion, YuvFormat, YuvMatrix, YuvRange};
+use rav1e::prelude::{
+    ChromaSamplePosition, ChromaSampling, ColorDescription, Config, Context, EncoderConfig,
+    EncoderStatus, FrameType, Pixel, PixelRange, Rational, SpeedSettings,
+};
+use std::io::Write;
+use std::thread::JoinHandle;
+use tokio::sync::mpsc::{Receiver, Sender};
+use wgpu::{TextureFormat, TextureView};
+
+/// File format the encoded stream is muxed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoContainer {
+    /// IVF, the bare AV1 test container. Widely readable, no metadata.
+    Ivf,
+    /// Fragmented MP4, one fragment per keyframe interval. Plays in
+    /// browsers and can be written to a pipe.
+    Mp4,
+}
+
+/// Encoder settings for `Av1Sink`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Av1Settings {
+    /// rav1e speed preset, from 0 (slowest, smallest) to 10 (fastest).
+    pub speed: u8,
+    /// Constant quantizer from 0 (best) to 255 (smallest).
+    pub quantizer: u8,
+    /// Most frames between keyframes.
+    pub keyframe_interval: u64,
+    /// Encode 10-bit instead of 8-bit 4:2:0.
+    pub ten_bit: bool,
+    /// Encoder threads; 0 uses one per core.
+    pub threads: usize,
+    pub conversion: YuvConversion,
+}
+
+impl Default for Av1Settings {
+    fn default() -> Self {
+        Self {
+            speed: 6,
+            quantizer: 100,
+            keyframe_interval: 240,
+            ten_bit: false,
+            threads: 0,
+            conversion: YuvConversion::new(YuvMatrix::Bt709, YuvRange::Limited),
+        }
+    }
+}
+
+enum Encoder {
+    Eight(Context<u8>),
+    Ten(Context<u16>),
+}
+
+/// Headless sink encoding frames to AV1 with rav1e and muxing them into
+/// IVF or fragmented MP4, without any external tools.
+///
+/// Frames are converted to 4:2:0 on the GPU like `YuvSink`, then encoded on
+/// a background thread. Render into the sink, call `write_frame` for each
+/// frame, and `finish` to flush the encoder and close the file.
+pub struct Av1Sink {
+    yuv: YuvSink,
+    sender: Option<Sender<YuvFrame>>,
+    encoder: Option<JoinHandle<Result<u64, RenderError>>>,
+    frames: u64,
+}
+
+impl Av1Sink {
+    pub fn new(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        frame_rate: (u32, u32),
+        container: VideoContainer,
+        settings: Av1Settings,
+        output: impl Write + Send + 'static,
+    ) -> Result<Self, RenderError> {
+        if frame_rate.0 == 0 || frame_rate.1 == 0 {
+            return Err(RenderError::InvalidOutput(format!(
+                "bad frame rate {}:{}",
+                frame_rate.0, frame_rate.1
+            )));
+        }
+        if settings.keyframe_interval == 0 {
+            return Err(RenderError::InvalidOutput(
+                "keyframe interval must be at least 1".into(),
+            ));
+        }
+        let format = match settings.ten_bit {
+            true => YuvFormat::I420P10,
+            false => YuvFormat::I420,
+        };
+        let yuv = YuvSink::new(ctx, width, height, format, settings.conversion);
+
+        let config = encoder_config(width, height, frame_rate, &settings, yuv.color_space);
+        let config = Config::new()
+            .with_encoder_config(config)
+            .with_threads(settings.threads);
+        let invalid = |e: rav1e::InvalidConfig| RenderError::InvalidOutput(e.to_string());
+        let encoder = match settings.ten_bit {
+            true => Encoder::Ten(config.new_context().map_err(invalid)?),
+            false => Encoder::Eight(config.new_context().map_err(invalid)?),
+        };
+        let muxer = match container {
+            VideoContainer::Ivf => Muxer::Ivf(IvfWriter::new(output, width, height, frame_rate)?),
+            VideoContainer::Mp4 => {
+                let av1c = match &encoder {
+                    Encoder::Eight(context) => context.container_sequence_header(),
+                    Encoder::Ten(context) => context.container_sequence_header(),
+                };
+                Muxer::Mp4(Mp4Writer::new(output, width, height, frame_rate, av1c))
+            }
+        };
+
+        // rav1e keeps its own lookahead, so a short queue is enough
+        let (sender, receiver) = tokio::sync::mpsc::channel(2);
+        let encoder = std::thread::Builder::new()
+            .name("av1-encoder".into())
+            .spawn(move || encode_stream(encoder, muxer, receiver))?;
+
+        Ok(Self {
+            yuv,
+            sender: Some(sender),
+            encoder: Some(encoder),
+            frames: 0,
+        })
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.yuv.dimensions
+    }
+
+    /// Reads back the last presented frame and queues it for encoding.
+    /// Waits while the encoder is behind, yielding to the async runtime.
+    pub async fn write_frame(&mut self, ctx: &RenderContext) -> Result<(), RenderError> {
+        let frame = self.yuv.read_planes(ctx).await?;
+        let sent = match &self.sender {
+            Some(sender) => sender.send(frame).await.is_ok(),
+            None => false,
+        };
+        if !sent {
+            // The encoder thread stopped on an error; report it
+            self.close()?;
+            return Err(RenderError::EncodeFailed("encoder stopped".into()));
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Encodes the remaining frames and closes the output. Returns the
+    /// number of packets written.
+    pub fn finish(mut self) -> Result<u64, RenderError> {
+        self.close()
+    }
+
+    fn close(&mut self) -> Result<u64, RenderError> {
+        self.sender = None;
+        match self.encoder.take() {
+            Some(encoder) => encoder
+                .join()
+                .unwrap_or_else(|_| Err(RenderError::EncodeFailed("encoder panicked".into()))),
+            None => Ok(0),
+        }
+    }
+}
+
+impl Drop for Av1Sink {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+impl RenderSink for Av1Sink {
+    fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
+        self.yuv.prepare_frame()
+    }
+
+    fn present(&mut self, ctx: &RenderContext) {
+        self.yuv.present(ctx)
+    }
+
+    fn format(&self) -> TextureFormat {
+        self.yuv.format()
+    }
+
+    fn output_color_space(&self) -> ColorSpace {
+        self.yuv.output_color_space()
+    }
+
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        self.yuv.tone_mapping()
+    }
+}
+
+fn encoder_config(
+    width: u32,
+    height: u32,
+    frame_rate: (u32, u32),
+    settings: &Av1Settings,
+    color_space: ColorSpace,
+) -> EncoderConfig {
+    let conversion = settings.conversion;
+    let color_primaries = match color_space.primaries {
+        ColorPrimaries::Rec709 => rav1e::color::ColorPrimaries::BT709,
+        ColorPrimaries::Rec2020 => rav1e::color::ColorPrimaries::BT2020,
+        ColorPrimaries::DisplayP3 => rav1e::color::ColorPrimaries::SMPTE432,
+        _ => rav1e::color::ColorPrimaries::Unspecified,
+    };
+    let transfer_characteristics = match color_space.transfer {
+        TransferFunction::Linear => rav1e::color::TransferCharacteristics::Linear,
+        TransferFunction::Srgb => rav1e::color::TransferCharacteristics::SRGB,
+        TransferFunction::Rec709 => rav1e::color::TransferCharacteristics::BT709,
+        TransferFunction::Pq => rav1e::color::TransferCharacteristics::SMPTE2084,
+        TransferFunction::Hlg => rav1e::color::TransferCharacteristics::HLG,
+        _ => rav1e::color::TransferCharacteristics::Unspecified,
+    };
+    let matrix_coefficients = match conversion.matrix {
+        YuvMatrix::Bt601 => rav1e::color::MatrixCoefficients::BT601,
+        YuvMatrix::Bt709 => rav1e::color::MatrixCoefficients::BT709,
+        YuvMatrix::Bt2020 => rav1e::color::MatrixCoefficients::BT2020NCL,
+    };
+
+    EncoderConfig {
+        width: width as usize,
+        height: height as usize,
+        time_base: Rational::new(frame_rate.1 as u64, frame_rate.0 as u64),
+        bit_depth: if settings.ten_bit { 10 } else { 8 },
+        chroma_sampling: ChromaSampling::Cs420,
+        chroma_sample_position: match conversion.siting {
+            ChromaSiting::Left => ChromaSamplePosition::Vertical,
+            ChromaSiting::TopLeft => ChromaSamplePosition::Colocated,
+            ChromaSiting::Center => ChromaSamplePosition::Unknown,
+        },
+        pixel_range: match conversion.range {
+            YuvRange::Limited => PixelRange::Limited,
+            YuvRange::Full => PixelRange::Full,
+        },
+        color_description: Some(ColorDescription {
+            color_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+        }),
+        min_key_frame_interval: settings.keyframe_interval.min(12),
+        max_key_frame_interval: settings.keyframe_interval,
+        quantizer: settings.quantizer as usize,
+        speed_settings: SpeedSettings::from_preset(settings.speed),
+        ..Default::default()
+    }
+}
+
+fn encode_stream(
+    mut encoder: Encoder,
+    mut muxer: Muxer<impl Write>,
+    mut frames: Receiver<YuvFrame>,
+) -> Result<u64, RenderError> {
+    let mut packets = 0;
+    let mut drain = |encoder: &mut Encoder| -> Result<(), RenderError> {
+        while let Some(packet) = match encoder {
+            Encoder::Eight(context) => receive_packet(context)?,
+            Encoder::Ten(context) => receive_packet(context)?,
+        } {
+            muxer.write_packet(packet)?;
+            packets += 1;
+        }
+        Ok(())
+    };
+
+    while let Some(frame) = frames.blocking_recv() {
+        match &mut encoder {
+            Encoder::Eight(context) => send_frame(context, &frame, 1)?,
+            Encoder::Ten(context) => send_frame(context, &frame, 2)?,
+        }
+        drain(&mut encoder)?;
+    }
+    match &mut encoder {
+        Encoder::Eight(context) => context.flush(),
+        Encoder::Ten(context) => context.flush(),
+    }
+    drain(&mut encoder)?;
+    muxer.finish()?;
+    Ok(packets)
+}
+
+fn send_frame<T: Pixel>(
+    context: &mut Context<T>,
+    frame: &YuvFrame,
+    bytes_per_sample: usize,
+) -> Result<(), RenderError> {
+    let mut input = context.new_frame();
+    for (index, (plane, data)) in input.planes.iter_mut().zip(&frame.planes).enumerate() {
+        plane.copy_from_raw_u8(data, frame.stride(index) as usize, bytes_per_sample);
+    }
+    context
+        .send_frame(input)
+        .map_err(|e| RenderError::EncodeFailed(e.to_string()))
+}
+
+/// Next finished packet, or `None` once the encoder needs more input or
+/// is done.
+fn receive_packet<T: Pixel>(context: &mut Context<T>) -> Result<Option<Av1Packet>, RenderError> {
+    loop {
+        match context.receive_packet() {
+            Ok(packet) => {
+                return Ok(Some(Av1Packet {
+                    keyframe: packet.frame_type == FrameType::KEY,
+                    pts: packet.input_frameno,
+                    data: packet.data,
+                }));
+            }
+            Err(EncoderStatus::Encoded) => {}
+            Err(EncoderStatus::NeedMoreData | EncoderStatus::LimitReached) => return Ok(None),
+            Err(e) => return Err(RenderError::EncodeFailed(e.to_string())),
+        }
+    }
+}
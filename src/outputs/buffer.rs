@@ -1,18 +1,44 @@
 use super::RenderSink;
 use crate::core::{RenderContext, RenderError};
+use crate::model::{ColorSpace, ToneMapping};
+use image::RgbaImage;
 use wgpu::{
-    Buffer, BufferDescriptor, BufferUsages, Extent3d, MapMode, Texture, TextureDescriptor,
-    TextureDimension, TextureFormat, TextureUsages, TextureView,
+    Buffer, BufferDescriptor, BufferUsages, BufferView, Extent3d, MapMode, Texture,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
 };
 
 pub struct BufferSink {
     pub texture: Texture,
     pub output_buffer: Buffer,
     pub dimensions: (u32, u32),
+    /// Color space the read back pixels are encoded in.
+    pub color_space: ColorSpace,
+    /// Applied when HDR or float content is written to this sink.
+    pub tone_mapping: Option<ToneMapping>,
 }
 
 impl BufferSink {
     pub fn new(ctx: &RenderContext, width: u32, height: u32) -> Self {
+        Self::with_format(
+            ctx,
+            width,
+            height,
+            TextureFormat::Rgba8Unorm, // Standard format
+            ColorSpace::SRGB,
+        )
+    }
+
+    /// Creates a sink rendering to an arbitrary copyable color format, e.g.
+    /// `Rgb10a2Unorm` or `Rgba16Float` with `ColorSpace::REC2100_PQ` for HDR
+    /// deliverables. `Rgba16Unorm` needs `TEXTURE_FORMAT_16BIT_NORM`, which
+    /// `RenderContext` enables when the adapter offers it.
+    pub fn with_format(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        color_space: ColorSpace,
+    ) -> Self {
         let texture_desc = TextureDescriptor {
             label: Some("BufferSink Texture"),
             size: Extent3d {
@@ -23,19 +49,17 @@ impl BufferSink {
             mip_level_count: 1,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8Unorm, // Standard format
+            format,
             usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
             view_formats: &[],
         };
         let texture = ctx.device.create_texture(&texture_desc);
 
         // Calculate buffer size with padding for alignment
-        let bytes_per_pixel = 4; // Rgba8
-        let unpadded_bytes_per_row = width * bytes_per_pixel;
-        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-        let buffer_size = (padded_bytes_per_row * height) as u64;
+        let bytes_per_pixel = format
+            .block_copy_size(None)
+            .expect("BufferSink format must be a single-aspect color format");
+        let buffer_size = (padded_bytes_per_row(width, bytes_per_pixel) * height) as u64;
 
         let output_buffer = ctx.device.create_buffer(&BufferDescriptor {
             label: Some("Output Buffer"),
@@ -48,11 +72,27 @@ impl BufferSink {
             texture,
             output_buffer,
             dimensions: (width, height),
+            color_space,
+            tone_mapping: None,
         }
     }
 
-    /// Read the buffer content back to CPU. This is async.
-    pub async fn read_pixels(&self, ctx: &RenderContext) -> Result<Vec<u8>, RenderError> {
+    /// Bytes per pixel of the sink format.
+    pub fn bytes_per_pixel(&self) -> u32 {
+        self.texture
+            .format()
+            .block_copy_size(None)
+            .expect("BufferSink format must be a single-aspect color format")
+    }
+
+    /// Row pitch of the read back data, padded to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+    pub fn padded_bytes_per_row(&self) -> u32 {
+        padded_bytes_per_row(self.dimensions.0, self.bytes_per_pixel())
+    }
+
+    /// Maps the last presented frame for reading. The buffer stays mapped,
+    /// and can't be rendered into, until the returned rows are dropped.
+    pub async fn read_rows(&self, ctx: &RenderContext) -> Result<MappedRows<'_>, RenderError> {
         let slice = self.output_buffer.slice(..);
         let (tx, rx) = tokio::sync::oneshot::channel();
 
@@ -62,30 +102,53 @@ impl BufferSink {
 
         ctx.instance.poll_all(true);
 
-        // await the channel, then check the result of mapping
-        match rx.await {
-            Ok(Ok(())) => {}
-            Ok(Err(e)) => panic!("Map failed: {:?}", e),
-            Err(_) => panic!("Channel closed"),
-        }
+        rx.await.map_err(|_| RenderError::ReadbackAborted)??;
 
-        let data = slice.get_mapped_range();
-        let result = data.to_vec();
+        Ok(MappedRows {
+            sink: self,
+            view: Some(slice.get_mapped_range()),
+        })
+    }
 
-        drop(data);
-        self.output_buffer.unmap();
+    /// Reads the last presented frame back to CPU, tightly packed in the
+    /// sink format.
+    pub async fn read_pixels(&self, ctx: &RenderContext) -> Result<Vec<u8>, RenderError> {
+        let mapped = self.read_rows(ctx).await?;
+        let (width, height) = self.dimensions;
+        let mut pixels = Vec::with_capacity((width * height * self.bytes_per_pixel()) as usize);
+        for row in mapped.rows() {
+            pixels.extend_from_slice(row);
+        }
+        Ok(pixels)
+    }
 
-        Ok(result)
+    /// Reads the last presented frame back as an image. Only 8-bit RGBA and
+    /// BGRA sinks can be read this way; BGRA is swizzled to RGBA.
+    pub async fn read_image(&self, ctx: &RenderContext) -> Result<RgbaImage, RenderError> {
+        let bgra = match self.texture.format() {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            format => {
+                return Err(RenderError::InvalidOutput(format!(
+                    "{:?} can't be read as an RGBA8 image",
+                    format
+                )));
+            }
+        };
+        let mut pixels = self.read_pixels(ctx).await?;
+        if bgra {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        let (width, height) = self.dimensions;
+        Ok(RgbaImage::from_raw(width, height, pixels).expect("pixels cover the sink"))
     }
 
     // Helper to encode copy
     pub fn copy_to_buffer(&self, encoder: &mut wgpu::CommandEncoder) {
         let (width, height) = self.dimensions;
-        let bytes_per_pixel = 4;
-        let unpadded_bytes_per_row = width * bytes_per_pixel;
-        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+        let padded_bytes_per_row = self.padded_bytes_per_row();
 
         encoder.copy_texture_to_buffer(
             wgpu::TexelCopyTextureInfo {
@@ -111,6 +174,39 @@ impl BufferSink {
     }
 }
 
+/// A mapped `BufferSink` readback. Unmaps the buffer when dropped.
+pub struct MappedRows<'a> {
+    sink: &'a BufferSink,
+    view: Option<BufferView>,
+}
+
+impl MappedRows<'_> {
+    /// Rows from top to bottom, without the copy alignment padding.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &[u8]> + '_ {
+        let (width, height) = self.sink.dimensions;
+        let row_bytes = (width * self.sink.bytes_per_pixel()) as usize;
+        let view = self.view.as_deref().expect("mapped until dropped");
+        view.chunks(self.sink.padded_bytes_per_row() as usize)
+            .take(height as usize)
+            .map(move |row| &row[..row_bytes])
+    }
+}
+
+impl Drop for MappedRows<'_> {
+    fn drop(&mut self) {
+        // Views must be released before the buffer can be unmapped
+        self.view = None;
+        self.sink.output_buffer.unmap();
+    }
+}
+
+pub(super) fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let unpadded_bytes_per_row = width * bytes_per_pixel;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padding = (align - unpadded_bytes_per_row % align) % align;
+    unpadded_bytes_per_row + padding
+}
+
 impl RenderSink for BufferSink {
     fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
         Ok(self
@@ -130,4 +226,16 @@ impl RenderSink for BufferSink {
 
         ctx.queue.submit(std::iter::once(encoder.finish()));
     }
+
+    fn format(&self) -> TextureFormat {
+        self.texture.format()
+    }
+
+    fn output_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        self.tone_mapping
+    }
 }
@@ -1,13 +1,42 @@
 use crate::core::{RenderContext, RenderError};
-use wgpu::TextureView;
+use crate::model::{ColorSpace, ToneMapping};
+use wgpu::{TextureFormat, TextureView};
 
 pub trait RenderSink {
     fn prepare_frame(&mut self) -> Result<TextureView, RenderError>;
     fn present(&mut self, ctx: &RenderContext);
+    /// Releases a frame that was prepared but won't be presented, because
+    /// another sink failed to prepare its own.
+    fn discard_frame(&mut self) {}
+    /// Format of the views returned by `prepare_frame`.
+    fn format(&self) -> TextureFormat;
+    /// Color space the output transform encodes into for this sink.
+    fn output_color_space(&self) -> ColorSpace {
+        ColorSpace::SRGB
+    }
+    /// Tone mapping applied in the output transform, before encoding.
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        None
+    }
 }
 
+pub mod animated;
+pub mod av1;
 pub mod buffer;
+mod mux;
+pub mod pipe;
+pub mod readback;
+pub mod sequence;
 pub mod surface;
+pub mod texture;
+pub mod yuv;
 
-pub use buffer::BufferSink;
+pub use animated::{AnimatedImageSink, AnimationFormat};
+pub use av1::{Av1Settings, Av1Sink, VideoContainer};
+pub use buffer::{BufferSink, MappedRows};
+pub use pipe::{PipeFormat, PipeSink};
+pub use readback::{ReadbackFrame, ReadbackSink, ReadbackTicket};
+pub use sequence::{ImageFileFormat, ImageSequenceSink, OverwritePolicy};
 pub use surface::SurfaceSink;
+pub use texture::TextureSink;
+pub use yuv::{YuvFrame, YuvSink};
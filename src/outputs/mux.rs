@@ -0,0 +1,340 @@
+//! Minimal IVF and fragmented MP4 writers for AV1 packets.
+
+use std::io::Write;
+
+/// One encoded temporal unit, in presentation order.
+pub(crate) struct Av1Packet {
+    pub data: Vec<u8>,
+    pub keyframe: bool,
+    /// Frame number; timestamps are `pts * frame_rate.1 / frame_rate.0`.
+    pub pts: u64,
+}
+
+pub(crate) enum Muxer<W: Write> {
+    Ivf(IvfWriter<W>),
+    Mp4(Mp4Writer<W>),
+}
+
+impl<W: Write> Muxer<W> {
+    pub fn write_packet(&mut self, packet: Av1Packet) -> std::io::Result<()> {
+        match self {
+            Self::Ivf(writer) => writer.write_packet(packet),
+            Self::Mp4(writer) => writer.write_packet(packet),
+        }
+    }
+
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Ivf(writer) => writer.out.flush(),
+            Self::Mp4(writer) => writer.finish(),
+        }
+    }
+}
+
+pub(crate) struct IvfWriter<W> {
+    out: W,
+}
+
+impl<W: Write> IvfWriter<W> {
+    /// Writes the file header. The frame count field is left at 0 since
+    /// the output may not be seekable; readers don't rely on it.
+    pub fn new(
+        mut out: W,
+        width: u32,
+        height: u32,
+        frame_rate: (u32, u32),
+    ) -> std::io::Result<Self> {
+        let mut header = Vec::with_capacity(32);
+        header.extend_from_slice(b"DKIF");
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&32u16.to_le_bytes());
+        header.extend_from_slice(b"AV01");
+        header.extend_from_slice(&(width as u16).to_le_bytes());
+        header.extend_from_slice(&(height as u16).to_le_bytes());
+        // Time base is the frame duration, so timestamps are frame numbers
+        header.extend_from_slice(&frame_rate.0.to_le_bytes());
+        header.extend_from_slice(&frame_rate.1.to_le_bytes());
+        header.extend_from_slice(&[0; 8]);
+        out.write_all(&header)?;
+        Ok(Self { out })
+    }
+
+    fn write_packet(&mut self, packet: Av1Packet) -> std::io::Result<()> {
+        self.out
+            .write_all(&(packet.data.len() as u32).to_le_bytes())?;
+        self.out.write_all(&packet.pts.to_le_bytes())?;
+        self.out.write_all(&packet.data)
+    }
+}
+
+/// Fragmented MP4 with one AV1 track and one fragment per GOP, so nothing
+/// has to be patched after the fact and the output can be a pipe.
+pub(crate) struct Mp4Writer<W> {
+    out: W,
+    width: u32,
+    height: u32,
+    frame_rate: (u32, u32),
+    // First four bytes of the av1C box, from the encoder
+    config: Vec<u8>,
+    header_written: bool,
+    sequence: u32,
+    gop: Vec<Av1Packet>,
+}
+
+impl<W: Write> Mp4Writer<W> {
+    pub fn new(out: W, width: u32, height: u32, frame_rate: (u32, u32), config: Vec<u8>) -> Self {
+        Self {
+            out,
+            width,
+            height,
+            frame_rate,
+            config,
+            header_written: false,
+            sequence: 0,
+            gop: vec![],
+        }
+    }
+
+    fn write_packet(&mut self, mut packet: Av1Packet) -> std::io::Result<()> {
+        if !self.header_written {
+            // av1C carries the sequence header from the first keyframe
+            let sequence_header = obus(&packet.data)
+                .find(|(kind, _)| *kind == OBU_SEQUENCE_HEADER)
+                .map_or(&[][..], |(_, obu)| obu)
+                .to_vec();
+            self.write_header(&sequence_header)?;
+        }
+        if packet.keyframe && !self.gop.is_empty() {
+            self.write_fragment()?;
+        }
+        // Temporal delimiters are implied by MP4 samples
+        packet.data = obus(&packet.data)
+            .filter(|(kind, _)| *kind != OBU_TEMPORAL_DELIMITER)
+            .flat_map(|(_, obu)| obu)
+            .copied()
+            .collect();
+        self.gop.push(packet);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        if !self.header_written {
+            self.write_header(&[])?;
+        }
+        if !self.gop.is_empty() {
+            self.write_fragment()?;
+        }
+        self.out.flush()
+    }
+
+    fn write_header(&mut self, sequence_header: &[u8]) -> std::io::Result<()> {
+        let (width, height) = (self.width, self.height);
+        let timescale = self.frame_rate.0;
+        let matrix: Vec<u8> = [0x10000u32, 0, 0, 0, 0x10000, 0, 0, 0, 0x4000_0000]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+
+        let ftyp = mp4_box(
+            b"ftyp",
+            &[b"iso6", &0u32.to_be_bytes()[..], b"iso6", b"av01", b"mp41"].concat(),
+        );
+
+        let mut mvhd = vec![0; 8];
+        mvhd.extend_from_slice(&timescale.to_be_bytes());
+        mvhd.extend_from_slice(&0u32.to_be_bytes());
+        mvhd.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        mvhd.extend_from_slice(&0x0100u16.to_be_bytes());
+        mvhd.extend_from_slice(&[0; 10]);
+        mvhd.extend_from_slice(&matrix);
+        mvhd.extend_from_slice(&[0; 24]);
+        mvhd.extend_from_slice(&2u32.to_be_bytes());
+        let mvhd = full_box(b"mvhd", 0, 0, &mvhd);
+
+        let mut tkhd = vec![0; 8];
+        tkhd.extend_from_slice(&1u32.to_be_bytes());
+        tkhd.extend_from_slice(&[0; 4 + 4 + 8 + 2 + 2 + 2 + 2]);
+        tkhd.extend_from_slice(&matrix);
+        tkhd.extend_from_slice(&(width << 16).to_be_bytes());
+        tkhd.extend_from_slice(&(height << 16).to_be_bytes());
+        // Enabled and in the movie
+        let tkhd = full_box(b"tkhd", 0, 3, &tkhd);
+
+        let mut mdhd = vec![0; 8];
+        mdhd.extend_from_slice(&timescale.to_be_bytes());
+        mdhd.extend_from_slice(&0u32.to_be_bytes());
+        // "und", packed ISO 639-2
+        mdhd.extend_from_slice(&0x55c4u16.to_be_bytes());
+        mdhd.extend_from_slice(&[0; 2]);
+        let mdhd = full_box(b"mdhd", 0, 0, &mdhd);
+        let hdlr = full_box(
+            b"hdlr",
+            0,
+            0,
+            &[&[0; 4][..], b"vide", &[0; 12], b"VideoHandler\0"].concat(),
+        );
+
+        let mut av01 = vec![0; 6];
+        av01.extend_from_slice(&1u16.to_be_bytes());
+        av01.extend_from_slice(&[0; 16]);
+        av01.extend_from_slice(&(width as u16).to_be_bytes());
+        av01.extend_from_slice(&(height as u16).to_be_bytes());
+        av01.extend_from_slice(&0x0048_0000u32.to_be_bytes());
+        av01.extend_from_slice(&0x0048_0000u32.to_be_bytes());
+        av01.extend_from_slice(&0u32.to_be_bytes());
+        av01.extend_from_slice(&1u16.to_be_bytes());
+        let mut compressor = [0u8; 32];
+        compressor[0] = 4;
+        compressor[1..5].copy_from_slice(b"AV1 ");
+        av01.extend_from_slice(&compressor);
+        av01.extend_from_slice(&0x0018u16.to_be_bytes());
+        av01.extend_from_slice(&(-1i16).to_be_bytes());
+        av01.extend_from_slice(&mp4_box(
+            b"av1C",
+            &[&self.config[..], sequence_header].concat(),
+        ));
+        let stsd = full_box(
+            b"stsd",
+            0,
+            0,
+            &[&1u32.to_be_bytes()[..], &mp4_box(b"av01", &av01)].concat(),
+        );
+
+        // Samples live in the fragments, so the sample tables are empty
+        let empty = 0u32.to_be_bytes();
+        let stbl = mp4_box(
+            b"stbl",
+            &[
+                stsd,
+                full_box(b"stts", 0, 0, &empty),
+                full_box(b"stsc", 0, 0, &empty),
+                full_box(b"stsz", 0, 0, &[empty, empty].concat()),
+                full_box(b"stco", 0, 0, &empty),
+            ]
+            .concat(),
+        );
+        let dref = full_box(
+            b"dref",
+            0,
+            0,
+            &[&1u32.to_be_bytes()[..], &full_box(b"url ", 0, 1, &[])].concat(),
+        );
+        let minf = mp4_box(
+            b"minf",
+            &[
+                full_box(b"vmhd", 0, 1, &[0; 8]),
+                mp4_box(b"dinf", &dref),
+                stbl,
+            ]
+            .concat(),
+        );
+        let trak = mp4_box(
+            b"trak",
+            &[tkhd, mp4_box(b"mdia", &[mdhd, hdlr, minf].concat())].concat(),
+        );
+
+        let mut trex = 1u32.to_be_bytes().to_vec();
+        trex.extend_from_slice(&1u32.to_be_bytes());
+        trex.extend_from_slice(&[0; 12]);
+        let mvex = mp4_box(b"mvex", &full_box(b"trex", 0, 0, &trex));
+
+        self.out.write_all(&ftyp)?;
+        self.out
+            .write_all(&mp4_box(b"moov", &[mvhd, trak, mvex].concat()))?;
+        self.header_written = true;
+        Ok(())
+    }
+
+    fn write_fragment(&mut self) -> std::io::Result<()> {
+        let samples = std::mem::take(&mut self.gop);
+        self.sequence += 1;
+        let duration = self.frame_rate.1;
+
+        let moof = |data_offset: u32| {
+            let mfhd = full_box(b"mfhd", 0, 0, &self.sequence.to_be_bytes());
+            // Offsets are relative to the moof; every sample lasts one frame
+            let tfhd = full_box(
+                b"tfhd",
+                0,
+                0x02_0008,
+                &[1u32.to_be_bytes(), duration.to_be_bytes()].concat(),
+            );
+            let decode_time = samples[0].pts * duration as u64;
+            let tfdt = full_box(b"tfdt", 1, 0, &decode_time.to_be_bytes());
+            let mut trun = (samples.len() as u32).to_be_bytes().to_vec();
+            trun.extend_from_slice(&data_offset.to_be_bytes());
+            for sample in &samples {
+                let flags: u32 = if sample.keyframe {
+                    0x0200_0000
+                } else {
+                    // Depends on others, not a sync sample
+                    0x0101_0000
+                };
+                trun.extend_from_slice(&(sample.data.len() as u32).to_be_bytes());
+                trun.extend_from_slice(&flags.to_be_bytes());
+            }
+            // Data offset, sample sizes and sample flags present
+            let trun = full_box(b"trun", 0, 0x00_0601, &trun);
+            mp4_box(
+                b"moof",
+                &[mfhd, mp4_box(b"traf", &[tfhd, tfdt, trun].concat())].concat(),
+            )
+        };
+        // The size doesn't depend on the offset, so measure then rebuild
+        let size = moof(0).len() as u32;
+        self.out.write_all(&moof(size + 8))?;
+
+        let payload: usize = samples.iter().map(|s| s.data.len()).sum();
+        self.out.write_all(&((payload + 8) as u32).to_be_bytes())?;
+        self.out.write_all(b"mdat")?;
+        for sample in &samples {
+            self.out.write_all(&sample.data)?;
+        }
+        Ok(())
+    }
+}
+
+fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len() + 8);
+    out.extend_from_slice(&(body.len() as u32 + 8).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    out
+}
+
+fn full_box(kind: &[u8; 4], version: u8, flags: u32, body: &[u8]) -> Vec<u8> {
+    let mut header = flags.to_be_bytes();
+    header[0] = version;
+    mp4_box(kind, &[&header[..], body].concat())
+}
+
+const OBU_SEQUENCE_HEADER: u8 = 1;
+const OBU_TEMPORAL_DELIMITER: u8 = 2;
+
+/// Splits a low-overhead AV1 bitstream into `(obu_type, whole OBU)`.
+fn obus(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let header = *data.get(pos)?;
+        let kind = (header >> 3) & 0xf;
+        let mut end = pos + 1 + ((header >> 2) & 1) as usize;
+        if header & 2 == 0 {
+            // No size field: the OBU runs to the end
+            end = data.len();
+        } else {
+            let mut size = 0usize;
+            for shift in 0..8 {
+                let byte = *data.get(end)?;
+                end += 1;
+                size |= ((byte & 0x7f) as usize) << (shift * 7);
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            end = (end + size).min(data.len());
+        }
+        let obu = &data[pos..end];
+        pos = end;
+        Some((kind, obu))
+    })
+}
@@ -0,0 +1,282 @@
+use super::{BufferSink, RenderSink, YuvSink};
+use crate::core::{RenderContext, RenderError};
+use crate::model::{ColorSpace, ToneMapping};
+use crate::resources::yuv::{YuvConversion, YuvFormat, YuvMatrix, YuvRange};
+use crate::sources::Y4mHeader;
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::thread::JoinHandle;
+use tokio::sync::mpsc::{Receiver, Sender};
+use wgpu::{TextureFormat, TextureView};
+
+/// Byte stream written by a `PipeSink`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeFormat {
+    /// YUV4MPEG2 with a header and per-frame markers. Planar formats only.
+    Y4m(YuvFormat),
+    /// Tightly packed 8-bit RGBA frames (`-f rawvideo -pix_fmt rgba`).
+    RawRgba,
+    /// Tightly packed planes back to back, e.g. NV12 for
+    /// `-f rawvideo -pix_fmt nv12`.
+    RawYuv(YuvFormat),
+}
+
+enum PipeTarget {
+    Rgba(BufferSink),
+    Yuv(YuvSink),
+}
+
+/// Headless sink streaming frames to any `Write`: a file, stdout, or the
+/// stdin of an encoder process such as `ffmpeg`.
+///
+/// Render into it, then call `write_frame` to read the frame back and queue
+/// it. A writer thread drains the queue; once `queue_depth` frames are
+/// waiting on a slow consumer, `write_frame` waits, yielding to the async
+/// runtime. Call `finish` to close the stream and, for spawned processes,
+/// check their exit status.
+pub struct PipeSink {
+    pub format: PipeFormat,
+    /// Frame rate announced in Y4M headers, as a fraction.
+    pub frame_rate: (u32, u32),
+    target: PipeTarget,
+    sender: Option<Sender<Vec<u8>>>,
+    writer: Option<JoinHandle<std::io::Result<()>>>,
+    child: Option<Child>,
+    frames: u64,
+}
+
+impl PipeSink {
+    /// Streams to `output`, queueing up to 4 frames. YUV formats use
+    /// BT.709 limited range; see `with_conversion`.
+    pub fn new(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        format: PipeFormat,
+        frame_rate: (u32, u32),
+        output: impl Write + Send + 'static,
+    ) -> Result<Self, RenderError> {
+        Self::with_queue_depth(ctx, width, height, format, frame_rate, output, 4)
+    }
+
+    pub fn with_queue_depth(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        format: PipeFormat,
+        frame_rate: (u32, u32),
+        output: impl Write + Send + 'static,
+        queue_depth: usize,
+    ) -> Result<Self, RenderError> {
+        if frame_rate.0 == 0 || frame_rate.1 == 0 {
+            return Err(RenderError::InvalidOutput(format!(
+                "bad frame rate {}:{}",
+                frame_rate.0, frame_rate.1
+            )));
+        }
+        let conversion = YuvConversion::new(YuvMatrix::Bt709, YuvRange::Limited);
+        let target = match format {
+            PipeFormat::RawRgba => PipeTarget::Rgba(BufferSink::new(ctx, width, height)),
+            PipeFormat::Y4m(yuv) | PipeFormat::RawYuv(yuv) => {
+                PipeTarget::Yuv(YuvSink::new(ctx, width, height, yuv, conversion))
+            }
+        };
+
+        let (sender, receiver) = tokio::sync::mpsc::channel(queue_depth.max(1));
+        let writer = std::thread::Builder::new()
+            .name("pipe-writer".into())
+            .spawn(move || write_stream(output, receiver))?;
+
+        let sink = Self {
+            format,
+            frame_rate,
+            target,
+            sender: Some(sender),
+            writer: Some(writer),
+            child: None,
+            frames: 0,
+        };
+        // Fail before any frame is rendered if Y4M can't carry the format
+        sink.y4m_header()?;
+        Ok(sink)
+    }
+
+    /// Spawns `command` with its stdin piped and streams into it.
+    pub fn spawn(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        format: PipeFormat,
+        frame_rate: (u32, u32),
+        command: &mut Command,
+    ) -> Result<Self, RenderError> {
+        let mut child = command.stdin(Stdio::piped()).spawn()?;
+        let stdin = child.stdin.take().expect("stdin was piped");
+        match Self::new(ctx, width, height, format, frame_rate, stdin) {
+            Ok(mut sink) => {
+                sink.child = Some(child);
+                Ok(sink)
+            }
+            Err(error) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                Err(error)
+            }
+        }
+    }
+
+    /// Sets the Y'CbCr matrix, range and siting of YUV formats, and the
+    /// color space they're encoded from.
+    pub fn with_conversion(mut self, conversion: YuvConversion) -> Self {
+        if let PipeTarget::Yuv(sink) = &mut self.target {
+            sink.conversion = conversion;
+            sink.color_space = conversion.matrix.default_color_space();
+        }
+        self
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        match &self.target {
+            PipeTarget::Rgba(sink) => sink.dimensions,
+            PipeTarget::Yuv(sink) => sink.dimensions,
+        }
+    }
+
+    /// Frames handed to the writer so far.
+    pub fn frames_written(&self) -> u64 {
+        self.frames
+    }
+
+    fn y4m_header(&self) -> Result<Option<String>, RenderError> {
+        let (PipeFormat::Y4m(format), PipeTarget::Yuv(sink)) = (self.format, &self.target) else {
+            return Ok(None);
+        };
+        let (width, height) = sink.dimensions;
+        let header = Y4mHeader {
+            width,
+            height,
+            frame_rate: self.frame_rate,
+            format,
+            siting: sink.conversion.siting,
+            range: Some(sink.conversion.range),
+        };
+        header.to_line().map(Some)
+    }
+
+    /// Reads back the last presented frame and queues it for the output.
+    /// Waits while the queue is full. Fails once the output has stopped
+    /// accepting data, with `ProcessExited` if a spawned process failed.
+    pub async fn write_frame(&mut self, ctx: &RenderContext) -> Result<(), RenderError> {
+        let mut bytes = Vec::new();
+        if self.frames == 0
+            && let Some(header) = self.y4m_header()?
+        {
+            bytes.extend_from_slice(header.as_bytes());
+            bytes.push(b'\n');
+        }
+        match &self.target {
+            PipeTarget::Rgba(sink) => {
+                let mapped = sink.read_rows(ctx).await?;
+                for row in mapped.rows() {
+                    bytes.extend_from_slice(row);
+                }
+            }
+            PipeTarget::Yuv(sink) => {
+                let frame = sink.read_planes(ctx).await?;
+                if matches!(self.format, PipeFormat::Y4m(_)) {
+                    bytes.extend_from_slice(b"FRAME\n");
+                }
+                for plane in &frame.planes {
+                    bytes.extend_from_slice(plane);
+                }
+            }
+        }
+
+        let sent = match &self.sender {
+            Some(sender) => sender.send(bytes).await.is_ok(),
+            None => false,
+        };
+        if !sent {
+            // The writer stopped on an error; report it
+            self.close()?;
+            return Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe).into());
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Flushes and closes the output, then waits for a spawned process to
+    /// exit. Returns the number of frames written.
+    pub fn finish(mut self) -> Result<u64, RenderError> {
+        self.close()?;
+        Ok(self.frames)
+    }
+
+    fn close(&mut self) -> Result<(), RenderError> {
+        self.sender = None;
+        let written = match self.writer.take() {
+            Some(writer) => writer
+                .join()
+                .unwrap_or_else(|_| Err(std::io::Error::other("pipe writer panicked"))),
+            None => Ok(()),
+        };
+        // A failed process explains a broken pipe better than the pipe does
+        if let Some(mut child) = self.child.take() {
+            let status = child.wait()?;
+            if !status.success() {
+                return Err(RenderError::ProcessExited(status));
+            }
+        }
+        written.map_err(RenderError::from)
+    }
+}
+
+impl Drop for PipeSink {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+impl RenderSink for PipeSink {
+    fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
+        match &mut self.target {
+            PipeTarget::Rgba(sink) => sink.prepare_frame(),
+            PipeTarget::Yuv(sink) => sink.prepare_frame(),
+        }
+    }
+
+    fn present(&mut self, ctx: &RenderContext) {
+        match &mut self.target {
+            PipeTarget::Rgba(sink) => sink.present(ctx),
+            PipeTarget::Yuv(sink) => sink.present(ctx),
+        }
+    }
+
+    fn format(&self) -> TextureFormat {
+        match &self.target {
+            PipeTarget::Rgba(sink) => sink.format(),
+            PipeTarget::Yuv(sink) => sink.format(),
+        }
+    }
+
+    fn output_color_space(&self) -> ColorSpace {
+        match &self.target {
+            PipeTarget::Rgba(sink) => sink.output_color_space(),
+            PipeTarget::Yuv(sink) => sink.output_color_space(),
+        }
+    }
+
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        match &self.target {
+            PipeTarget::Rgba(sink) => sink.tone_mapping(),
+            PipeTarget::Yuv(sink) => sink.tone_mapping(),
+        }
+    }
+}
+
+fn write_stream(mut output: impl Write, mut frames: Receiver<Vec<u8>>) -> std::io::Result<()> {
+    while let Some(bytes) = frames.blocking_recv() {
+        output.write_all(&bytes)?;
+    }
+    output.flush()
+}
@@ -0,0 +1,374 @@
+use super::RenderSink;
+use super::buffer::padded_bytes_per_row;
+use crate::core::{RenderContext, RenderError};
+use crate::model::{ColorSpace, ToneMapping};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use wgpu::{
+    Buffer, BufferAsyncError, BufferDescriptor, BufferUsages, Extent3d, MapMode, PollType,
+    SubmissionIndex, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureView,
+};
+
+/// Map result of one frame, filled in by the `map_async` callback.
+#[derive(Debug, Default)]
+struct MapState {
+    result: Mutex<Option<Result<(), BufferAsyncError>>>,
+    ready: Notify,
+}
+
+/// A frame read back by a `ReadbackSink`.
+#[derive(Debug, Clone)]
+pub struct ReadbackFrame {
+    /// Position of the frame among those presented to the sink, from 0.
+    pub index: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed pixels in the sink format.
+    pub data: Vec<u8>,
+}
+
+/// Signals that a presented frame has been copied and mapped, and can be
+/// taken from the sink without waiting.
+#[derive(Debug, Clone)]
+pub struct ReadbackTicket {
+    pub index: u64,
+    state: Arc<MapState>,
+}
+
+impl ReadbackTicket {
+    pub fn is_ready(&self) -> bool {
+        self.state.result.lock().unwrap().is_some()
+    }
+
+    /// Waits for the frame without blocking. Something else has to poll
+    /// the device meanwhile, as `ReadbackSink::next_frame` does.
+    pub async fn ready(&self) {
+        loop {
+            // Registered before the check so a callback in between isn't missed
+            let notified = self.state.ready.notified();
+            if self.is_ready() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Blocks until the frame is ready.
+    pub fn wait(&self, ctx: &RenderContext) {
+        while !self.is_ready() {
+            ctx.instance.poll_all(true);
+        }
+    }
+}
+
+struct InFlight {
+    slot: usize,
+    submission: SubmissionIndex,
+    ticket: ReadbackTicket,
+}
+
+/// Headless sink reading frames back through a ring of staging buffers.
+///
+/// Each `present` copies the frame into the next free buffer and starts
+/// mapping it, without waiting, so the GPU can render the following frames
+/// while earlier ones are still being read. Frames come back in order from
+/// `next_frame` or `try_next_frame`. If every buffer is in flight, `present`
+/// waits for the oldest frame and holds its pixels, or the error reading it,
+/// until they're taken. Up to `depth` frames are held; past that, rendering
+/// into the sink fails until some are taken.
+pub struct ReadbackSink {
+    pub texture: Texture,
+    pub dimensions: (u32, u32),
+    /// Color space the read back pixels are encoded in.
+    pub color_space: ColorSpace,
+    /// Applied when HDR or float content is written to this sink.
+    pub tone_mapping: Option<ToneMapping>,
+    buffers: Vec<Buffer>,
+    in_flight: VecDeque<InFlight>,
+    // Frames finished by `present`, with their ticket in case they failed
+    completed: VecDeque<(ReadbackTicket, Result<ReadbackFrame, RenderError>)>,
+    presented: u64,
+}
+
+impl ReadbackSink {
+    /// An 8-bit sRGB sink with `depth` staging buffers.
+    pub fn new(ctx: &RenderContext, width: u32, height: u32, depth: usize) -> Self {
+        Self::with_format(
+            ctx,
+            width,
+            height,
+            TextureFormat::Rgba8Unorm,
+            ColorSpace::SRGB,
+            depth,
+        )
+    }
+
+    pub fn with_format(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        color_space: ColorSpace,
+        depth: usize,
+    ) -> Self {
+        let texture = ctx.device.create_texture(&TextureDescriptor {
+            label: Some("ReadbackSink Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let bytes_per_pixel = format
+            .block_copy_size(None)
+            .expect("ReadbackSink format must be a single-aspect color format");
+        let size = (padded_bytes_per_row(width, bytes_per_pixel) * height) as u64;
+        let buffers = (0..depth.max(1))
+            .map(|_| {
+                ctx.device.create_buffer(&BufferDescriptor {
+                    label: Some("Readback Buffer"),
+                    size,
+                    usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
+
+        Self {
+            texture,
+            dimensions: (width, height),
+            color_space,
+            tone_mapping: None,
+            buffers,
+            in_flight: VecDeque::new(),
+            completed: VecDeque::new(),
+            presented: 0,
+        }
+    }
+
+    /// Number of staging buffers.
+    pub fn depth(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Frames presented so far; the next frame gets this index.
+    pub fn frames_presented(&self) -> u64 {
+        self.presented
+    }
+
+    /// Frames still being read back by the GPU.
+    pub fn pending(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Frames already read back by `present`, waiting to be taken.
+    pub fn frames_held(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Ticket for the most recently presented frame, if it hasn't been
+    /// taken yet.
+    pub fn latest_ticket(&self) -> Option<ReadbackTicket> {
+        match self.in_flight.back() {
+            Some(frame) => Some(frame.ticket.clone()),
+            None => self.completed.back().map(|(ticket, _)| ticket.clone()),
+        }
+    }
+
+    fn bytes_per_pixel(&self) -> u32 {
+        self.texture
+            .format()
+            .block_copy_size(None)
+            .expect("ReadbackSink format must be a single-aspect color format")
+    }
+
+    /// Takes the oldest frame, waiting for the GPU if it isn't ready yet.
+    /// Returns `None` when no frames are left. A frame that failed to read
+    /// back is reported here in its place, so indices never skip silently.
+    pub async fn next_frame(
+        &mut self,
+        ctx: &RenderContext,
+    ) -> Result<Option<ReadbackFrame>, RenderError> {
+        if let Some((_, frame)) = self.completed.pop_front() {
+            return frame.map(Some);
+        }
+        let Some(frame) = self.in_flight.pop_front() else {
+            return Ok(None);
+        };
+        if !frame.ticket.is_ready() {
+            // The poll blocks, so it runs off the async runtime while the
+            // callback is awaited
+            let device = ctx.device.clone();
+            let submission = frame.submission.clone();
+            let poll = tokio::task::spawn_blocking(move || {
+                let _ = device.poll(PollType::Wait {
+                    submission_index: Some(submission),
+                    timeout: None,
+                });
+            });
+            tokio::select! {
+                _ = frame.ticket.ready() => {}
+                // A poll that returns without the callback leaves the map aborted
+                _ = poll => {}
+            }
+        }
+        self.finish(frame).map(Some)
+    }
+
+    /// Takes the oldest frame if it's ready, without blocking.
+    pub fn try_next_frame(
+        &mut self,
+        ctx: &RenderContext,
+    ) -> Result<Option<ReadbackFrame>, RenderError> {
+        if let Some((_, frame)) = self.completed.pop_front() {
+            return frame.map(Some);
+        }
+        let _ = ctx.device.poll(PollType::Poll);
+        match self.in_flight.front() {
+            Some(frame) if frame.ticket.is_ready() => {
+                let frame = self.in_flight.pop_front().unwrap();
+                self.finish(frame).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads out `frame`'s copy once its map has settled, and frees its
+    /// buffer. The buffer is left idle on failure too, ready for the next
+    /// copy.
+    fn finish(&mut self, frame: InFlight) -> Result<ReadbackFrame, RenderError> {
+        // Not matched on directly: unmapping runs the callback, which locks it
+        let state = frame.ticket.state.result.lock().unwrap().clone();
+        match state {
+            Some(Ok(())) => {}
+            Some(Err(e)) => return Err(e.into()),
+            None => {
+                // Cancel the pending map so the slot can be copied into again
+                self.buffers[frame.slot].unmap();
+                return Err(RenderError::ReadbackAborted);
+            }
+        }
+
+        let (width, height) = self.dimensions;
+        let row_bytes = (width * self.bytes_per_pixel()) as usize;
+        let padded = padded_bytes_per_row(width, self.bytes_per_pixel()) as usize;
+        let buffer = &self.buffers[frame.slot];
+        let view = buffer.slice(..).get_mapped_range();
+        let mut data = Vec::with_capacity(row_bytes * height as usize);
+        for row in view.chunks(padded).take(height as usize) {
+            data.extend_from_slice(&row[..row_bytes]);
+        }
+        drop(view);
+        buffer.unmap();
+
+        Ok(ReadbackFrame {
+            index: frame.ticket.index,
+            width,
+            height,
+            data,
+        })
+    }
+}
+
+impl RenderSink for ReadbackSink {
+    fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
+        let depth = self.buffers.len();
+        if self.in_flight.len() == depth && self.completed.len() == depth {
+            return Err(RenderError::InvalidOutput(format!(
+                "readback ring is full with {} frames waiting to be taken",
+                2 * depth
+            )));
+        }
+        Ok(self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    fn present(&mut self, ctx: &RenderContext) {
+        if self.in_flight.len() == self.buffers.len() {
+            // Free the oldest buffer; a failure waits to be taken like a frame
+            let oldest = self.in_flight.pop_front().unwrap();
+            if !oldest.ticket.is_ready() {
+                // Waits for this frame only; later ones keep rendering
+                let _ = ctx.device.poll(PollType::Wait {
+                    submission_index: Some(oldest.submission.clone()),
+                    timeout: None,
+                });
+            }
+            let ticket = oldest.ticket.clone();
+            let frame = self.finish(oldest);
+            self.completed.push_back((ticket, frame));
+        }
+
+        // Frames finish in order, so the ring slot after the newest is free
+        let slot = self.presented as usize % self.buffers.len();
+        let (width, height) = self.dimensions;
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("ReadbackSink Copy Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.buffers[slot],
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row(width, self.bytes_per_pixel())),
+                    rows_per_image: Some(height),
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        let submission = ctx.queue.submit(std::iter::once(encoder.finish()));
+
+        let state = Arc::new(MapState::default());
+        let callback_state = state.clone();
+        self.buffers[slot]
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                *callback_state.result.lock().unwrap() = Some(result);
+                callback_state.ready.notify_waiters();
+            });
+
+        self.in_flight.push_back(InFlight {
+            slot,
+            submission,
+            ticket: ReadbackTicket {
+                index: self.presented,
+                state,
+            },
+        });
+        self.presented += 1;
+    }
+
+    fn format(&self) -> TextureFormat {
+        self.texture.format()
+    }
+
+    fn output_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        self.tone_mapping
+    }
+}
@@ -0,0 +1,365 @@
+use super::{BufferSink, RenderSink};
+use crate::core::{RenderContext, RenderError};
+use crate::model::{ColorSpace, ToneMapping};
+use crate::sources::SequencePattern;
+use image::{DynamicImage, ImageBuffer, ImageFormat, Rgba};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use tokio::sync::Notify;
+use wgpu::{Features, TextureFormat, TextureView};
+
+/// File type and sample depth written by an `ImageSequenceSink`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFileFormat {
+    /// 8-bit RGBA PNG.
+    Png8,
+    /// 16-bit RGBA PNG.
+    Png16,
+    /// 8-bit RGBA TIFF.
+    Tiff8,
+    /// 16-bit RGBA TIFF.
+    Tiff16,
+    /// 32-bit float RGBA OpenEXR, scene-linear by default.
+    Exr,
+}
+
+impl ImageFileFormat {
+    /// Format the wrapped `BufferSink` renders to. 16-bit files render to
+    /// `Rgba16Unorm` when `TEXTURE_FORMAT_16BIT_NORM` is available and fall
+    /// back to half floats, which hold only 11 bits, otherwise.
+    pub fn texture_format(self, features: Features) -> TextureFormat {
+        match self {
+            Self::Png8 | Self::Tiff8 => TextureFormat::Rgba8Unorm,
+            Self::Png16 | Self::Tiff16
+                if features.contains(Features::TEXTURE_FORMAT_16BIT_NORM) =>
+            {
+                TextureFormat::Rgba16Unorm
+            }
+            Self::Png16 | Self::Tiff16 | Self::Exr => TextureFormat::Rgba16Float,
+        }
+    }
+
+    pub fn default_color_space(self) -> ColorSpace {
+        match self {
+            Self::Exr => ColorSpace::LINEAR_REC709,
+            _ => ColorSpace::SRGB,
+        }
+    }
+
+    fn image_format(self) -> ImageFormat {
+        match self {
+            Self::Png8 | Self::Png16 => ImageFormat::Png,
+            Self::Tiff8 | Self::Tiff16 => ImageFormat::Tiff,
+            Self::Exr => ImageFormat::OpenExr,
+        }
+    }
+}
+
+/// What `write_frame` does when the target file already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    /// Replace the file.
+    Overwrite,
+    /// Leave the file alone and drop the frame.
+    Skip,
+    /// Fail with an `AlreadyExists` I/O error.
+    #[default]
+    Error,
+}
+
+struct WriteJob {
+    path: PathBuf,
+    format: ImageFileFormat,
+    width: u32,
+    height: u32,
+    // Unpadded rows in `texture_format`
+    texture_format: TextureFormat,
+    data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct WriteQueue {
+    pending: VecDeque<WriteJob>,
+    active: usize,
+    written: u32,
+    // First failure, reported by the next `write_frame` or `flush`
+    error: Option<RenderError>,
+    shutdown: bool,
+}
+
+struct WriteShared {
+    queue: Mutex<WriteQueue>,
+    changed: Condvar,
+    // Wakes `write_frame` without parking its async runtime thread
+    dequeued: Notify,
+}
+
+/// Headless sink writing each rendered frame to a numbered file, e.g.
+/// `out/frame_%04d.png`.
+///
+/// Render into it like a `BufferSink`, then call `write_frame` with the
+/// frame number. Pixels are read back on the calling thread and encoded on
+/// a pool of writer threads; `write_frame` only waits once `max_pending`
+/// frames are queued, yielding to the async runtime while it does.
+///
+/// Call `flush` at the end to wait for the last files.
+pub struct ImageSequenceSink {
+    pub buffer: BufferSink,
+    pub pattern: SequencePattern,
+    pub format: ImageFileFormat,
+    pub overwrite: OverwritePolicy,
+    /// Frames queued for encoding before `write_frame` waits.
+    pub max_pending: usize,
+    shared: Arc<WriteShared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ImageSequenceSink {
+    /// Writes on two threads. `pattern` must be `SequencePattern::Printf`;
+    /// its directory is created if missing.
+    pub fn new(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        pattern: SequencePattern,
+        format: ImageFileFormat,
+    ) -> Result<Self, RenderError> {
+        Self::with_threads(ctx, width, height, pattern, format, 2)
+    }
+
+    pub fn with_threads(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        pattern: SequencePattern,
+        format: ImageFileFormat,
+        threads: usize,
+    ) -> Result<Self, RenderError> {
+        if let Some(dir) = pattern.file_path(0)?.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let buffer = BufferSink::with_format(
+            ctx,
+            width,
+            height,
+            format.texture_format(ctx.device.features()),
+            format.default_color_space(),
+        );
+
+        let shared = Arc::new(WriteShared {
+            queue: Mutex::new(WriteQueue::default()),
+            changed: Condvar::new(),
+            dequeued: Notify::new(),
+        });
+        let workers = (0..threads.max(1))
+            .map(|index| {
+                let shared = shared.clone();
+                std::thread::Builder::new()
+                    .name(format!("sequence-write-{}", index))
+                    .spawn(move || write_worker(&shared))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            buffer,
+            pattern,
+            format,
+            overwrite: OverwritePolicy::default(),
+            max_pending: threads.max(1) * 2,
+            shared,
+            workers,
+        })
+    }
+
+    pub fn with_overwrite(mut self, policy: OverwritePolicy) -> Self {
+        self.overwrite = policy;
+        self
+    }
+
+    /// File frame `number` is written to.
+    pub fn path(&self, number: u32) -> PathBuf {
+        // The pattern was checked at construction
+        self.pattern
+            .file_path(number)
+            .expect("ImageSequenceSink pattern must be Printf")
+    }
+
+    /// Frames read back and waiting to be encoded.
+    pub fn pending(&self) -> usize {
+        let queue = self.shared.queue.lock().unwrap();
+        queue.pending.len() + queue.active
+    }
+
+    /// Reads back the last presented frame and queues it as frame `number`.
+    /// Returns the file it will be written to, or `None` if the overwrite
+    /// policy skipped it.
+    pub async fn write_frame(
+        &mut self,
+        ctx: &RenderContext,
+        number: u32,
+    ) -> Result<Option<PathBuf>, RenderError> {
+        if let Some(error) = self.shared.queue.lock().unwrap().error.take() {
+            return Err(error);
+        }
+        let path = self.path(number);
+        if path.exists() {
+            match self.overwrite {
+                OverwritePolicy::Overwrite => {}
+                OverwritePolicy::Skip => return Ok(None),
+                OverwritePolicy::Error => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::AlreadyExists,
+                        format!("{} already exists", path.display()),
+                    )
+                    .into());
+                }
+            }
+        }
+
+        let data = self.buffer.read_pixels(ctx).await?;
+        let (width, height) = self.buffer.dimensions;
+
+        let job = WriteJob {
+            path: path.clone(),
+            format: self.format,
+            width,
+            height,
+            texture_format: self.buffer.format(),
+            data,
+        };
+        loop {
+            // Registered before checking so a job taken in between still wakes us
+            let dequeued = self.shared.dequeued.notified();
+            let mut dequeued = std::pin::pin!(dequeued);
+            dequeued.as_mut().enable();
+            {
+                let mut queue = self.shared.queue.lock().unwrap();
+                if queue.pending.len() < self.max_pending.max(1) {
+                    queue.pending.push_back(job);
+                    break;
+                }
+            }
+            dequeued.await;
+        }
+        self.shared.changed.notify_all();
+        Ok(Some(path))
+    }
+
+    /// Waits for every queued frame to be written. Returns the number of
+    /// files written so far, or the first error since the last check.
+    pub fn flush(&self) -> Result<u32, RenderError> {
+        let mut queue = self.shared.queue.lock().unwrap();
+        while !queue.pending.is_empty() || queue.active > 0 {
+            queue = self.shared.changed.wait(queue).unwrap();
+        }
+        match queue.error.take() {
+            Some(error) => Err(error),
+            None => Ok(queue.written),
+        }
+    }
+}
+
+impl Drop for ImageSequenceSink {
+    // Finishes the queued frames; errors are lost, so `flush` first
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().shutdown = true;
+        self.shared.changed.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl RenderSink for ImageSequenceSink {
+    fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
+        self.buffer.prepare_frame()
+    }
+
+    fn present(&mut self, ctx: &RenderContext) {
+        self.buffer.present(ctx)
+    }
+
+    fn format(&self) -> TextureFormat {
+        self.buffer.format()
+    }
+
+    fn output_color_space(&self) -> ColorSpace {
+        self.buffer.output_color_space()
+    }
+
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        self.buffer.tone_mapping()
+    }
+}
+
+fn write_worker(shared: &WriteShared) {
+    let mut queue = shared.queue.lock().unwrap();
+    loop {
+        let Some(job) = queue.pending.pop_front() else {
+            if queue.shutdown {
+                return;
+            }
+            queue = shared.changed.wait(queue).unwrap();
+            continue;
+        };
+        queue.active += 1;
+        drop(queue);
+        shared.changed.notify_all();
+        shared.dequeued.notify_waiters();
+
+        let result = encode(job);
+
+        queue = shared.queue.lock().unwrap();
+        queue.active -= 1;
+        match result {
+            Ok(()) => queue.written += 1,
+            Err(error) => {
+                queue.error.get_or_insert(error);
+            }
+        }
+        shared.changed.notify_all();
+    }
+}
+
+fn encode(job: WriteJob) -> Result<(), RenderError> {
+    let WriteJob {
+        path,
+        format,
+        width,
+        height,
+        texture_format,
+        data,
+    } = job;
+    let image = match format {
+        ImageFileFormat::Png8 | ImageFileFormat::Tiff8 => {
+            DynamicImage::ImageRgba8(ImageBuffer::from_raw(width, height, data).unwrap())
+        }
+        ImageFileFormat::Png16 | ImageFileFormat::Tiff16 => {
+            let samples = match texture_format {
+                TextureFormat::Rgba16Unorm => data
+                    .chunks_exact(2)
+                    .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                    .collect(),
+                _ => halves(&data)
+                    .map(|v| (v.clamp(0.0, 1.0) * 65535.0).round() as u16)
+                    .collect(),
+            };
+            DynamicImage::ImageRgba16(
+                ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, samples).unwrap(),
+            )
+        }
+        ImageFileFormat::Exr => {
+            let samples = halves(&data).collect();
+            DynamicImage::ImageRgba32F(ImageBuffer::from_raw(width, height, samples).unwrap())
+        }
+    };
+    image.save_with_format(&path, format.image_format())?;
+    Ok(())
+}
+
+fn halves(data: &[u8]) -> impl Iterator<Item = f32> + '_ {
+    data.chunks_exact(2)
+        .map(|b| half::f16::from_le_bytes([b[0], b[1]]).to_f32())
+}
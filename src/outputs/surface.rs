@@ -1,10 +1,15 @@
 use super::RenderSink;
 use crate::core::{RenderContext, RenderError};
-use wgpu::{Surface, SurfaceConfiguration, SurfaceTexture, TextureView};
+use crate::model::{ColorSpace, ToneMapping};
+use wgpu::{Surface, SurfaceConfiguration, TextureFormat, TextureView};
 
 pub struct SurfaceSink<'a> {
     pub surface: Surface<'a>,
     pub config: SurfaceConfiguration,
+    /// Color space of the display the surface is shown on.
+    pub color_space: ColorSpace,
+    /// Applied when HDR or float content is shown on this surface.
+    pub tone_mapping: Option<ToneMapping>,
     // We hold the SurfaceTexture temporarily until present is called
     current_surface_texture: Option<wgpu::SurfaceTexture>,
 }
@@ -34,6 +39,8 @@ impl<'a> SurfaceSink<'a> {
         Self {
             surface,
             config,
+            color_space: ColorSpace::SRGB,
+            tone_mapping: None,
             current_surface_texture: None,
         }
     }
@@ -49,6 +56,8 @@ impl<'a> SurfaceSink<'a> {
 
 impl<'a> RenderSink for SurfaceSink<'a> {
     fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
+        // A frame left over from a failed render would block acquiring the next
+        self.current_surface_texture = None;
         let texture = self
             .surface
             .get_current_texture()
@@ -67,4 +76,20 @@ impl<'a> RenderSink for SurfaceSink<'a> {
             texture.present();
         }
     }
+
+    fn discard_frame(&mut self) {
+        self.current_surface_texture = None;
+    }
+
+    fn format(&self) -> TextureFormat {
+        self.config.format
+    }
+
+    fn output_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        self.tone_mapping
+    }
 }
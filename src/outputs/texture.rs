@@ -0,0 +1,152 @@
+use super::RenderSink;
+use crate::core::{RenderContext, RenderError};
+use crate::model::{ColorSpace, ToneMapping};
+use wgpu::{
+    Extent3d, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureView,
+};
+
+/// Offscreen sink rendering into a texture the host samples directly, e.g.
+/// as an egui or iced image or a material in a game engine.
+///
+/// Nothing is copied or presented: once `render` returns, the frame is in
+/// `texture` for any later submission on the same queue. The texture is
+/// kept across frames and only replaced by `resize`, so hosts can register
+/// it once. Cloning `texture` or `view` keeps it alive independently of
+/// the sink.
+pub struct TextureSink {
+    pub texture: Texture,
+    pub view: TextureView,
+    /// Color space frames are encoded in.
+    pub color_space: ColorSpace,
+    /// Applied when HDR or float content is written to this sink.
+    pub tone_mapping: Option<ToneMapping>,
+    usage: TextureUsages,
+    frames: u64,
+}
+
+impl TextureSink {
+    /// An `Rgba8UnormSrgb` sink that can be sampled, which decodes to linear
+    /// on read like the textures UI toolkits expect.
+    pub fn new(ctx: &RenderContext, width: u32, height: u32) -> Self {
+        Self::with_format(
+            ctx,
+            width,
+            height,
+            TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::SRGB,
+            TextureUsages::TEXTURE_BINDING,
+        )
+    }
+
+    /// Creates a sink with any renderable color format. `usage` is what the
+    /// host needs on top of `RENDER_ATTACHMENT`, e.g. `TEXTURE_BINDING` to
+    /// sample it or `COPY_SRC` to copy it elsewhere.
+    pub fn with_format(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        color_space: ColorSpace,
+        usage: TextureUsages,
+    ) -> Self {
+        let usage = usage | TextureUsages::RENDER_ATTACHMENT;
+        let texture = create_texture(ctx, width, height, format, usage);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture,
+            view,
+            color_space,
+            tone_mapping: None,
+            usage,
+            frames: 0,
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.texture.width(), self.texture.height())
+    }
+
+    /// Usage flags of the texture, including `RENDER_ATTACHMENT`.
+    pub fn usage(&self) -> TextureUsages {
+        self.usage
+    }
+
+    /// Frames rendered so far, for hosts to tell when the content changed.
+    pub fn frames_rendered(&self) -> u64 {
+        self.frames
+    }
+
+    /// Replaces the texture when the size changes, e.g. when the host's
+    /// viewport is resized. Returns whether it was replaced, in which case
+    /// the host must pick up the new `texture` and `view`. Fails for an
+    /// empty size, e.g. a minimized window, leaving the texture as it was.
+    pub fn resize(
+        &mut self,
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+    ) -> Result<bool, RenderError> {
+        if width == 0 || height == 0 {
+            return Err(RenderError::InvalidOutput(format!(
+                "can't resize to an empty {}x{} texture",
+                width, height
+            )));
+        }
+        if self.dimensions() == (width, height) {
+            return Ok(false);
+        }
+        self.texture = create_texture(ctx, width, height, self.texture.format(), self.usage);
+        self.view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        Ok(true)
+    }
+}
+
+fn create_texture(
+    ctx: &RenderContext,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    usage: TextureUsages,
+) -> Texture {
+    ctx.device.create_texture(&TextureDescriptor {
+        label: Some("TextureSink Texture"),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format,
+        usage,
+        view_formats: &[],
+    })
+}
+
+impl RenderSink for TextureSink {
+    fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
+        Ok(self.view.clone())
+    }
+
+    fn present(&mut self, _ctx: &RenderContext) {
+        // The frame stays on the GPU; commands sampling it are ordered after
+        // the render on the queue
+        self.frames += 1;
+    }
+
+    fn format(&self) -> TextureFormat {
+        self.texture.format()
+    }
+
+    fn output_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        self.tone_mapping
+    }
+}
@@ -0,0 +1,192 @@
+use super::RenderSink;
+use crate::core::{RenderContext, RenderError};
+use crate::model::{ColorSpace, ToneMapping};
+use crate::pipeline::{YuvPacker, packed_planes};
+use crate::resources::yuv::{ChromaFilter, YuvConversion, YuvFormat};
+use wgpu::{
+    Buffer, BufferDescriptor, BufferUsages, Extent3d, MapMode, Texture, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureUsages, TextureView,
+};
+
+/// A read back Y'CbCr frame with tightly packed planes.
+#[derive(Debug, Clone)]
+pub struct YuvFrame {
+    pub format: YuvFormat,
+    pub width: u32,
+    pub height: u32,
+    pub planes: Vec<Vec<u8>>,
+}
+
+impl YuvFrame {
+    /// Row size of `plane` in bytes.
+    pub fn stride(&self, plane: usize) -> u32 {
+        self.format.plane_row_bytes(plane, self.width)
+    }
+}
+
+/// Headless sink producing encoder-ready planar or semi-planar Y'CbCr frames
+/// in any `YuvFormat`.
+///
+/// The output transform renders R'G'B' into `texture`; `present` then packs it
+/// into Y'CbCr planes with a compute pass and copies them for readback.
+pub struct YuvSink {
+    pub texture: Texture,
+    pub planes_buffer: Buffer,
+    pub output_buffer: Buffer,
+    pub dimensions: (u32, u32),
+    pub format: YuvFormat,
+    pub conversion: YuvConversion,
+    pub chroma_filter: ChromaFilter,
+    /// Color space of the R'G'B' signal before the matrix is applied.
+    pub color_space: ColorSpace,
+    pub tone_mapping: Option<ToneMapping>,
+    packer: YuvPacker,
+}
+
+impl YuvSink {
+    pub fn new(
+        ctx: &RenderContext,
+        width: u32,
+        height: u32,
+        format: YuvFormat,
+        conversion: YuvConversion,
+    ) -> Self {
+        let texture = ctx.device.create_texture(&TextureDescriptor {
+            label: Some("YuvSink Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            // Float keeps 10-bit precision ahead of quantization
+            format: TextureFormat::Rgba16Float,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let size = packed_planes(format, width, height)
+            .iter()
+            .map(|p| p.row_words * p.rows * 4)
+            .sum::<u32>() as u64;
+        let planes_buffer = ctx.device.create_buffer(&BufferDescriptor {
+            label: Some("YuvSink Planes"),
+            size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let output_buffer = ctx.device.create_buffer(&BufferDescriptor {
+            label: Some("YuvSink Output Buffer"),
+            size,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            texture,
+            planes_buffer,
+            output_buffer,
+            dimensions: (width, height),
+            format,
+            conversion,
+            chroma_filter: ChromaFilter::default(),
+            color_space: conversion.matrix.default_color_space(),
+            tone_mapping: None,
+            packer: YuvPacker::new(&ctx.device),
+        }
+    }
+
+    /// Reads the last presented frame back to CPU.
+    pub async fn read_planes(&self, ctx: &RenderContext) -> Result<YuvFrame, RenderError> {
+        let slice = self.output_buffer.slice(..);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        slice.map_async(MapMode::Read, move |v| {
+            let _ = tx.send(v);
+        });
+
+        ctx.instance.poll_all(true);
+
+        rx.await.map_err(|_| RenderError::ReadbackAborted)??;
+
+        let (width, height) = self.dimensions;
+        let data = slice.get_mapped_range();
+        let planes = packed_planes(self.format, width, height)
+            .iter()
+            .map(|plane| {
+                let start = plane.word_offset as usize * 4;
+                let pitch = plane.row_words as usize * 4;
+                let mut packed = Vec::with_capacity((plane.row_bytes * plane.rows) as usize);
+                for row in 0..plane.rows as usize {
+                    let row_start = start + row * pitch;
+                    packed
+                        .extend_from_slice(&data[row_start..row_start + plane.row_bytes as usize]);
+                }
+                packed
+            })
+            .collect();
+
+        drop(data);
+        self.output_buffer.unmap();
+
+        Ok(YuvFrame {
+            format: self.format,
+            width,
+            height,
+            planes,
+        })
+    }
+}
+
+impl RenderSink for YuvSink {
+    fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
+        Ok(self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    fn present(&mut self, ctx: &RenderContext) {
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("YuvSink Pack Encoder"),
+            });
+
+        let view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        self.packer.encode(
+            &ctx.device,
+            &mut encoder,
+            self.format,
+            self.conversion,
+            self.chroma_filter,
+            self.dimensions,
+            &view,
+            &self.planes_buffer,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.planes_buffer,
+            0,
+            &self.output_buffer,
+            0,
+            self.planes_buffer.size(),
+        );
+
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    fn format(&self) -> TextureFormat {
+        self.texture.format()
+    }
+
+    fn output_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    fn tone_mapping(&self) -> Option<ToneMapping> {
+        self.tone_mapping
+    }
+}
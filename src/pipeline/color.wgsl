@@ -0,0 +1,163 @@
+// Transfer functions shared by the layer and output shaders.
+// IDs match `TransferFunction` discriminants on the CPU side.
+const TF_LINEAR: u32 = 0u;
+const TF_SRGB: u32 = 1u;
+const TF_REC709: u32 = 2u;
+const TF_SLOG3: u32 = 3u;
+const TF_LOGC3: u32 = 4u;
+const TF_PQ: u32 = 5u;
+const TF_HLG: u32 = 6u;
+
+// ARRI LogC3 EI 800
+const LOGC_CUT: f32 = 0.010591;
+const LOGC_A: f32 = 5.555556;
+const LOGC_B: f32 = 0.052272;
+const LOGC_C: f32 = 0.247190;
+const LOGC_D: f32 = 0.385537;
+const LOGC_E: f32 = 5.367655;
+const LOGC_F: f32 = 0.092809;
+
+// SMPTE ST 2084
+const PQ_M1: f32 = 0.1593017578125;
+const PQ_M2: f32 = 78.84375;
+const PQ_C1: f32 = 0.8359375;
+const PQ_C2: f32 = 18.8515625;
+const PQ_C3: f32 = 18.6875;
+
+// ARIB STD-B67
+const HLG_A: f32 = 0.17883277;
+const HLG_B: f32 = 0.28466892;
+const HLG_C: f32 = 0.55991073;
+// BT.2100 OOTF for a 1000 nit display
+const HLG_GAMMA: f32 = 1.2;
+const HLG_PEAK_NITS: f32 = 1000.0;
+const PQ_PEAK_NITS: f32 = 10000.0;
+
+fn log10(x: f32) -> f32 {
+    return log2(x) * 0.30102999566;
+}
+
+fn pow10(x: f32) -> f32 {
+    return exp2(x * 3.32192809489);
+}
+
+fn to_linear_1(v: f32, tf: u32) -> f32 {
+    switch tf {
+        case TF_SRGB: {
+            if v <= 0.04045 {
+                return v / 12.92;
+            }
+            return pow((v + 0.055) / 1.055, 2.4);
+        }
+        case TF_REC709: {
+            if v < 0.081 {
+                return v / 4.5;
+            }
+            return pow((v + 0.099) / 1.099, 1.0 / 0.45);
+        }
+        case TF_SLOG3: {
+            if v >= 171.2103 / 1023.0 {
+                return pow10((v * 1023.0 - 420.0) / 261.5) * 0.19 - 0.01;
+            }
+            return (v * 1023.0 - 95.0) * 0.01125 / (171.2103 - 95.0);
+        }
+        case TF_LOGC3: {
+            if v > LOGC_E * LOGC_CUT + LOGC_F {
+                return (pow10((v - LOGC_D) / LOGC_C) - LOGC_B) / LOGC_A;
+            }
+            return (v - LOGC_F) / LOGC_E;
+        }
+        case TF_PQ: {
+            let p = pow(max(v, 0.0), 1.0 / PQ_M2);
+            return pow(max(p - PQ_C1, 0.0) / (PQ_C2 - PQ_C3 * p), 1.0 / PQ_M1);
+        }
+        case TF_HLG: {
+            if v <= 0.5 {
+                return v * v / 3.0;
+            }
+            return (exp((v - HLG_C) / HLG_A) + HLG_B) / 12.0;
+        }
+        default: {
+            return v;
+        }
+    }
+}
+
+fn from_linear_1(l: f32, tf: u32) -> f32 {
+    switch tf {
+        case TF_SRGB: {
+            if l <= 0.0031308 {
+                return l * 12.92;
+            }
+            return 1.055 * pow(l, 1.0 / 2.4) - 0.055;
+        }
+        case TF_REC709: {
+            if l < 0.018 {
+                return l * 4.5;
+            }
+            return 1.099 * pow(l, 0.45) - 0.099;
+        }
+        case TF_SLOG3: {
+            if l >= 0.01125 {
+                return (420.0 + log10((l + 0.01) / 0.19) * 261.5) / 1023.0;
+            }
+            return (l * (171.2103 - 95.0) / 0.01125 + 95.0) / 1023.0;
+        }
+        case TF_LOGC3: {
+            if l > LOGC_CUT {
+                return LOGC_C * log10(LOGC_A * l + LOGC_B) + LOGC_D;
+            }
+            return LOGC_E * l + LOGC_F;
+        }
+        case TF_PQ: {
+            let y = pow(max(l, 0.0), PQ_M1);
+            return pow((PQ_C1 + PQ_C2 * y) / (1.0 + PQ_C3 * y), PQ_M2);
+        }
+        case TF_HLG: {
+            let x = max(l, 0.0);
+            if x <= 1.0 / 12.0 {
+                return sqrt(3.0 * x);
+            }
+            return HLG_A * log(12.0 * x - HLG_B) + HLG_C;
+        }
+        default: {
+            return l;
+        }
+    }
+}
+
+fn to_linear(c: vec3<f32>, tf: u32) -> vec3<f32> {
+    return vec3<f32>(to_linear_1(c.r, tf), to_linear_1(c.g, tf), to_linear_1(c.b, tf));
+}
+
+fn from_linear(c: vec3<f32>, tf: u32) -> vec3<f32> {
+    return vec3<f32>(from_linear_1(c.r, tf), from_linear_1(c.g, tf), from_linear_1(c.b, tf));
+}
+
+// The pipeline's linear light is relative to reference white (1.0 = SDR white).
+// These wrap the raw curves with the absolute-luminance scaling HDR needs.
+fn decode(c: vec3<f32>, tf: u32, reference_white: f32, luma: vec3<f32>) -> vec3<f32> {
+    let l = to_linear(c, tf);
+    if tf == TF_PQ {
+        return l * PQ_PEAK_NITS / reference_white;
+    }
+    if tf == TF_HLG {
+        // Scene light -> display light (OOTF)
+        let y = max(dot(luma, l), 1e-6);
+        return l * pow(y, HLG_GAMMA - 1.0) * HLG_PEAK_NITS / reference_white;
+    }
+    return l;
+}
+
+fn encode(l: vec3<f32>, tf: u32, reference_white: f32, luma: vec3<f32>) -> vec3<f32> {
+    if tf == TF_PQ {
+        return from_linear(l * reference_white / PQ_PEAK_NITS, tf);
+    }
+    if tf == TF_HLG {
+        // Display light -> scene light (inverse OOTF)
+        let d = max(l * reference_white / HLG_PEAK_NITS, vec3<f32>(0.0));
+        let y = max(dot(luma, d), 1e-6);
+        return from_linear(d * pow(y, (1.0 - HLG_GAMMA) / HLG_GAMMA), tf);
+    }
+    return from_linear(l, tf);
+}
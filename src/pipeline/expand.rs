@@ -0,0 +1,163 @@
+use super::mipmap::mip_view;
+use super::uniforms::ExpandUniforms;
+use crate::resources::format::PixelFormat;
+use crevice::std140::AsStd140;
+use std::collections::HashMap;
+use wgpu::util::DeviceExt;
+use wgpu::{
+    BindGroupLayout, Buffer, CommandEncoder, Device, PipelineLayout, RenderPipeline, ShaderModule,
+    Texture, TextureFormat,
+};
+
+/// Expands raw pixel bytes from a storage buffer into an RGBA texture for
+/// formats the GPU cannot sample directly.
+pub struct PixelExpander {
+    pub bind_group_layout: BindGroupLayout,
+    pub layout: PipelineLayout,
+    shader: ShaderModule,
+    pipelines: HashMap<TextureFormat, RenderPipeline>,
+}
+
+impl PixelExpander {
+    pub fn new(device: &Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Pixel Expand Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(include_str!("fullscreen.wgsl"), include_str!("expand.wgsl")).into(),
+            ),
+        });
+
+        // Group 0: Uniforms + source bytes
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Expand Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Expand Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+
+        Self {
+            bind_group_layout,
+            layout,
+            shader,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    fn pipeline(&mut self, device: &Device, format: TextureFormat) -> &RenderPipeline {
+        self.pipelines.entry(format).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Pixel Expand Pipeline"),
+                layout: Some(&self.layout),
+                vertex: wgpu::VertexState {
+                    module: &self.shader,
+                    entry_point: Some("vs_fullscreen"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.shader,
+                    entry_point: Some("fs_expand"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: None,
+            })
+        })
+    }
+
+    /// Encodes expansion of `source` (rows `stride` bytes apart) into `target`.
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        format: PixelFormat,
+        stride: u32,
+        source: &Buffer,
+        target: &Texture,
+    ) {
+        let uniforms = ExpandUniforms {
+            format: match format {
+                PixelFormat::Rgb8 => 0,
+                PixelFormat::Rgba16 => 1,
+                _ => 2,
+            },
+            stride,
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Expand Uniform Buffer"),
+            contents: uniforms.as_std140().as_bytes(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: source.as_entire_binding(),
+                },
+            ],
+            label: Some("Expand BG"),
+        });
+
+        let target_view = mip_view(target, 0);
+        let pipeline = self.pipeline(device, target.format());
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Pixel Expand Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
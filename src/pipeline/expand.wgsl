@@ -0,0 +1,47 @@
+// Unpacks pixel formats without a matching GPU texture format (24-bit RGB,
+// 16-bit integer RGBA without 16-bit norm support, 32-bit float RGBA) from a
+// raw byte buffer into the resource texture.
+
+struct ExpandUniforms {
+    // EXPAND_* selector
+    format: u32,
+    // Row stride of the source data, in bytes
+    stride: u32,
+};
+
+const EXPAND_RGB8: u32 = 0u;
+const EXPAND_RGBA16: u32 = 1u;
+const EXPAND_RGBA32F: u32 = 2u;
+
+@group(0) @binding(0)
+var<uniform> expand: ExpandUniforms;
+@group(0) @binding(1)
+var<storage, read> src: array<u32>;
+
+fn load_bits(offset: u32, bits: u32) -> u32 {
+    let word = src[offset / 4u] >> ((offset % 4u) * 8u);
+    return word & ((1u << bits) - 1u);
+}
+
+@fragment
+fn fs_expand(in: FullscreenVertex) -> @location(0) vec4<f32> {
+    let p = vec2<u32>(in.position.xy);
+    switch expand.format {
+        case EXPAND_RGB8: {
+            let base = p.y * expand.stride + p.x * 3u;
+            let rgb = vec3<u32>(load_bits(base, 8u), load_bits(base + 1u, 8u), load_bits(base + 2u, 8u));
+            return vec4<f32>(vec3<f32>(rgb) / 255.0, 1.0);
+        }
+        case EXPAND_RGBA16: {
+            let base = p.y * expand.stride + p.x * 8u;
+            let lo = src[base / 4u];
+            let hi = src[base / 4u + 1u];
+            let rgba = vec4<u32>(lo & 0xffffu, lo >> 16u, hi & 0xffffu, hi >> 16u);
+            return vec4<f32>(rgba) / 65535.0;
+        }
+        default: {
+            let word = (p.y * expand.stride + p.x * 16u) / 4u;
+            return bitcast<vec4<f32>>(vec4<u32>(src[word], src[word + 1u], src[word + 2u], src[word + 3u]));
+        }
+    }
+}
@@ -0,0 +1,16 @@
+// Fullscreen vertex stage shared by the screen-space passes.
+
+struct FullscreenVertex {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Single oversized triangle covering the viewport.
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> FullscreenVertex {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: FullscreenVertex;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
@@ -0,0 +1,12 @@
+// Downsamples one mip level into the next. Sampling the source bilinearly at
+// the destination texel center averages the 2x2 block it covers.
+
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var s_source: sampler;
+
+@fragment
+fn fs_downsample(in: FullscreenVertex) -> @location(0) vec4<f32> {
+    return textureSampleLevel(t_source, s_source, in.uv, 0.0);
+}
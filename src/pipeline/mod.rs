@@ -1,7 +1,16 @@
+pub mod expand;
 pub mod geometry;
+pub mod mipmap;
+pub mod output;
+#[allow(clippy::module_inception)]
 pub mod pipeline;
 pub mod uniforms;
+pub mod yuv;
 
+pub use expand::*;
 pub use geometry::*;
+pub use mipmap::*;
+pub use output::*;
 pub use pipeline::*;
 pub use uniforms::*;
+pub use yuv::*;
@@ -0,0 +1,174 @@
+// Output transform: working space -> sink color space.
+
+struct OutputUniforms {
+    // Working primaries -> output primaries
+    gamut: mat3x3<f32>,
+    working_transfer: u32,
+    output_transfer: u32,
+    // Non-zero when the target view is an sRGB format that encodes on write.
+    srgb_target: u32,
+    reference_white: f32,
+    tone_map_operator: u32,
+    // Relative to reference white
+    source_peak: f32,
+    target_peak: f32,
+    gamut_compression: u32,
+    working_luma: vec3<f32>,
+    output_luma: vec3<f32>,
+    // Master texels per output pixel; above 1 for downscaled renditions.
+    scale: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> output: OutputUniforms;
+@group(0) @binding(1)
+var t_master: texture_2d<f32>;
+@group(0) @binding(2)
+var s_master: sampler;
+
+// IDs match `ToneMapOperator` on the CPU side.
+const TM_NONE: u32 = 0u;
+const TM_CLIP: u32 = 1u;
+const TM_REINHARD: u32 = 2u;
+const TM_HABLE: u32 = 3u;
+const TM_ACES: u32 = 4u;
+const TM_BT2390: u32 = 5u;
+
+fn hable(x: f32) -> f32 {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
+}
+
+fn aces_fit(x: f32) -> f32 {
+    let v = x * 0.6;
+    return clamp((v * (2.51 * v + 0.03)) / (v * (2.43 * v + 0.59) + 0.14), 0.0, 1.0);
+}
+
+// BT.2390 EETF: maps [0, source peak] onto [0, target peak] in the PQ domain.
+// Input and output are relative to reference white.
+fn bt2390(x: f32) -> f32 {
+    let nits = output.reference_white / PQ_PEAK_NITS;
+    let src_max = from_linear_1(output.source_peak * nits, TF_PQ);
+    let dst_max = from_linear_1(output.target_peak * nits, TF_PQ);
+
+    let e1 = from_linear_1(x * nits, TF_PQ) / src_max;
+    let max_lum = dst_max / src_max;
+    let ks = 1.5 * max_lum - 0.5;
+
+    var e2 = e1;
+    if e1 > ks {
+        let t = (e1 - ks) / (1.0 - ks);
+        let t2 = t * t;
+        let t3 = t2 * t;
+        e2 = (2.0 * t3 - 3.0 * t2 + 1.0) * ks + (t3 - 2.0 * t2 + t) * (1.0 - ks)
+            + (-2.0 * t3 + 3.0 * t2) * max_lum;
+    }
+    return to_linear_1(min(e2, max_lum) * src_max, TF_PQ) / nits;
+}
+
+// Maps a max-channel value relative to reference white into [0, target peak].
+fn tone_curve(v: f32) -> f32 {
+    let x = v / output.target_peak;
+    let peak = output.source_peak / output.target_peak;
+    var y = x;
+    switch output.tone_map_operator {
+        case TM_CLIP: {
+            y = min(x, 1.0);
+        }
+        case TM_REINHARD: {
+            y = min(x * (1.0 + x / (peak * peak)) / (1.0 + x), 1.0);
+        }
+        case TM_HABLE: {
+            y = min(hable(x * 2.0) / hable(peak * 2.0), 1.0);
+        }
+        case TM_ACES: {
+            y = aces_fit(x);
+        }
+        case TM_BT2390: {
+            return bt2390(v);
+        }
+        default: {}
+    }
+    return y * output.target_peak;
+}
+
+// Simplified ACES reference gamut compression: distances from the achromatic
+// axis past the threshold are rolled off so they reach the gamut boundary at `limit`.
+fn compress_distance(d: f32) -> f32 {
+    let threshold = 0.8;
+    let limit = 1.2;
+    let power = 1.2;
+    if d < threshold {
+        return d;
+    }
+    let scale = (limit - threshold)
+        / pow(pow((1.0 - threshold) / (limit - threshold), -power) - 1.0, 1.0 / power);
+    let n = (d - threshold) / scale;
+    return threshold + scale * n / pow(1.0 + pow(n, power), 1.0 / power);
+}
+
+fn compress_gamut(rgb: vec3<f32>) -> vec3<f32> {
+    let ach = max(rgb.r, max(rgb.g, rgb.b));
+    if ach <= 0.0 {
+        return rgb;
+    }
+    let d = (vec3<f32>(ach) - rgb) / ach;
+    let cd = vec3<f32>(compress_distance(d.r), compress_distance(d.g), compress_distance(d.b));
+    return vec3<f32>(ach) - cd * ach;
+}
+
+fn tone_map(rgb: vec3<f32>) -> vec3<f32> {
+    var c = rgb;
+    if output.gamut_compression != 0u {
+        c = compress_gamut(c);
+    }
+    let m = max(c.r, max(c.g, c.b));
+    if m <= 0.0 {
+        return c;
+    }
+    return c * (tone_curve(m) / m);
+}
+
+// Most bilinear taps per axis when downscaling, i.e. up to 16x reduction
+// before the filter starts to alias.
+const MAX_TAPS: f32 = 8.0;
+
+// Box filter over the master texels covered by this output pixel. Each
+// bilinear tap already averages two texels per axis.
+fn sample_master(uv: vec2<f32>) -> vec4<f32> {
+    let taps = clamp(ceil(output.scale * 0.5), vec2<f32>(1.0), vec2<f32>(MAX_TAPS));
+    if taps.x == 1.0 && taps.y == 1.0 {
+        return textureSampleLevel(t_master, s_master, uv, 0.0);
+    }
+    let pixel = output.scale / vec2<f32>(textureDimensions(t_master));
+    var sum = vec4<f32>(0.0);
+    for (var y = 0.0; y < taps.y; y += 1.0) {
+        for (var x = 0.0; x < taps.x; x += 1.0) {
+            let offset = (vec2<f32>(x, y) + 0.5) / taps - 0.5;
+            sum += textureSampleLevel(t_master, s_master, uv + offset * pixel, 0.0);
+        }
+    }
+    return sum / (taps.x * taps.y);
+}
+
+@fragment
+fn fs_output(in: FullscreenVertex) -> @location(0) vec4<f32> {
+    let color = sample_master(in.uv);
+
+    let working = decode(color.rgb, output.working_transfer, output.reference_white, output.working_luma);
+    var linear = output.gamut * working;
+    if output.tone_map_operator != TM_NONE {
+        linear = tone_map(linear);
+    }
+    var encoded = encode(linear, output.output_transfer, output.reference_white, output.output_luma);
+    if output.srgb_target != 0u {
+        // The hardware re-applies the sRGB curve on store.
+        encoded = to_linear(encoded, TF_SRGB);
+    }
+    return vec4<f32>(encoded, color.a);
+}
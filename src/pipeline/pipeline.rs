@@ -1,6 +1,10 @@
 use super::geometry::VideoVertex;
 use wgpu::{Device, PipelineLayout, RenderPipeline, TextureFormat};
 
+/// Format of the intermediate frame layers are composited into.
+/// Float so working-space values outside [0, 1] survive until the output transform.
+pub const WORKING_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
 pub struct CompositionPipeline {
     pub pipeline: RenderPipeline,
     pub layout: PipelineLayout,
@@ -11,7 +15,12 @@ pub struct CompositionPipeline {
 impl CompositionPipeline {
     pub fn new(device: &Device, format: TextureFormat) -> Self {
         // 1. Shaders
-        let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Composition Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(include_str!("color.wgsl"), include_str!("shader.wgsl")).into(),
+            ),
+        });
 
         // 2. Bind Group Layouts
         // Group 0: Uniforms (Transform + Opacity)
@@ -6,11 +6,41 @@ struct VertexOutput {
 
 struct LayerUniforms {
     transform: mat4x4<f32>,
-    opacity: f32, // Passed but need padding handling on CPU side?
-    // In WGSL, uniform buffers usually need specific alignment.
-    // crevice std140 will handle it.
+    opacity: f32,
+    // Input primaries -> working primaries
+    gamut: mat3x3<f32>,
+    input_transfer: u32,
+    working_transfer: u32,
+    reference_white: f32,
+    // Inverse tone mapping for SDR inputs; disabled when peak is 0
+    itm_peak: f32,
+    itm_knee: f32,
+    // How texture channels map to RGBA, see CHANNELS_*
+    channels: u32,
+    // Non-zero when the texture stores premultiplied color
+    premultiplied: u32,
+    // RESAMPLE_* filter
+    resampling: u32,
+    // Luminance coefficients of the working primaries
+    luma: vec3<f32>,
+    // Layout matches crevice std140 on the CPU side.
 };
 
+// Shared with `PixelFormat::channel_mode`
+const CHANNELS_RGBA: u32 = 0u;
+const CHANNELS_GRAY: u32 = 1u;
+const CHANNELS_ALPHA: u32 = 2u;
+
+// Shared with `ResamplingQuality`
+const RESAMPLE_NEAREST: u32 = 0u;
+const RESAMPLE_BILINEAR: u32 = 1u;
+const RESAMPLE_BICUBIC: u32 = 2u;
+const RESAMPLE_LANCZOS: u32 = 3u;
+// Widest kernel stretch left after mip selection; bounds the tap count
+// when a source without mips is heavily downscaled.
+const MAX_KERNEL_SCALE: f32 = 2.0;
+const PI: f32 = 3.14159265;
+
 @group(0) @binding(0)
 var<uniform> uniforms: LayerUniforms;
 
@@ -19,6 +49,102 @@ var t_diffuse: texture_2d<f32>;
 @group(1) @binding(1)
 var s_diffuse: sampler;
 
+// Stretches SDR luminance above the knee so SDR white reaches `itm_peak`,
+// keeping the slope continuous at the knee.
+fn expand_sdr(rgb: vec3<f32>) -> vec3<f32> {
+    let y = dot(uniforms.luma, rgb);
+    let knee = uniforms.itm_knee;
+    if y <= knee {
+        return rgb;
+    }
+    let t = min((y - knee) / (1.0 - knee), 1.0);
+    let a = (uniforms.itm_peak - knee) / (1.0 - knee) - 1.0;
+    let expanded = knee + (1.0 - knee) * (t + a * t * t);
+    return rgb * (expanded / y);
+}
+
+// Catmull-Rom cubic, support 2.
+fn cubic_weight(x: f32) -> f32 {
+    let a = abs(x);
+    if a < 1.0 {
+        return (1.5 * a - 2.5) * a * a + 1.0;
+    }
+    if a < 2.0 {
+        return ((-0.5 * a + 2.5) * a - 4.0) * a + 2.0;
+    }
+    return 0.0;
+}
+
+fn sinc(x: f32) -> f32 {
+    if abs(x) < 1e-4 {
+        return 1.0;
+    }
+    let px = PI * x;
+    return sin(px) / px;
+}
+
+// Lanczos-3, support 3.
+fn lanczos_weight(x: f32) -> f32 {
+    if abs(x) >= 3.0 {
+        return 0.0;
+    }
+    return sinc(x) * sinc(x / 3.0);
+}
+
+fn kernel_weight(x: f32, lanczos: bool) -> f32 {
+    if lanczos {
+        return lanczos_weight(x);
+    }
+    return cubic_weight(x);
+}
+
+// Mip level covering `footprint` level-0 texels per pixel.
+fn footprint_level(footprint: vec2<f32>) -> f32 {
+    let max_lod = f32(textureNumLevels(t_diffuse) - 1u);
+    return clamp(floor(log2(max(max(footprint.x, footprint.y), 1.0))), 0.0, max_lod);
+}
+
+// Point sampling through texel loads, since GL can't pair one texture with
+// two samplers.
+fn sample_nearest(uv: vec2<f32>, footprint: vec2<f32>) -> vec4<f32> {
+    let level = i32(footprint_level(footprint));
+    let dims = vec2<i32>(textureDimensions(t_diffuse, level));
+    let texel = clamp(vec2<i32>(floor(uv * vec2<f32>(dims))), vec2<i32>(0), dims - 1);
+    return textureLoad(t_diffuse, texel, level);
+}
+
+// Convolves the texture with a separable kernel. When downscaling, the mip
+// level closest to the output size takes most of the reduction and the kernel
+// is stretched by the rest, so every covered texel contributes.
+// `footprint` is the number of level-0 texels per output pixel on each axis.
+fn sample_kernel(uv: vec2<f32>, footprint: vec2<f32>, lanczos: bool) -> vec4<f32> {
+    let support = select(2.0, 3.0, lanczos);
+    let lod = footprint_level(footprint);
+    let level = i32(lod);
+    let dims = vec2<i32>(textureDimensions(t_diffuse, level));
+    let scale = clamp(footprint / exp2(lod), vec2<f32>(1.0), vec2<f32>(MAX_KERNEL_SCALE));
+
+    let center = uv * vec2<f32>(dims) - 0.5;
+    let base = vec2<i32>(floor(center));
+    let radius = vec2<i32>(ceil(support * scale));
+
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var dy = 1 - radius.y; dy <= radius.y; dy++) {
+        let y = base.y + dy;
+        let wy = kernel_weight((center.y - f32(y)) / scale.y, lanczos);
+        for (var dx = 1 - radius.x; dx <= radius.x; dx++) {
+            let x = base.x + dx;
+            let w = wy * kernel_weight((center.x - f32(x)) / scale.x, lanczos);
+            let texel = clamp(vec2<i32>(x, y), vec2<i32>(0), dims - 1);
+            sum += textureLoad(t_diffuse, texel, level) * w;
+            total += w;
+        }
+    }
+    // Negative lobes can ring below zero around hard edges
+    return max(sum / total, vec4<f32>(0.0));
+}
+
 @vertex
 fn vs_main(
     @location(0) position: vec3<f32>, // Changed to vec3 to match VideoVertex
@@ -32,7 +158,46 @@ fn vs_main(
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var color = textureSample(t_diffuse, s_diffuse, in.uv);
+    // Texels per output pixel, for scale-aware kernels
+    let texel_uv = in.uv * vec2<f32>(textureDimensions(t_diffuse));
+    let dx = dpdx(texel_uv);
+    let dy = dpdy(texel_uv);
+    let footprint = vec2<f32>(length(vec2<f32>(dx.x, dy.x)), length(vec2<f32>(dx.y, dy.y)));
+
+    var color: vec4<f32>;
+    switch uniforms.resampling {
+        case RESAMPLE_NEAREST: {
+            color = sample_nearest(in.uv, footprint);
+        }
+        case RESAMPLE_BICUBIC: {
+            color = sample_kernel(in.uv, footprint, false);
+        }
+        case RESAMPLE_LANCZOS: {
+            color = sample_kernel(in.uv, footprint, true);
+        }
+        default: {
+            color = textureSample(t_diffuse, s_diffuse, in.uv);
+        }
+    }
+    if uniforms.channels == CHANNELS_GRAY {
+        color = vec4<f32>(color.rrr, 1.0);
+    } else if uniforms.channels == CHANNELS_ALPHA {
+        color = vec4<f32>(vec3<f32>(1.0), color.r);
+    }
+    // Transfer functions apply to straight color
+    if uniforms.premultiplied != 0u && color.a > 0.0 {
+        color = vec4<f32>(color.rgb / color.a, color.a);
+    }
+
+    // Input space -> working space
+    var linear = uniforms.gamut
+        * decode(color.rgb, uniforms.input_transfer, uniforms.reference_white, uniforms.luma);
+    if uniforms.itm_peak > 0.0 {
+        linear = expand_sdr(linear);
+    }
+    let encoded = encode(linear, uniforms.working_transfer, uniforms.reference_white, uniforms.luma);
+    color = vec4<f32>(encoded, color.a);
+
     color.a = color.a * uniforms.opacity;
     return color;
 }
@@ -1,5 +1,8 @@
 use crevice::std140::AsStd140;
 
+// crevice pads vec3 to 16 bytes while WGSL packs a following scalar into the
+// tail, so vec3 fields go last (or before another 16-aligned field).
+
 #[derive(AsStd140)]
 pub struct LayerUniforms {
     pub transform: mint::ColumnMatrix4<f32>,
@@ -7,6 +10,75 @@ pub struct LayerUniforms {
     // Padding to strict 16-byte alignment is handled by crevice/mint usually,
     // but opacity is f32 (4 bytes). Next might be start of struct or padding.
     // crevice handles padding.
+    /// Input primaries -> working primaries.
+    pub gamut: mint::ColumnMatrix3<f32>,
+    pub input_transfer: u32,
+    pub working_transfer: u32,
+    pub reference_white: f32,
+    /// Inverse tone mapping target, relative to reference white. 0 disables it.
+    pub itm_peak: f32,
+    pub itm_knee: f32,
+    /// `PixelFormat::channel_mode` of the source texture.
+    pub channels: u32,
+    pub premultiplied: u32,
+    /// `ResamplingQuality` of the layer.
+    pub resampling: u32,
+    pub luma: mint::Vector3<f32>,
+}
+
+#[derive(AsStd140)]
+pub struct OutputUniforms {
+    /// Working primaries -> sink primaries.
+    pub gamut: mint::ColumnMatrix3<f32>,
+    pub working_transfer: u32,
+    pub output_transfer: u32,
+    pub srgb_target: u32,
+    pub reference_white: f32,
+    /// `ToneMapOperator` id, 0 when tone mapping is off.
+    pub tone_map_operator: u32,
+    /// Source and target peaks, relative to reference white.
+    pub source_peak: f32,
+    pub target_peak: f32,
+    pub gamut_compression: u32,
+    pub working_luma: mint::Vector3<f32>,
+    pub output_luma: mint::Vector3<f32>,
+    /// Master texels per output pixel on each axis.
+    pub scale: mint::Vector2<f32>,
+}
+
+#[derive(AsStd140)]
+pub struct YuvUniforms {
+    pub matrix: mint::ColumnMatrix3<f32>,
+    pub scale: mint::Vector4<f32>,
+    pub offset: mint::Vector4<f32>,
+    pub chroma_offset: mint::Vector2<f32>,
+    pub chroma_shift: mint::Vector2<u32>,
+    pub code_shift: u32,
+    pub semi_planar: u32,
+}
+
+#[derive(AsStd140)]
+pub struct YuvPackUniforms {
+    pub matrix: mint::ColumnMatrix3<f32>,
+    pub scale: mint::Vector4<f32>,
+    pub offset: mint::Vector4<f32>,
+    pub chroma_offset: mint::Vector2<f32>,
+    pub chroma_shift: mint::Vector2<u32>,
+    pub plane: u32,
+    pub bytes_per_sample: u32,
+    pub code_shift: u32,
+    pub max_code: u32,
+    pub row_words: u32,
+    pub row_samples: u32,
+    pub rows: u32,
+    pub word_offset: u32,
+    pub chroma_filter: u32,
+}
+
+#[derive(AsStd140)]
+pub struct ExpandUniforms {
+    pub format: u32,
+    pub stride: u32,
 }
 
 // Need to add mint to dependencies since crevice uses it for types
@@ -0,0 +1,372 @@
+use super::mipmap::mip_view;
+use super::uniforms::{YuvPackUniforms, YuvUniforms};
+use crate::resources::yuv::{ChromaFilter, YuvConversion, YuvFormat};
+use crevice::std140::AsStd140;
+use std::collections::HashMap;
+use wgpu::util::DeviceExt;
+use wgpu::{
+    BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device, PipelineLayout,
+    RenderPipeline, ShaderModule, Texture, TextureFormat, TextureView,
+};
+
+/// Converts per-plane Y'CbCr textures into an RGBA texture on the GPU.
+pub struct YuvConverter {
+    pub bind_group_layout: BindGroupLayout,
+    pub layout: PipelineLayout,
+    shader: ShaderModule,
+    pipelines: HashMap<TextureFormat, RenderPipeline>,
+}
+
+impl YuvConverter {
+    pub fn new(device: &Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("YUV Conversion Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(include_str!("fullscreen.wgsl"), include_str!("yuv.wgsl")).into(),
+            ),
+        });
+
+        let plane_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Uint,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        // Group 0: Uniforms + Y, U (or UV), V planes
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("YUV Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                plane_entry(1),
+                plane_entry(2),
+                plane_entry(3),
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("YUV Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+
+        Self {
+            bind_group_layout,
+            layout,
+            shader,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    fn pipeline(&mut self, device: &Device, format: TextureFormat) -> &RenderPipeline {
+        self.pipelines.entry(format).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("YUV Conversion Pipeline"),
+                layout: Some(&self.layout),
+                vertex: wgpu::VertexState {
+                    module: &self.shader,
+                    entry_point: Some("vs_fullscreen"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.shader,
+                    entry_point: Some("fs_yuv"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: None,
+            })
+        })
+    }
+
+    /// Encodes the conversion of `planes` into `target`. Planes must already
+    /// hold the frame's data.
+    pub fn encode(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        format: YuvFormat,
+        conversion: YuvConversion,
+        planes: &[Texture],
+        target: &Texture,
+    ) {
+        let bits = format.bit_depth();
+        let (scale, offset) = conversion.range.code_scale_offset(bits);
+        let shift = format.chroma_shift();
+        let (offset_x, offset_y) = conversion.siting.offset(shift);
+
+        let uniforms = YuvUniforms {
+            matrix: conversion.matrix.to_rgb().to_cols_array_2d().into(),
+            scale: scale.extend(0.0).to_array().into(),
+            offset: offset.extend(0.0).to_array().into(),
+            chroma_offset: [offset_x, offset_y].into(),
+            chroma_shift: [shift.0, shift.1].into(),
+            // Samples are stored in the MSBs of each word
+            code_shift: format.code_shift(),
+            semi_planar: format.is_semi_planar() as u32,
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("YUV Uniform Buffer"),
+            contents: uniforms.as_std140().as_bytes(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let views: Vec<_> = planes
+            .iter()
+            .map(|p| p.create_view(&wgpu::TextureViewDescriptor::default()))
+            .collect();
+        // Semi-planar formats bind the CbCr plane twice; the shader ignores binding 3
+        let v_view = views.get(2).unwrap_or(&views[1]);
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&views[0]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&views[1]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(v_view),
+                },
+            ],
+            label: Some("YUV BG"),
+        });
+
+        let target_view = mip_view(target, 0);
+        let pipeline = self.pipeline(device, target.format());
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("YUV Conversion Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+/// Where one plane lives in a packed Y'CbCr buffer. Rows are padded to whole
+/// 32-bit words.
+#[derive(Debug, Clone, Copy)]
+pub struct PackedPlane {
+    pub word_offset: u32,
+    pub row_words: u32,
+    pub rows: u32,
+    /// Tightly packed row size in bytes.
+    pub row_bytes: u32,
+}
+
+/// Buffer layout of all planes of a `width` x `height` frame.
+pub fn packed_planes(format: YuvFormat, width: u32, height: u32) -> Vec<PackedPlane> {
+    let mut word_offset = 0;
+    (0..format.plane_count())
+        .map(|index| {
+            let (_, rows) = format.plane_size(index, width, height);
+            let row_bytes = format.plane_row_bytes(index, width);
+            let row_words = row_bytes.div_ceil(4);
+            let plane = PackedPlane {
+                word_offset,
+                row_words,
+                rows,
+                row_bytes,
+            };
+            word_offset += row_words * rows;
+            plane
+        })
+        .collect()
+}
+
+/// Compute pass converting an R'G'B' texture into packed Y'CbCr planes.
+pub struct YuvPacker {
+    pub bind_group_layout: BindGroupLayout,
+    pub pipeline: ComputePipeline,
+}
+
+impl YuvPacker {
+    pub fn new(device: &Device) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("yuv_pack.wgsl"));
+
+        // Group 0: Uniforms + RGB source + packed planes
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("YUV Pack Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("YUV Pack Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("YUV Pack Pipeline"),
+            layout: Some(&layout),
+            module: &shader,
+            entry_point: Some("cs_pack"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        Self {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    /// Encodes packing of `source` (R'G'B', `width` x `height`) into `planes`,
+    /// laid out as described by `packed_planes`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        format: YuvFormat,
+        conversion: YuvConversion,
+        filter: ChromaFilter,
+        (width, height): (u32, u32),
+        source: &TextureView,
+        planes: &Buffer,
+    ) {
+        let bits = format.bit_depth();
+        let bytes_per_sample = bits.div_ceil(8);
+        // Inverse of the decode scale/offset: code = value * scale + offset
+        let (scale, offset) = conversion.range.code_scale_offset(bits);
+        let (code_scale, code_offset) = (scale.recip(), -offset / scale);
+        let shift = format.chroma_shift();
+        let (offset_x, offset_y) = conversion.siting.offset(shift);
+
+        let layout = packed_planes(format, width, height);
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("YUV Pack Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+
+        for (index, plane) in layout.iter().enumerate() {
+            let (plane_width, _) = format.plane_size(index, width, height);
+            let (selector, row_samples) = match index {
+                0 => (0, plane_width),
+                _ if format.is_semi_planar() => (3, plane_width * 2),
+                _ => (index as u32, plane_width),
+            };
+            let chroma_shift = if index == 0 { (0, 0) } else { shift };
+
+            let uniforms = YuvPackUniforms {
+                matrix: conversion.matrix.from_rgb().to_cols_array_2d().into(),
+                scale: code_scale.extend(0.0).to_array().into(),
+                offset: code_offset.extend(0.0).to_array().into(),
+                chroma_offset: [offset_x, offset_y].into(),
+                chroma_shift: [chroma_shift.0, chroma_shift.1].into(),
+                plane: selector,
+                bytes_per_sample,
+                code_shift: format.code_shift(),
+                max_code: (1 << bits) - 1,
+                row_words: plane.row_words,
+                row_samples,
+                rows: plane.rows,
+                word_offset: plane.word_offset,
+                chroma_filter: filter as u32,
+            };
+            let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("YUV Pack Uniform Buffer"),
+                contents: uniforms.as_std140().as_bytes(),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: planes.as_entire_binding(),
+                    },
+                ],
+                label: Some("YUV Pack BG"),
+            });
+
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(plane.row_words.div_ceil(64), plane.rows, 1);
+        }
+    }
+}
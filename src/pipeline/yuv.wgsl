@@ -0,0 +1,56 @@
+// Planar Y'CbCr -> R'G'B' conversion, run once per upload at luma resolution.
+
+struct YuvUniforms {
+    // (Y', Cb, Cr) -> R'G'B'
+    matrix: mat3x3<f32>,
+    // Integer code -> Y' in [0, 1], CbCr in [-0.5, 0.5]
+    scale: vec4<f32>,
+    offset: vec4<f32>,
+    // Position of the first chroma sample, in luma pixels
+    chroma_offset: vec2<f32>,
+    // log2 chroma subsampling
+    chroma_shift: vec2<u32>,
+    // Padding bits below the sample (6 for P010)
+    code_shift: u32,
+    // Non-zero when plane 1 holds interleaved CbCr
+    semi_planar: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> yuv: YuvUniforms;
+@group(0) @binding(1)
+var t_y: texture_2d<u32>;
+@group(0) @binding(2)
+var t_u: texture_2d<u32>;
+@group(0) @binding(3)
+var t_v: texture_2d<u32>;
+
+fn load_chroma(c: vec2<i32>) -> vec2<f32> {
+    let size = vec2<i32>(textureDimensions(t_u));
+    let p = clamp(c, vec2<i32>(0), size - vec2<i32>(1));
+    if yuv.semi_planar != 0u {
+        return vec2<f32>(textureLoad(t_u, p, 0).rg >> vec2<u32>(yuv.code_shift));
+    }
+    let u = textureLoad(t_u, p, 0).r >> yuv.code_shift;
+    let v = textureLoad(t_v, p, 0).r >> yuv.code_shift;
+    return vec2<f32>(f32(u), f32(v));
+}
+
+@fragment
+fn fs_yuv(in: FullscreenVertex) -> @location(0) vec4<f32> {
+    let p = vec2<i32>(in.position.xy);
+    let y = f32(textureLoad(t_y, p, 0).r >> yuv.code_shift);
+
+    // Bilinear chroma upsampling honoring the siting
+    let factor = vec2<f32>(vec2<u32>(1u) << yuv.chroma_shift);
+    let c = (vec2<f32>(p) - yuv.chroma_offset) / factor;
+    let c0 = floor(c);
+    let f = c - c0;
+    let i0 = vec2<i32>(c0);
+    let top = mix(load_chroma(i0), load_chroma(i0 + vec2<i32>(1, 0)), f.x);
+    let bottom = mix(load_chroma(i0 + vec2<i32>(0, 1)), load_chroma(i0 + vec2<i32>(1, 1)), f.x);
+    let chroma = mix(top, bottom, f.y);
+
+    let ycbcr = vec3<f32>(y, chroma) * yuv.scale.xyz + yuv.offset.xyz;
+    return vec4<f32>(yuv.matrix * ycbcr, 1.0);
+}
@@ -0,0 +1,131 @@
+// R'G'B' -> packed Y'CbCr planes. One invocation writes one 32-bit word of a
+// plane, so 8-bit planes pack four samples per word and 16-bit planes two.
+
+struct PackUniforms {
+    // R'G'B' -> (Y', Cb, Cr)
+    matrix: mat3x3<f32>,
+    // (Y', Cb, Cr) -> integer code
+    scale: vec4<f32>,
+    offset: vec4<f32>,
+    // Position of the first chroma sample, in luma pixels
+    chroma_offset: vec2<f32>,
+    // log2 chroma subsampling; zero for the luma plane
+    chroma_shift: vec2<u32>,
+    // PLANE_* selector
+    plane: u32,
+    bytes_per_sample: u32,
+    // Padding bits below the sample (6 for P010)
+    code_shift: u32,
+    max_code: u32,
+    row_words: u32,
+    // Samples per row (twice the width for interleaved CbCr)
+    row_samples: u32,
+    rows: u32,
+    word_offset: u32,
+    chroma_filter: u32,
+};
+
+const PLANE_Y: u32 = 0u;
+const PLANE_CB: u32 = 1u;
+const PLANE_CR: u32 = 2u;
+const PLANE_CBCR: u32 = 3u;
+
+// IDs match `ChromaFilter` on the CPU side.
+const FILTER_NEAREST: u32 = 0u;
+const FILTER_BOX: u32 = 1u;
+const FILTER_TENT: u32 = 2u;
+
+@group(0) @binding(0)
+var<uniform> pack: PackUniforms;
+@group(0) @binding(1)
+var t_rgb: texture_2d<f32>;
+@group(0) @binding(2)
+var<storage, read_write> planes: array<u32>;
+
+fn load_rgb(p: vec2<i32>) -> vec3<f32> {
+    let size = vec2<i32>(textureDimensions(t_rgb));
+    return textureLoad(t_rgb, clamp(p, vec2<i32>(0), size - vec2<i32>(1)), 0).rgb;
+}
+
+// Tent weight of pixel `x` for a site at `s` with radius `r`.
+fn tent(x: f32, s: f32, r: f32) -> f32 {
+    return max(0.0, 1.0 - abs(x - s) / r);
+}
+
+// Filtered R'G'B' for the chroma sample at `c`.
+fn chroma_rgb(c: vec2<i32>) -> vec3<f32> {
+    let factor = vec2<i32>(vec2<u32>(1u) << pack.chroma_shift);
+    let site = vec2<f32>(c * factor) + pack.chroma_offset;
+
+    switch pack.chroma_filter {
+        case FILTER_BOX: {
+            var sum = vec3<f32>(0.0);
+            for (var y = 0; y < factor.y; y++) {
+                for (var x = 0; x < factor.x; x++) {
+                    sum += load_rgb(c * factor + vec2<i32>(x, y));
+                }
+            }
+            return sum / f32(factor.x * factor.y);
+        }
+        case FILTER_TENT: {
+            let radius = vec2<f32>(factor);
+            let lo = vec2<i32>(ceil(site - radius));
+            let hi = vec2<i32>(floor(site + radius));
+            var sum = vec3<f32>(0.0);
+            var weight = 0.0;
+            for (var y = lo.y; y <= hi.y; y++) {
+                for (var x = lo.x; x <= hi.x; x++) {
+                    let w = tent(f32(x), site.x, radius.x) * tent(f32(y), site.y, radius.y);
+                    sum += load_rgb(vec2<i32>(x, y)) * w;
+                    weight += w;
+                }
+            }
+            return sum / max(weight, 1e-6);
+        }
+        default: {
+            return load_rgb(vec2<i32>(floor(site + vec2<f32>(0.5))));
+        }
+    }
+}
+
+fn to_code(v: f32, channel: u32) -> u32 {
+    let code = round(v * pack.scale[channel] + pack.offset[channel]);
+    return u32(clamp(code, 0.0, f32(pack.max_code)));
+}
+
+fn sample_code(element: u32, row: u32) -> u32 {
+    if pack.plane == PLANE_Y {
+        let ycbcr = pack.matrix * load_rgb(vec2<i32>(i32(element), i32(row)));
+        return to_code(ycbcr.x, 0u);
+    }
+
+    var channel = pack.plane;
+    var x = element;
+    if pack.plane == PLANE_CBCR {
+        channel = 1u + element % 2u;
+        x = element / 2u;
+    }
+    let ycbcr = pack.matrix * chroma_rgb(vec2<i32>(i32(x), i32(row)));
+    return to_code(ycbcr[channel], channel);
+}
+
+@compute @workgroup_size(64, 1, 1)
+fn cs_pack(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let word_x = gid.x;
+    let row = gid.y;
+    if word_x >= pack.row_words || row >= pack.rows {
+        return;
+    }
+
+    let per_word = 4u / pack.bytes_per_sample;
+    var word = 0u;
+    for (var k = 0u; k < per_word; k++) {
+        let element = word_x * per_word + k;
+        if element >= pack.row_samples {
+            break;
+        }
+        let code = sample_code(element, row) << pack.code_shift;
+        word |= code << (k * pack.bytes_per_sample * 8u);
+    }
+    planes[pack.word_offset + row * pack.row_words + word_x] = word;
+}
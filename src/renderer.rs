@@ -1,25 +1,33 @@
 use crate::core::RenderContext;
-use crate::model::FrameDescription;
+use crate::model::{ColorSpace, FrameDescription};
 use crate::outputs::RenderSink;
-use crate::pipeline::{CompositionPipeline, LayerUniforms, QUAD_INDICES, QUAD_VERTICES};
-use crate::resources::TextureManager;
+use crate::pipeline::{
+    CompositionPipeline, LayerUniforms, OutputPipeline, OutputUniforms, QUAD_INDICES,
+    QUAD_VERTICES, WORKING_FORMAT,
+};
+use crate::resources::{AlphaMode, TextureKey, TextureManager, TexturePool};
 use crevice::std140::AsStd140;
 pub use glam::Mat4; // Exposed for internal use, though tests should use glam dependency directly
 use wgpu::util::DeviceExt;
-use wgpu::{CommandEncoder, Device, Queue, TextureView};
+use wgpu::{Texture, TextureView};
 
 pub struct Renderer {
     pipeline: CompositionPipeline,
+    output_pipeline: OutputPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     sampler: wgpu::Sampler,
+    anisotropic_sampler: wgpu::Sampler,
+    // Working-space frame, reallocated when the composition size changes
+    master: Option<(Texture, TextureView)>,
 }
 
 impl Renderer {
     pub fn new(context: &RenderContext) -> Self {
-        // Init pipeline
-        let format = wgpu::TextureFormat::Rgba8Unorm; // Match BufferSink
-        let pipeline = CompositionPipeline::new(&context.device, format);
+        // Init pipelines: layers composite into the working format, the output
+        // transform writes whatever format each sink asks for.
+        let pipeline = CompositionPipeline::new(&context.device, WORKING_FORMAT);
+        let output_pipeline = OutputPipeline::new(&context.device);
 
         // Init geometry buffers
         let vertex_buffer = context
@@ -38,59 +46,127 @@ impl Renderer {
                 usage: wgpu::BufferUsages::INDEX,
             });
 
-        // Init Sampler (Linear for smooth scaling)
+        // Init Samplers (Linear for smooth scaling, anisotropic for resources with mips)
         let sampler = context.device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::MipmapFilterMode::Nearest, // Wait, error said `expected MipmapFilterMode, found FilterMode`?
-            // In wgpu 0.28, is it MipmapFilterMode? Or did I use FilterMode enum?
-            // Usually it is FilterMode. Let's check if there is a separate enum.
-            // `wgpu::FilterMode` has `Nearest`, `Linear`.
-            // If error says `expected MipmapFilterMode`, then wgpu 0.28 split it.
-            // Let's try `wgpu::MipmapFilterMode::Nearest`.
-
-            // Also fixing RenderPassDescriptor missing multiview_mask
+            ..Default::default()
+        });
+        let anisotropic_sampler = context.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::MipmapFilterMode::Linear,
+            anisotropy_clamp: 16,
             ..Default::default()
         });
 
         Self {
             pipeline,
+            output_pipeline,
             vertex_buffer,
             index_buffer,
             sampler,
+            anisotropic_sampler,
+            master: None,
         }
     }
 
+    /// Returns the working-space target for a frame of the given size,
+    /// swapping it through `pool` when the size changes.
+    fn master_view(
+        &mut self,
+        context: &RenderContext,
+        pool: &TexturePool,
+        (width, height): (u32, u32),
+    ) -> TextureView {
+        let reusable = self
+            .master
+            .as_ref()
+            .is_some_and(|(tex, _)| tex.width() == width && tex.height() == height);
+
+        if !reusable {
+            if let Some((texture, _)) = self.master.take() {
+                pool.release(texture);
+            }
+            let texture = pool.acquire(
+                &context.device,
+                "Master Frame",
+                TextureKey::new(
+                    width,
+                    height,
+                    WORKING_FORMAT,
+                    wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                ),
+            );
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.master = Some((texture, view));
+        }
+
+        self.master.as_ref().map(|(_, view)| view.clone()).unwrap()
+    }
+
+    /// Composites `composition` once and writes it to every sink, each in
+    /// its own format, color space and size. Sinks smaller than the frame
+    /// get a filtered downscale of the same composite, so a preview, an
+    /// export and a ladder of renditions cost a single composition pass.
+    /// Sinks of a different aspect ratio are stretched to fill.
+    ///
+    /// All sinks are prepared before anything is drawn; if one fails, the
+    /// frame is not rendered to any of them, and those already prepared
+    /// discard theirs.
     pub fn render(
         &mut self,
         context: &RenderContext,
         texture_manager: &TextureManager,
         composition: &FrameDescription,
-        sink: &mut dyn RenderSink,
+        sinks: &mut [&mut dyn RenderSink],
     ) -> Result<(), crate::core::RenderError> {
+        let mut output_views = Vec::with_capacity(sinks.len());
+        for i in 0..sinks.len() {
+            match sinks[i].prepare_frame() {
+                Ok(view) => output_views.push(view),
+                Err(e) => {
+                    for sink in &mut sinks[..i] {
+                        sink.discard_frame();
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
         let mut encoder = context
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
             });
 
-        let output_view = sink.prepare_frame()?;
+        let working_space = composition.working_space;
+        let working_luma = working_space.primaries.luma_coefficients();
+        let reference_white = composition.reference_white_nits;
+        let master_view = self.master_view(context, texture_manager.pool(), composition.dimensions);
+
+        // Background is authored as sRGB; bring it into the working space.
+        let [bg_r, bg_g, bg_b, bg_a] = composition.background_color;
+        let [bg_r, bg_g, bg_b] = ColorSpace::SRGB.convert_rgb(working_space, [bg_r, bg_g, bg_b]);
 
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Composition Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &output_view,
+                    view: &master_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: composition.background_color[0] as f64,
-                            g: composition.background_color[1] as f64,
-                            b: composition.background_color[2] as f64,
-                            a: composition.background_color[3] as f64,
+                            r: bg_r as f64,
+                            g: bg_g as f64,
+                            b: bg_b as f64,
+                            a: bg_a as f64,
                         }),
                         store: wgpu::StoreOp::Store,
                     },
@@ -137,9 +213,32 @@ impl Renderer {
                     // Final MVP = Projection * Model
                     let transform_final = projection * model_matrix;
 
+                    // Only display-referred SDR sources get expanded
+                    let (itm_peak, itm_knee) = match composition.inverse_tone_mapping {
+                        Some(itm) if res.color_space.transfer.is_sdr() => {
+                            (itm.peak_nits / reference_white, itm.knee)
+                        }
+                        _ => (0.0, 0.0),
+                    };
+
+                    let gamut = res
+                        .color_space
+                        .primaries
+                        .conversion_to(working_space.primaries);
+
                     let uniforms = LayerUniforms {
                         transform: transform_final.to_cols_array_2d().into(), // Convert to mint::ColumnMatrix4 via array
                         opacity: layer.opacity,
+                        gamut: gamut.to_cols_array_2d().into(),
+                        input_transfer: res.color_space.transfer.shader_id(),
+                        working_transfer: working_space.transfer.shader_id(),
+                        reference_white,
+                        itm_peak,
+                        itm_knee,
+                        channels: res.pixel_format.map_or(0, |f| f.channel_mode()),
+                        premultiplied: (res.alpha_mode == AlphaMode::Premultiplied) as u32,
+                        resampling: layer.resampling as u32,
+                        luma: working_luma.to_array().into(),
                     };
 
                     // Create temp uniform buffer
@@ -165,9 +264,17 @@ impl Renderer {
                         });
 
                     // Create Bind Group 1 (Texture)
-                    let view = res
-                        .texture
-                        .create_view(&wgpu::TextureViewDescriptor::default());
+                    let sampler = if res.texture.mip_level_count() > 1 {
+                        &self.anisotropic_sampler
+                    } else {
+                        &self.sampler
+                    };
+                    let view = match &res.view {
+                        Some(view) => view.clone(),
+                        None => res
+                            .texture
+                            .create_view(&wgpu::TextureViewDescriptor::default()),
+                    };
                     let texture_bg = context
                         .device
                         .create_bind_group(&wgpu::BindGroupDescriptor {
@@ -179,7 +286,7 @@ impl Renderer {
                                 },
                                 wgpu::BindGroupEntry {
                                     binding: 1,
-                                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                                    resource: wgpu::BindingResource::Sampler(sampler),
                                 },
                             ],
                             label: Some("Texture BG"),
@@ -192,9 +299,127 @@ impl Renderer {
             }
         } // Drop render pass to release borrow
 
+        // Output transform: working space -> each sink's color space and format
+        for (sink, output_view) in sinks.iter().zip(&output_views) {
+            self.write_output(
+                context,
+                composition,
+                &master_view,
+                &**sink,
+                output_view,
+                &mut encoder,
+            );
+        }
+
         context.queue.submit(std::iter::once(encoder.finish()));
-        sink.present(context); // Handle swapchain presentation or buffer copy
+        for sink in sinks.iter_mut() {
+            sink.present(context); // Handle swapchain presentation or buffer copy
+        }
+        texture_manager.pool().end_frame();
 
         Ok(())
     }
+
+    /// Records the output transform of the composited frame into `sink`'s view.
+    fn write_output(
+        &mut self,
+        context: &RenderContext,
+        composition: &FrameDescription,
+        master_view: &TextureView,
+        sink: &dyn RenderSink,
+        output_view: &TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let working_space = composition.working_space;
+        let working_luma = working_space.primaries.luma_coefficients();
+        let reference_white = composition.reference_white_nits;
+        let (master, target) = (master_view.texture(), output_view.texture());
+        let output_space = sink.output_color_space();
+        let format = sink.format();
+
+        let tone_mapping = sink.tone_mapping();
+        let (tone_map_operator, source_peak, target_peak, gamut_compression) = match tone_mapping {
+            Some(tm) => (
+                tm.operator as u32,
+                tm.source_peak_nits / reference_white,
+                tm.target_peak_nits.unwrap_or(reference_white) / reference_white,
+                tm.gamut_compression as u32,
+            ),
+            None => (0, 1.0, 1.0, 0),
+        };
+
+        let output_uniforms = OutputUniforms {
+            gamut: working_space
+                .primaries
+                .conversion_to(output_space.primaries)
+                .to_cols_array_2d()
+                .into(),
+            working_transfer: working_space.transfer.shader_id(),
+            output_transfer: output_space.transfer.shader_id(),
+            srgb_target: format.is_srgb() as u32,
+            reference_white,
+            tone_map_operator,
+            source_peak,
+            target_peak,
+            gamut_compression,
+            working_luma: working_luma.to_array().into(),
+            output_luma: output_space.primaries.luma_coefficients().to_array().into(),
+            scale: [
+                master.width() as f32 / target.width() as f32,
+                master.height() as f32 / target.height() as f32,
+            ]
+            .into(),
+        };
+        let output_uniform_buffer =
+            context
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Output Uniform Buffer"),
+                    contents: output_uniforms.as_std140().as_bytes(),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+        let output_bg = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.output_pipeline.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: output_uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(master_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+                label: Some("Output BG"),
+            });
+
+        {
+            let mut output_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Output Transform Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: output_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                multiview_mask: None,
+            });
+
+            output_pass.set_pipeline(self.output_pipeline.get_or_create(&context.device, format));
+            output_pass.set_bind_group(0, &output_bg, &[]);
+            output_pass.draw(0..3, 0..1);
+        }
+    }
 }
@@ -0,0 +1,159 @@
+use crate::core::RenderError;
+use wgpu::{Features, TextureFormat};
+
+/// Layout of CPU pixel data handed to `TextureManager`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    Rgba8,
+    Bgra8,
+    /// Packed 24-bit RGB, expanded to RGBA on the GPU.
+    Rgb8,
+    /// 16-bit unsigned normalized RGBA.
+    Rgba16,
+    Rgba16Float,
+    Rgba32Float,
+    /// Single-channel luminance, shown as grey.
+    Gray8,
+    /// Single-channel coverage, shown as white with that alpha.
+    Alpha8,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> u32 {
+        match self {
+            Self::Gray8 | Self::Alpha8 => 1,
+            Self::Rgb8 => 3,
+            Self::Rgba8 | Self::Bgra8 => 4,
+            Self::Rgba16 | Self::Rgba16Float => 8,
+            Self::Rgba32Float => 16,
+        }
+    }
+
+    /// Whether the data goes through the GPU expansion pass instead of being
+    /// copied straight into the sampled texture.
+    pub fn needs_expansion(self, features: Features) -> bool {
+        match self {
+            Self::Rgb8 | Self::Rgba32Float => true,
+            Self::Rgba16 => !features.contains(Features::TEXTURE_FORMAT_16BIT_NORM),
+            _ => false,
+        }
+    }
+
+    /// Format of the texture layers sample from.
+    pub fn texture_format(self, features: Features) -> TextureFormat {
+        match self {
+            Self::Rgba8 | Self::Rgb8 => TextureFormat::Rgba8Unorm,
+            Self::Bgra8 => TextureFormat::Bgra8Unorm,
+            Self::Rgba16 if features.contains(Features::TEXTURE_FORMAT_16BIT_NORM) => {
+                TextureFormat::Rgba16Unorm
+            }
+            // 32-bit float is not filterable everywhere; half float keeps HDR range
+            Self::Rgba16 | Self::Rgba16Float | Self::Rgba32Float => TextureFormat::Rgba16Float,
+            Self::Gray8 | Self::Alpha8 => TextureFormat::R8Unorm,
+        }
+    }
+
+    /// How the layer shader expands sampled channels. Matches `CHANNELS_*`
+    /// in `shader.wgsl`.
+    pub fn channel_mode(self) -> u32 {
+        match self {
+            Self::Gray8 => 1,
+            Self::Alpha8 => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// Whether color channels are already multiplied by alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AlphaMode {
+    #[default]
+    Straight,
+    Premultiplied,
+}
+
+/// Describes a block of pixel data to upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PixelLayout {
+    pub format: PixelFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Bytes between the starts of consecutive rows. `None` means tightly packed.
+    pub stride: Option<u32>,
+    pub alpha: AlphaMode,
+}
+
+impl PixelLayout {
+    pub fn new(format: PixelFormat, width: u32, height: u32) -> Self {
+        Self {
+            format,
+            width,
+            height,
+            stride: None,
+            alpha: AlphaMode::Straight,
+        }
+    }
+
+    pub fn with_stride(mut self, stride: u32) -> Self {
+        self.stride = Some(stride);
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: AlphaMode) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Bytes of pixel data in one row, without padding.
+    pub fn row_bytes(&self) -> u32 {
+        self.width * self.format.bytes_per_pixel()
+    }
+
+    /// Effective row stride.
+    pub fn stride(&self) -> u32 {
+        self.stride.unwrap_or_else(|| self.row_bytes())
+    }
+
+    /// Minimum number of bytes the data must contain.
+    pub fn required_bytes(&self) -> usize {
+        if self.height == 0 {
+            return 0;
+        }
+        self.stride() as usize * (self.height as usize - 1) + self.row_bytes() as usize
+    }
+
+    /// Checks that `len` bytes hold this layout and that the stride suits the
+    /// upload path the format takes.
+    pub fn validate(&self, features: Features, len: usize) -> Result<(), RenderError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(RenderError::InvalidUpload(format!(
+                "{:?} image has empty size {}x{}",
+                self.format, self.width, self.height
+            )));
+        }
+        let stride = self.stride();
+        if stride < self.row_bytes() || len < self.required_bytes() {
+            return Err(RenderError::InvalidUpload(format!(
+                "{}x{} {:?} needs stride >= {} and {} bytes, got stride {} and {} bytes",
+                self.width,
+                self.height,
+                self.format,
+                self.row_bytes(),
+                self.required_bytes(),
+                stride,
+                len
+            )));
+        }
+        // The expansion shader reads wider formats as whole 32-bit words
+        if self.format.needs_expansion(features)
+            && self.format != PixelFormat::Rgb8
+            && !stride.is_multiple_of(4)
+        {
+            return Err(RenderError::InvalidUpload(format!(
+                "{:?} rows must start on 4-byte boundaries, got stride {}",
+                self.format, stride
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use super::format::{PixelFormat, PixelLayout};
+use crate::core::RenderError;
+use crate::model::{ColorPrimaries, ColorSpace, TransferFunction};
+use image::metadata::Orientation;
+use image::{ColorType, DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use std::io::Cursor;
+use std::path::Path;
+
+/// An image decoded on the CPU, ready for `TextureManager::upload_pixels`.
+///
+/// Decoding is independent of the GPU, so it can run on worker threads.
+#[derive(Debug, Clone)]
+pub struct DecodedImage {
+    pub layout: PixelLayout,
+    pub data: Vec<u8>,
+    /// Color space taken from the file's CICP, ICC or gamma metadata.
+    pub color_space: ColorSpace,
+}
+
+impl DecodedImage {
+    /// Reads and decodes an image file. The format is detected from the
+    /// file contents.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RenderError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Reads the layout `open` would produce from the file's header,
+    /// without decoding pixels.
+    pub fn probe(path: impl AsRef<Path>) -> Result<PixelLayout, RenderError> {
+        let mut decoder = ImageReader::open(path)?
+            .with_guessed_format()?
+            .into_decoder()?;
+        let (width, height) = decoder.dimensions();
+        let format = upload_format(decoder.color_type());
+        Ok(match decoder.orientation()? {
+            Orientation::Rotate90
+            | Orientation::Rotate270
+            | Orientation::Rotate90FlipH
+            | Orientation::Rotate270FlipH => PixelLayout::new(format, height, width),
+            _ => PixelLayout::new(format, width, height),
+        })
+    }
+
+    /// Decodes an encoded image (PNG, JPEG, WebP, TIFF, BMP, OpenEXR, ...).
+    ///
+    /// EXIF orientation is applied, 8-bit data stays 8-bit, 16-bit data is
+    /// kept as `Rgba16` and float data (EXR, HDR) as `Rgba32Float`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RenderError> {
+        let reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+        let format = reader.format();
+        let mut decoder = reader.into_decoder()?;
+        let orientation = decoder.orientation()?;
+        let icc = decoder.icc_profile()?;
+        let mut image = DynamicImage::from_decoder(decoder)?;
+        image.apply_orientation(orientation);
+
+        let color_space = match format {
+            Some(ImageFormat::Png) => png_color_space(bytes),
+            _ => None,
+        }
+        .or_else(|| icc.as_deref().and_then(icc_color_space))
+        .unwrap_or(match image {
+            // Float formats store scene-linear values
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+                ColorSpace::LINEAR_REC709
+            }
+            _ => ColorSpace::SRGB,
+        });
+
+        let (width, height) = (image.width(), image.height());
+        let format = upload_format(image.color());
+        let data = match format {
+            PixelFormat::Gray8 | PixelFormat::Rgb8 => image.into_bytes(),
+            PixelFormat::Rgba16 => bytemuck::cast_slice(&image.into_rgba16().into_raw()).to_vec(),
+            PixelFormat::Rgba32Float => {
+                bytemuck::cast_slice(&image.into_rgba32f().into_raw()).to_vec()
+            }
+            _ => image.into_rgba8().into_raw(),
+        };
+
+        Ok(Self {
+            layout: PixelLayout::new(format, width, height),
+            data,
+            color_space,
+        })
+    }
+}
+
+/// Pixel format `DecodedImage` stores an image of `color` type in.
+pub fn upload_format(color: ColorType) -> PixelFormat {
+    match color {
+        ColorType::L8 => PixelFormat::Gray8,
+        ColorType::Rgb8 => PixelFormat::Rgb8,
+        ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16 => {
+            PixelFormat::Rgba16
+        }
+        ColorType::Rgb32F | ColorType::Rgba32F => PixelFormat::Rgba32Float,
+        _ => PixelFormat::Rgba8,
+    }
+}
+
+/// Color space from a PNG's cICP, sRGB or gAMA chunks, in that order of
+/// precedence. iCCP is left to the ICC lookup.
+fn png_color_space(bytes: &[u8]) -> Option<ColorSpace> {
+    let reader = png::Decoder::new(Cursor::new(bytes)).read_info().ok()?;
+    let info = reader.info();
+
+    if let Some(cicp) = info.coding_independent_code_points {
+        return cicp_color_space(cicp.color_primaries, cicp.transfer_function);
+    }
+    if info.srgb.is_some() || info.icc_profile.is_some() {
+        return None;
+    }
+    // gAMA stores the encoding exponent (1/2.2 for typical images)
+    let gamma = info.gamma()?.into_value();
+    ((gamma - 1.0).abs() < 0.01).then_some(ColorSpace::LINEAR_REC709)
+}
+
+/// Maps ITU-T H.273 code points onto the supported color spaces.
+fn cicp_color_space(primaries: u8, transfer: u8) -> Option<ColorSpace> {
+    let primaries = match primaries {
+        1 => ColorPrimaries::Rec709,
+        9 => ColorPrimaries::Rec2020,
+        12 => ColorPrimaries::DisplayP3,
+        _ => return None,
+    };
+    let transfer = match transfer {
+        1 | 6 | 14 | 15 => TransferFunction::Rec709,
+        8 => TransferFunction::Linear,
+        13 => TransferFunction::Srgb,
+        16 => TransferFunction::Pq,
+        18 => TransferFunction::Hlg,
+        _ => return None,
+    };
+    Some(ColorSpace::new(primaries, transfer))
+}
+
+/// Recognizes common ICC profiles by their description tag. There is no
+/// CMS here, so unknown profiles fall back to sRGB.
+fn icc_color_space(profile: &[u8]) -> Option<ColorSpace> {
+    let description = icc_description(profile)?.to_lowercase();
+    let linear = description.contains("linear");
+    let space = if description.contains("display p3") || description.contains("p3-d65") {
+        ColorSpace::DISPLAY_P3
+    } else if description.contains("2020") || description.contains("2100") {
+        if description.contains("pq") {
+            ColorSpace::REC2100_PQ
+        } else if description.contains("hlg") {
+            ColorSpace::REC2100_HLG
+        } else {
+            ColorSpace::REC2020
+        }
+    } else if description.contains("srgb") {
+        ColorSpace::SRGB
+    } else if description.contains("709") {
+        ColorSpace::REC709
+    } else {
+        return None;
+    };
+    Some(if linear { space.linear() } else { space })
+}
+
+/// Reads the `desc` tag of an ICC profile, either the v2 `desc` text type
+/// or the v4 `mluc` multi-localized type (first record).
+fn icc_description(profile: &[u8]) -> Option<String> {
+    // The count comes from the file; only trust entries that fit in it
+    let tag_count = (be_u32(profile, 128)? as usize).min(profile.len().saturating_sub(132) / 12);
+    let (offset, size) = (0..tag_count).find_map(|i| {
+        let entry = 132 + i * 12;
+        if profile.get(entry..entry + 4)? != b"desc" {
+            return None;
+        }
+        Some((be_u32(profile, entry + 4)?, be_u32(profile, entry + 8)?))
+    })?;
+    let tag = profile.get(offset as usize..offset.checked_add(size)? as usize)?;
+
+    match tag.get(0..4)? {
+        b"desc" => {
+            let len = be_u32(tag, 8)? as usize;
+            let text = tag.get(12..12 + len)?;
+            Some(
+                String::from_utf8_lossy(text)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            )
+        }
+        b"mluc" => {
+            let len = be_u32(tag, 20)? as usize;
+            let start = be_u32(tag, 24)? as usize;
+            let units: Vec<u16> = tag
+                .get(start..start + len)?
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            Some(String::from_utf16_lossy(&units))
+        }
+        _ => None,
+    }
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
@@ -1,2 +1,14 @@
+pub mod format;
+pub mod loader;
+pub mod pool;
+pub mod streaming;
 pub mod texture_manager;
-pub use texture_manager::TextureManager;
+pub mod yuv;
+pub use format::*;
+pub use loader::DecodedImage;
+pub use pool::{PoolStats, TextureKey, TexturePool};
+pub use streaming::{UploadFence, UploadRing, UploadSlot};
+pub use texture_manager::{
+    ImportedTexture, LoadedImage, MemoryStats, TextureManager, TextureResource,
+};
+pub use yuv::*;
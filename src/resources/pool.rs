@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wgpu::{Device, Extent3d, Texture, TextureDescriptor, TextureFormat, TextureUsages};
+
+/// Textures are interchangeable when all of these match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureKey {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    pub usage: TextureUsages,
+    pub mip_level_count: u32,
+}
+
+impl TextureKey {
+    pub fn new(width: u32, height: u32, format: TextureFormat, usage: TextureUsages) -> Self {
+        Self {
+            width,
+            height,
+            format,
+            usage,
+            mip_level_count: 1,
+        }
+    }
+
+    pub fn with_mip_levels(mut self, count: u32) -> Self {
+        self.mip_level_count = count;
+        self
+    }
+
+    pub fn of(texture: &Texture) -> Self {
+        Self::new(
+            texture.width(),
+            texture.height(),
+            texture.format(),
+            texture.usage(),
+        )
+        .with_mip_levels(texture.mip_level_count())
+    }
+
+    /// Bytes across all mip levels.
+    pub fn byte_size(&self) -> u64 {
+        let block = self.format.block_copy_size(None).unwrap_or(4) as u64;
+        (0..self.mip_level_count)
+            .map(|level| {
+                let width = (self.width >> level).max(1) as u64;
+                let height = (self.height >> level).max(1) as u64;
+                width * height * block
+            })
+            .sum()
+    }
+}
+
+/// Counters reported by `TexturePool::stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Textures waiting to be reused.
+    pub idle_textures: usize,
+    pub idle_bytes: u64,
+    /// Acquisitions served from the pool.
+    pub hits: u64,
+    /// Acquisitions that had to create a texture.
+    pub misses: u64,
+    /// Idle textures dropped by trimming.
+    pub trimmed: u64,
+}
+
+struct IdleTexture {
+    texture: Texture,
+    // Frame and release order, for idle trimming and oldest-first shrinking
+    frame: u64,
+    order: u64,
+}
+
+#[derive(Default)]
+struct PoolState {
+    idle: HashMap<TextureKey, Vec<IdleTexture>>,
+    frame: u64,
+    order: u64,
+    stats: PoolStats,
+}
+
+/// Recycles textures between resources and frames so playback of clips with
+/// changing sizes doesn't churn `device.create_texture`.
+///
+/// Shared by `TextureManager` and the renderer's intermediate targets; all
+/// methods take `&self` so the pool can sit behind an `Arc`.
+pub struct TexturePool {
+    state: Mutex<PoolState>,
+    max_idle_frames: u64,
+}
+
+impl Default for TexturePool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TexturePool {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(PoolState::default()),
+            max_idle_frames: 120,
+        }
+    }
+
+    /// Sets how many frames an idle texture survives before `end_frame`
+    /// drops it. Defaults to 120.
+    pub fn with_max_idle_frames(mut self, frames: u64) -> Self {
+        self.max_idle_frames = frames;
+        self
+    }
+
+    /// Takes an idle texture matching `key`, or creates one.
+    pub fn acquire(&self, device: &Device, label: &str, key: TextureKey) -> Texture {
+        let mut state = self.state.lock().unwrap();
+        if let Some(idle) = state.idle.get_mut(&key).and_then(|list| list.pop()) {
+            state.stats.hits += 1;
+            state.stats.idle_textures -= 1;
+            state.stats.idle_bytes -= key.byte_size();
+            return idle.texture;
+        }
+        state.stats.misses += 1;
+        drop(state);
+
+        device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size: Extent3d {
+                width: key.width,
+                height: key.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: key.mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: key.format,
+            usage: key.usage,
+            view_formats: &[],
+        })
+    }
+
+    /// Hands a texture back for reuse. Work already submitted against it
+    /// stays valid; wgpu orders later writes after it.
+    pub fn release(&self, texture: Texture) {
+        let key = TextureKey::of(&texture);
+        let mut state = self.state.lock().unwrap();
+        let (frame, order) = (state.frame, state.order);
+        state.order += 1;
+        state.stats.idle_textures += 1;
+        state.stats.idle_bytes += key.byte_size();
+        state.idle.entry(key).or_default().push(IdleTexture {
+            texture,
+            frame,
+            order,
+        });
+    }
+
+    /// Advances the frame counter and drops textures idle for longer than
+    /// `max_idle_frames`. The renderer calls this once per frame.
+    pub fn end_frame(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.frame += 1;
+        let oldest = state.frame.saturating_sub(self.max_idle_frames);
+        Self::retain(&mut state, |idle| idle.frame >= oldest);
+    }
+
+    /// Drops every idle texture, e.g. when playback stops.
+    pub fn trim(&self) {
+        let mut state = self.state.lock().unwrap();
+        Self::retain(&mut state, |_| false);
+    }
+
+    /// Drops the longest-idle textures until at most `max_bytes` are idle.
+    pub fn shrink_to(&self, max_bytes: u64) {
+        let mut state = self.state.lock().unwrap();
+        while state.stats.idle_bytes > max_bytes {
+            let Some(oldest) = state.idle.values().flatten().map(|i| i.order).min() else {
+                break;
+            };
+            Self::retain(&mut state, |idle| idle.order != oldest);
+        }
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.state.lock().unwrap().stats
+    }
+
+    fn retain(state: &mut PoolState, keep: impl Fn(&IdleTexture) -> bool) {
+        let mut dropped = PoolStats::default();
+        for (key, list) in state.idle.iter_mut() {
+            list.retain(|idle| {
+                let kept = keep(idle);
+                if !kept {
+                    dropped.idle_textures += 1;
+                    dropped.idle_bytes += key.byte_size();
+                }
+                kept
+            });
+        }
+        state.idle.retain(|_, list| !list.is_empty());
+        state.stats.idle_textures -= dropped.idle_textures;
+        state.stats.idle_bytes -= dropped.idle_bytes;
+        state.stats.trimmed += dropped.idle_textures as u64;
+    }
+}
@@ -0,0 +1,237 @@
+use super::format::PixelLayout;
+use crate::core::{RenderContext, RenderError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use wgpu::{Buffer, Device, Features};
+
+// Slot lifecycle: mapped and free -> handed to a producer -> copying on the
+// GPU -> remapped and free again.
+const SLOT_FREE: u8 = 0;
+const SLOT_FILLING: u8 = 1;
+const SLOT_IN_FLIGHT: u8 = 2;
+
+struct SlotShared {
+    buffer: Buffer,
+    state: AtomicU8,
+}
+
+/// Persistent ring of mapped staging buffers for streaming uploads.
+///
+/// Producers (typically decoder worker threads) take a slot, write a frame
+/// into its mapped memory and hand it to `TextureManager::submit_upload`,
+/// which copies it with `copy_buffer_to_texture`. The slot is remapped and
+/// becomes free once the GPU copy has finished.
+pub struct UploadRing {
+    slots: Vec<Arc<SlotShared>>,
+    slot_size: u64,
+    features: Features,
+}
+
+impl UploadRing {
+    /// Creates `slot_count` slots of `slot_size` bytes each. Use
+    /// `UploadRing::staged_size` to size slots for a layout.
+    pub fn new(device: &Device, slot_count: usize, slot_size: u64) -> Self {
+        let slot_size = slot_size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+        let slots = (0..slot_count)
+            .map(|index| {
+                Arc::new(SlotShared {
+                    buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&format!("Upload Ring Slot {}", index)),
+                        size: slot_size,
+                        usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                        mapped_at_creation: true,
+                    }),
+                    state: AtomicU8::new(SLOT_FREE),
+                })
+            })
+            .collect();
+
+        Self {
+            slots,
+            slot_size,
+            features: device.features(),
+        }
+    }
+
+    /// Bytes a slot needs to stage `layout`. Directly copyable formats are
+    /// staged with rows padded to `COPY_BYTES_PER_ROW_ALIGNMENT`; formats
+    /// expanded on the GPU keep their source stride.
+    pub fn staged_size(layout: &PixelLayout, features: Features) -> u64 {
+        let (stride, _) = staged_stride(layout, features);
+        let rows = layout.height.saturating_sub(1) as u64;
+        (stride as u64 * rows + layout.row_bytes() as u64)
+            .next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+    }
+
+    pub fn slot_size(&self) -> u64 {
+        self.slot_size
+    }
+
+    /// Number of slots ready to be filled.
+    pub fn free_slots(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.state.load(Ordering::Acquire) == SLOT_FREE)
+            .count()
+    }
+
+    /// Takes a free slot, or `None` while every slot is being filled or copied.
+    pub fn acquire(&self) -> Option<UploadSlot> {
+        let shared = self.slots.iter().find(|slot| {
+            slot.state
+                .compare_exchange(SLOT_FREE, SLOT_FILLING, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        })?;
+        Some(UploadSlot {
+            shared: shared.clone(),
+            features: self.features,
+            staged: None,
+            submitted: false,
+        })
+    }
+
+    /// Takes a free slot, waiting on in-flight copies if none is available.
+    /// Returns `None` if the ring has no slots at all.
+    pub fn acquire_wait(&self, ctx: &RenderContext) -> Option<UploadSlot> {
+        loop {
+            if let Some(slot) = self.acquire() {
+                return Some(slot);
+            }
+            if !self
+                .slots
+                .iter()
+                .any(|slot| slot.state.load(Ordering::Acquire) == SLOT_IN_FLIGHT)
+            {
+                return None;
+            }
+            ctx.instance.poll_all(true);
+        }
+    }
+}
+
+/// One staging slot on loan from an `UploadRing`. Can be filled on any
+/// thread; dropping it without submitting returns it to the ring.
+pub struct UploadSlot {
+    shared: Arc<SlotShared>,
+    features: Features,
+    staged: Option<(PixelLayout, u32)>,
+    submitted: bool,
+}
+
+impl UploadSlot {
+    pub fn capacity(&self) -> u64 {
+        self.shared.buffer.size()
+    }
+
+    /// Copies `data`, laid out as described by `layout`, into the slot's
+    /// mapped memory, repacking rows into the layout the GPU copy expects.
+    pub fn write(&mut self, layout: &PixelLayout, data: &[u8]) -> Result<(), RenderError> {
+        layout.validate(self.features, data.len())?;
+        let needed = UploadRing::staged_size(layout, self.features);
+        if needed > self.capacity() {
+            return Err(RenderError::InvalidUpload(format!(
+                "{}x{} {:?} needs a {} byte slot, ring slots hold {}",
+                layout.width,
+                layout.height,
+                layout.format,
+                needed,
+                self.capacity()
+            )));
+        }
+
+        let (staged, repack) = staged_stride(layout, self.features);
+        let stride = layout.stride() as usize;
+        let row_bytes = layout.row_bytes() as usize;
+        let mut view = self.shared.buffer.get_mapped_range_mut(..needed);
+        if repack {
+            for row in 0..layout.height as usize {
+                let src = &data[row * stride..row * stride + row_bytes];
+                let dst = row * staged as usize;
+                view[dst..dst + row_bytes].copy_from_slice(src);
+            }
+        } else {
+            let len = layout.required_bytes();
+            view[..len].copy_from_slice(&data[..len]);
+        }
+        drop(view);
+
+        self.staged = Some((*layout, staged));
+        Ok(())
+    }
+
+    /// Layout and staged row stride of the written frame.
+    pub(crate) fn staged(&self) -> Option<(PixelLayout, u32)> {
+        self.staged
+    }
+
+    pub(crate) fn buffer(&self) -> &Buffer {
+        &self.shared.buffer
+    }
+
+    /// Unmaps the slot ahead of its copy.
+    pub(crate) fn begin_copy(&mut self) {
+        self.submitted = true;
+        self.shared.state.store(SLOT_IN_FLIGHT, Ordering::Release);
+        self.shared.buffer.unmap();
+    }
+
+    /// Remaps the slot once the submitted copy has finished, freeing it and
+    /// signalling the returned fence.
+    pub(crate) fn finish_copy(self) -> UploadFence {
+        let fence = UploadFence {
+            done: Arc::new(AtomicBool::new(false)),
+        };
+        let shared = self.shared.clone();
+        let done = fence.done.clone();
+        self.shared
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Write, move |result| {
+                // A failed map leaves the slot out of rotation
+                if result.is_ok() {
+                    shared.state.store(SLOT_FREE, Ordering::Release);
+                }
+                done.store(true, Ordering::Release);
+            });
+        fence
+    }
+}
+
+impl Drop for UploadSlot {
+    fn drop(&mut self) {
+        if !self.submitted {
+            self.shared.state.store(SLOT_FREE, Ordering::Release);
+        }
+    }
+}
+
+/// Signals that a streamed upload's GPU copy has finished and its staging
+/// slot is free for reuse.
+#[derive(Debug, Clone)]
+pub struct UploadFence {
+    done: Arc<AtomicBool>,
+}
+
+impl UploadFence {
+    pub fn is_complete(&self) -> bool {
+        self.done.load(Ordering::Acquire)
+    }
+
+    /// Blocks until the copy has finished.
+    pub fn wait(&self, ctx: &RenderContext) {
+        while !self.is_complete() {
+            ctx.instance.poll_all(true);
+        }
+    }
+}
+
+/// Row stride used inside a slot, and whether rows need repacking to get it.
+fn staged_stride(layout: &PixelLayout, features: Features) -> (u32, bool) {
+    if layout.format.needs_expansion(features) {
+        return (layout.stride(), false);
+    }
+    let aligned = layout
+        .row_bytes()
+        .next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    (aligned, aligned != layout.stride())
+}
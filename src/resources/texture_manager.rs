@@ -1,29 +1,258 @@
+use super::format::{AlphaMode, PixelFormat, PixelLayout};
+use super::loader::DecodedImage;
+use super::pool::{TextureKey, TexturePool};
+use super::streaming::{UploadFence, UploadRing, UploadSlot};
+use super::yuv::{YuvConversion, YuvFormat, YuvPlane};
+use crate::core::RenderError;
+use crate::model::{ColorSpace, TransferFunction};
+use crate::pipeline::{MipmapGenerator, PixelExpander, YuvConverter, mip_level_count};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use uuid::Uuid;
-use wgpu::{Device, Extent3d, Queue, Texture, TextureDescriptor, TextureFormat, TextureUsages};
+use wgpu::{
+    Device, Extent3d, Queue, Texture, TextureDimension, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView,
+};
 
 pub struct TextureResource {
     pub texture: Texture,
     pub width: u32,
     pub height: u32,
     pub format: TextureFormat,
+    /// Color space the uploaded bytes are encoded in. Decoding happens in the
+    /// layer shader, so the texture itself is stored without sRGB conversion.
+    pub color_space: ColorSpace,
+    /// Layout of the uploaded data; `None` for planar YUV resources.
+    pub pixel_format: Option<PixelFormat>,
+    pub alpha_mode: AlphaMode,
+    /// Per-plane source textures for planar uploads; empty for RGBA resources.
+    pub planes: Vec<Texture>,
+    /// Textures streamed uploads write into while `texture` is drawn; they
+    /// rotate to the front as each upload lands.
+    pub back_buffers: Vec<Texture>,
+    /// Raw bytes awaiting GPU expansion, for formats without a GPU equivalent.
+    pub staging: Option<wgpu::Buffer>,
+    /// View layers sample, for imported textures; managed resources are
+    /// drawn through a default view of `texture`.
+    pub view: Option<TextureView>,
+    // Host-owned texture registered with `import_texture`; never written to
+    // or returned to the pool
+    imported: bool,
+    // Tick of the last upload or lookup, for LRU eviction
+    last_used: AtomicU64,
+}
+
+impl TextureResource {
+    /// GPU memory held by this resource: the sampled texture plus any plane
+    /// textures and staging buffer. Imported textures belong to the host and
+    /// count as zero.
+    pub fn byte_size(&self) -> u64 {
+        if self.imported {
+            return 0;
+        }
+        let textures: u64 = self
+            .planes
+            .iter()
+            .chain(&self.back_buffers)
+            .map(texture_bytes)
+            .sum();
+        let staging = self.staging.as_ref().map_or(0, |b| b.size());
+        texture_bytes(&self.texture) + textures + staging
+    }
+
+    /// Storage buffer holding at least `size` raw bytes for GPU expansion.
+    fn staging_buffer(&mut self, device: &Device, id: Uuid, size: u64) -> wgpu::Buffer {
+        let size = size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+        if self.staging.as_ref().is_none_or(|b| b.size() < size) {
+            self.staging = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("texture_{}_staging", id)),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        self.staging.clone().unwrap()
+    }
+
+    /// Whether the texture is owned by the host, see
+    /// `TextureManager::import_texture`.
+    pub fn is_imported(&self) -> bool {
+        self.imported
+    }
+}
+
+/// A texture owned by the host, e.g. the output of a hardware decoder, a
+/// camera pipeline or another renderer on the same device, to register with
+/// `TextureManager::import_texture`.
+pub struct ImportedTexture {
+    pub texture: Texture,
+    /// View to sample instead of a default view of `texture`, e.g. one
+    /// selecting a single mip level or array layer.
+    pub view: Option<TextureView>,
+    /// Color space of the texel values. sRGB texture formats are decoded by
+    /// the sampler, so their transfer is recorded as linear.
+    pub color_space: ColorSpace,
+    pub alpha_mode: AlphaMode,
+}
+
+impl ImportedTexture {
+    /// Imports `texture` as straight-alpha sRGB content.
+    pub fn new(texture: Texture) -> Self {
+        Self {
+            texture,
+            view: None,
+            color_space: ColorSpace::SRGB,
+            alpha_mode: AlphaMode::Straight,
+        }
+    }
+
+    pub fn with_view(mut self, view: TextureView) -> Self {
+        self.view = Some(view);
+        self
+    }
+
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    pub fn with_alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+}
+
+/// Snapshot of the GPU memory held by a `TextureManager`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub resource_count: usize,
+    /// Bytes held by live resources.
+    pub resident_bytes: u64,
+    /// Bytes held by retired textures awaiting reuse.
+    pub pooled_bytes: u64,
+    pub budget: Option<u64>,
+    /// Resources evicted to stay within the budget so far.
+    pub evictions: u64,
+}
+
+/// Result of `TextureManager::load_image`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadedImage {
+    /// Resource ID to reference from layers.
+    pub id: Uuid,
+    /// Dimensions after EXIF orientation.
+    pub width: u32,
+    pub height: u32,
+    pub color_space: ColorSpace,
 }
 
 pub struct TextureManager {
     // Active resource map
     pub resources: HashMap<Uuid, TextureResource>,
-    // orphaned texture pool (Future Optimization)
-    // pool: Vec<Texture>,
+    // Source of resource textures; retired ones go back for reuse, e.g.
+    // when a resource toggles between proxy and full resolution
+    pool: Arc<TexturePool>,
+    // Conversion passes, created on first use
+    yuv_converter: Option<YuvConverter>,
+    pixel_expander: Option<PixelExpander>,
+    mipmap_generator: Option<MipmapGenerator>,
+    mipmaps: bool,
+    // Textures per streamed resource, front buffer included
+    frame_buffers: usize,
+    // LRU bookkeeping. Lookups only take `&self`, hence the atomic clock.
+    clock: AtomicU64,
+    budget: Option<u64>,
+    evictions: u64,
+    on_evict: Option<Box<dyn FnMut(Uuid) + Send>>,
+}
+
+impl Default for TextureManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TextureManager {
     pub fn new() -> Self {
+        Self::with_pool(Arc::new(TexturePool::new()))
+    }
+
+    /// Creates a manager drawing textures from a pool shared with others.
+    pub fn with_pool(pool: Arc<TexturePool>) -> Self {
         Self {
             resources: HashMap::new(),
+            pool,
+            yuv_converter: None,
+            pixel_expander: None,
+            mipmap_generator: None,
+            mipmaps: false,
+            frame_buffers: 2,
+            clock: AtomicU64::new(0),
+            budget: None,
+            evictions: 0,
+            on_evict: None,
+        }
+    }
+
+    /// Caps the GPU memory held by resources and pooled textures, evicting
+    /// least recently used resources when an upload goes over. A single
+    /// resource larger than the budget is still kept. `None` disables the cap.
+    pub fn set_memory_budget(&mut self, bytes: Option<u64>) {
+        self.budget = bytes;
+        self.enforce_budget(None);
+    }
+
+    /// Registers a callback invoked with the id of every evicted resource, so
+    /// the host can upload it again before it is next drawn.
+    pub fn set_eviction_callback(&mut self, callback: impl FnMut(Uuid) + Send + 'static) {
+        self.on_evict = Some(Box::new(callback));
+    }
+
+    /// Enables a full mip chain, generated on the GPU after every upload, so
+    /// heavily downscaled layers sample without aliasing. Applies to later
+    /// uploads; existing resources are reallocated on their next upload.
+    pub fn set_mipmaps(&mut self, enabled: bool) {
+        self.mipmaps = enabled;
+    }
+
+    /// Sets how many textures each streamed resource rotates through: 2 for
+    /// double buffering (the default), 3 for triple buffering, 1 to write
+    /// straight into the texture being drawn.
+    pub fn set_frame_buffers(&mut self, count: usize) {
+        self.frame_buffers = count.max(1);
+    }
+
+    /// The pool resource textures come from. The renderer draws its
+    /// intermediate targets from it too.
+    pub fn pool(&self) -> &Arc<TexturePool> {
+        &self.pool
+    }
+
+    /// Drops a resource, returning its textures to the pool. Returns `false`
+    /// if the resource is unknown.
+    pub fn remove(&mut self, id: &Uuid) -> bool {
+        self.retire(*id).is_some()
+    }
+
+    /// Drops every resource and all idle pooled textures.
+    pub fn clear(&mut self) {
+        self.resources.clear();
+        self.pool.trim();
+    }
+
+    pub fn memory_stats(&self) -> MemoryStats {
+        MemoryStats {
+            resource_count: self.resources.len(),
+            resident_bytes: self.resources.values().map(|r| r.byte_size()).sum(),
+            pooled_bytes: self.pool.stats().idle_bytes,
+            budget: self.budget,
+            evictions: self.evictions,
         }
     }
 
-    /// Uploads bytes to GPU texture.
+    /// Uploads tightly packed RGBA8 bytes to GPU texture.
     pub fn update_texture(
         &mut self,
         device: &Device,
@@ -32,68 +261,617 @@ impl TextureManager {
         width: u32,
         height: u32,
         data: &[u8],
-    ) {
-        let entry = self.resources.entry(id).or_insert_with(|| {
-            // If doesn't exist, create texture on GPU
-            let size = Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            };
+    ) -> Result<(), RenderError> {
+        let layout = PixelLayout::new(PixelFormat::Rgba8, width, height);
+        self.upload_pixels(device, queue, id, &layout, data)
+    }
 
-            let texture = device.create_texture(&TextureDescriptor {
-                label: Some(&format!("texture_{}", id)),
-                size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: TextureFormat::Rgba8UnormSrgb, // Standard for video
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-                view_formats: &[],
-            });
-
-            TextureResource {
-                texture,
-                width,
-                height,
-                format: TextureFormat::Rgba8UnormSrgb,
+    /// Uploads pixel data described by `layout` to GPU texture.
+    ///
+    /// Formats with a matching GPU format are copied directly, honoring the
+    /// row stride. The rest (e.g. 24-bit RGB) are staged in a buffer and
+    /// expanded to RGBA by a GPU pass, so padded decoder output never needs
+    /// repacking on the CPU.
+    ///
+    /// If the size or format differs from the previous upload the texture is
+    /// reallocated, keeping the resource's color space.
+    pub fn upload_pixels(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        id: Uuid,
+        layout: &PixelLayout,
+        data: &[u8],
+    ) -> Result<(), RenderError> {
+        layout.validate(device.features(), data.len())?;
+        let expand = layout.format.needs_expansion(device.features());
+        let (width, height, stride) = (layout.width, layout.height, layout.stride());
+        self.prepare_resource(device, id, layout);
+        let entry = self.resources.get_mut(&id).unwrap();
+
+        if !expand {
+            // WGPU 0.28: ImageCopyTexture -> TexelCopyTextureInfo
+            // ImageDataLayout -> TexelCopyBufferLayout
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &entry.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &data[..layout.required_bytes()],
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(stride),
+                    rows_per_image: Some(height),
+                },
+                Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+            self.finish_upload(device, queue, id);
+            return Ok(());
+        }
+
+        // Stage raw bytes; buffer writes must be whole words
+        let data = &data[..layout.required_bytes()];
+        let staging = entry.staging_buffer(device, id, data.len() as u64);
+        let aligned = data.len() - data.len() % 4;
+        queue.write_buffer(&staging, 0, &data[..aligned]);
+        if aligned < data.len() {
+            let mut tail = [0u8; 4];
+            tail[..data.len() - aligned].copy_from_slice(&data[aligned..]);
+            queue.write_buffer(&staging, aligned as u64, &tail);
+        }
+
+        let expander = self
+            .pixel_expander
+            .get_or_insert_with(|| PixelExpander::new(device));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Pixel Expand Encoder"),
+        });
+        expander.encode(
+            device,
+            &mut encoder,
+            layout.format,
+            stride,
+            &staging,
+            &entry.texture,
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+        self.finish_upload(device, queue, id);
+
+        Ok(())
+    }
+
+    /// Decodes an image file and uploads it as a new resource.
+    ///
+    /// See `DecodedImage::from_bytes` for the supported formats and how
+    /// orientation and color metadata are handled.
+    pub fn load_image(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<LoadedImage, RenderError> {
+        let image = DecodedImage::open(path)?;
+        self.upload_image(device, queue, Uuid::new_v4(), &image)
+    }
+
+    /// Like `load_image`, for an encoded image already in memory.
+    pub fn load_image_bytes(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+    ) -> Result<LoadedImage, RenderError> {
+        let image = DecodedImage::from_bytes(bytes)?;
+        self.upload_image(device, queue, Uuid::new_v4(), &image)
+    }
+
+    /// Uploads a decoded image to the resource `id`, applying its color space.
+    pub fn upload_image(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        id: Uuid,
+        image: &DecodedImage,
+    ) -> Result<LoadedImage, RenderError> {
+        self.upload_pixels(device, queue, id, &image.layout, &image.data)?;
+        self.set_color_space(id, image.color_space);
+        Ok(LoadedImage {
+            id,
+            width: image.layout.width,
+            height: image.layout.height,
+            color_space: image.color_space,
+        })
+    }
+
+    /// Copies a frame staged in an `UploadRing` slot into the resource with
+    /// `copy_buffer_to_texture`.
+    ///
+    /// The copy targets the resource's next back buffer, which becomes the
+    /// front texture once submitted, so frames already recorded against the
+    /// old front are unaffected. The returned fence completes when the slot
+    /// is free again; poll it or `wait` on it to apply backpressure.
+    pub fn submit_upload(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        id: Uuid,
+        mut slot: UploadSlot,
+    ) -> Result<UploadFence, RenderError> {
+        let Some((layout, staged_stride)) = slot.staged() else {
+            return Err(RenderError::InvalidUpload(
+                "upload slot submitted before being written".into(),
+            ));
+        };
+        let expand = layout.format.needs_expansion(device.features());
+        self.prepare_resource(device, id, &layout);
+
+        // Keep `frame_buffers - 1` back textures matching the front one
+        let pool = self.pool.clone();
+        let back_count = self.frame_buffers - 1;
+        let entry = self.resources.get_mut(&id).unwrap();
+        let key = TextureKey::of(&entry.texture);
+        let (keep, stale): (Vec<_>, Vec<_>) = entry
+            .back_buffers
+            .drain(..)
+            .partition(|t| TextureKey::of(t) == key);
+        entry.back_buffers = keep;
+        stale.into_iter().for_each(|t| pool.release(t));
+        while entry.back_buffers.len() > back_count {
+            pool.release(entry.back_buffers.pop().unwrap());
+        }
+        while entry.back_buffers.len() < back_count {
+            let label = format!("texture_{}_back{}", id, entry.back_buffers.len());
+            entry.back_buffers.push(pool.acquire(device, &label, key));
+        }
+        let target = if back_count == 0 {
+            entry.texture.clone()
+        } else {
+            entry.back_buffers.remove(0)
+        };
+
+        slot.begin_copy();
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Streaming Upload Encoder"),
+        });
+        if !expand {
+            encoder.copy_buffer_to_texture(
+                wgpu::TexelCopyBufferInfo {
+                    buffer: slot.buffer(),
+                    layout: wgpu::TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: Some(staged_stride),
+                        rows_per_image: Some(layout.height),
+                    },
+                },
+                wgpu::TexelCopyTextureInfo {
+                    texture: &target,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                extent(layout.width, layout.height),
+            );
+        } else {
+            let size = UploadRing::staged_size(&layout, device.features());
+            let staging = entry.staging_buffer(device, id, size);
+            encoder.copy_buffer_to_buffer(slot.buffer(), 0, &staging, 0, size);
+            self.pixel_expander
+                .get_or_insert_with(|| PixelExpander::new(device))
+                .encode(
+                    device,
+                    &mut encoder,
+                    layout.format,
+                    staged_stride,
+                    &staging,
+                    &target,
+                );
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+        let fence = slot.finish_copy();
+
+        if back_count > 0 {
+            let front = std::mem::replace(&mut entry.texture, target);
+            entry.back_buffers.push(front);
+        }
+        self.finish_upload(device, queue, id);
+
+        Ok(fence)
+    }
+
+    /// Makes sure `id` has a texture matching `layout`, reallocating it from
+    /// the pool (keeping the color space) when size or format changed.
+    fn prepare_resource(&mut self, device: &Device, id: Uuid, layout: &PixelLayout) {
+        let features = device.features();
+        let texture_format = layout.format.texture_format(features);
+        let mut usage = TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST;
+        if layout.format.needs_expansion(features) || self.mipmaps {
+            usage |= TextureUsages::RENDER_ATTACHMENT;
+        }
+        // Transfer decoded in shader
+        let key = self.resource_key(layout.width, layout.height, texture_format, usage);
+        let reusable = self.resources.get(&id).is_some_and(|res| {
+            !res.imported && res.planes.is_empty() && TextureKey::of(&res.texture) == key
+        });
+        if !reusable {
+            let (color_space, staging) = self.retire(id).unwrap_or_default();
+            let texture = self.pool.acquire(device, &format!("texture_{}", id), key);
+            self.resources.insert(
+                id,
+                TextureResource {
+                    texture,
+                    width: layout.width,
+                    height: layout.height,
+                    format: texture_format,
+                    color_space,
+                    pixel_format: Some(layout.format),
+                    alpha_mode: layout.alpha,
+                    planes: vec![],
+                    back_buffers: vec![],
+                    staging,
+                    view: None,
+                    imported: false,
+                    last_used: AtomicU64::new(0),
+                },
+            );
+        }
+
+        let entry = self.resources.get_mut(&id).unwrap();
+        entry.pixel_format = Some(layout.format);
+        entry.alpha_mode = layout.alpha;
+    }
+
+    /// Uploads a planar Y'CbCr frame and converts it to RGB on the GPU.
+    ///
+    /// Each plane is uploaded as-is (honoring its stride) into its own texture,
+    /// then a conversion pass writes the resource's RGBA texture. The resource
+    /// color space defaults to the one matching `conversion.matrix`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_yuv_texture(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        id: Uuid,
+        format: YuvFormat,
+        width: u32,
+        height: u32,
+        planes: &[YuvPlane],
+        conversion: YuvConversion,
+    ) -> Result<(), RenderError> {
+        if width == 0 || height == 0 {
+            return Err(RenderError::InvalidUpload(format!(
+                "{:?} frame has empty size {}x{}",
+                format, width, height
+            )));
+        }
+        if planes.len() != format.plane_count() {
+            return Err(RenderError::InvalidUpload(format!(
+                "{:?} expects {} planes, got {}",
+                format,
+                format.plane_count(),
+                planes.len()
+            )));
+        }
+        for (index, plane) in planes.iter().enumerate() {
+            let (_, rows) = format.plane_size(index, width, height);
+            let row_bytes = format.plane_row_bytes(index, width);
+            let required = plane.stride as usize * (rows as usize - 1) + row_bytes as usize;
+            if plane.stride < row_bytes || plane.data.len() < required {
+                return Err(RenderError::InvalidUpload(format!(
+                    "plane {} of {:?} needs stride >= {} and {} bytes, got stride {} and {} bytes",
+                    index,
+                    format,
+                    row_bytes,
+                    required,
+                    plane.stride,
+                    plane.data.len()
+                )));
             }
+        }
+
+        let rgba_format = format.converted_format();
+        let key = self.resource_key(
+            width,
+            height,
+            rgba_format,
+            TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        );
+        let reusable = self.resources.get(&id).is_some_and(|res| {
+            !res.imported
+                && TextureKey::of(&res.texture) == key
+                && res.planes.len() == format.plane_count()
+                && res.planes.iter().enumerate().all(|(i, p)| {
+                    let (w, h) = format.plane_size(i, width, height);
+                    p.format() == format.plane_format(i) && p.size() == extent(w, h)
+                })
         });
+        if !reusable {
+            let (color_space, staging) = match self.retire(id) {
+                Some(carried) => carried,
+                None => (conversion.matrix.default_color_space(), None),
+            };
+            let texture = self.pool.acquire(device, &format!("texture_{}", id), key);
+            let planes = (0..format.plane_count())
+                .map(|index| {
+                    let (w, h) = format.plane_size(index, width, height);
+                    self.pool.acquire(
+                        device,
+                        &format!("texture_{}_plane{}", id, index),
+                        TextureKey::new(
+                            w,
+                            h,
+                            format.plane_format(index),
+                            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                        ),
+                    )
+                })
+                .collect();
+            self.resources.insert(
+                id,
+                TextureResource {
+                    texture,
+                    width,
+                    height,
+                    format: rgba_format,
+                    color_space,
+                    pixel_format: None,
+                    alpha_mode: AlphaMode::Straight,
+                    planes,
+                    back_buffers: vec![],
+                    staging,
+                    view: None,
+                    imported: false,
+                    last_used: AtomicU64::new(0),
+                },
+            );
+        }
 
-        // Simple dimension check - in prod we should recreate texture if size changed
-        if entry.width != width || entry.height != height {
-            eprintln!(
-                "Warning: Texture size mismatch for ID {}. Expected {}x{}, got {}x{}",
-                id, entry.width, entry.height, width, height
+        let entry = self.resources.get(&id).unwrap();
+        for (index, (plane, texture)) in planes.iter().zip(&entry.planes).enumerate() {
+            let (w, h) = format.plane_size(index, width, height);
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                plane.data,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(plane.stride),
+                    rows_per_image: Some(h),
+                },
+                Extent3d {
+                    width: w,
+                    height: h,
+                    depth_or_array_layers: 1,
+                },
             );
-            // TODO: Recreate texture
-            return;
         }
 
-        // WGPU 0.28: ImageCopyTexture -> TexelCopyTextureInfo
-        // ImageDataLayout -> TexelCopyBufferLayout
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &entry.texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            data,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * width), // 4 bytes per pixel (RGBA)
-                rows_per_image: Some(height),
-            },
-            Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
+        let converter = self
+            .yuv_converter
+            .get_or_insert_with(|| YuvConverter::new(device));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("YUV Upload Encoder"),
+        });
+        converter.encode(
+            device,
+            &mut encoder,
+            format,
+            conversion,
+            &entry.planes,
+            &entry.texture,
         );
+        queue.submit(std::iter::once(encoder.finish()));
+        self.finish_upload(device, queue, id);
+
+        Ok(())
+    }
+
+    /// Registers a texture owned by the host under `id` without copying it,
+    /// replacing any resource already there.
+    ///
+    /// The manager keeps a reference to the texture, which keeps it alive
+    /// until the resource is removed, cleared or replaced by an upload; the
+    /// host must not `destroy` it while frames referencing it are in flight.
+    /// Contents are sampled as they are when the frame is submitted, so the
+    /// host can keep rendering into it between frames. Imported textures are
+    /// never written to, returned to the pool or evicted, and do not count
+    /// toward the memory budget.
+    ///
+    /// The texture must be a 2D, single-sampled texture created on the same
+    /// device with `TEXTURE_BINDING` usage and a filterable color format.
+    pub fn import_texture(
+        &mut self,
+        device: &Device,
+        id: Uuid,
+        import: ImportedTexture,
+    ) -> Result<(), RenderError> {
+        let ImportedTexture {
+            texture,
+            view,
+            mut color_space,
+            alpha_mode,
+        } = import;
+        let format = texture.format();
+        if !texture.usage().contains(TextureUsages::TEXTURE_BINDING) {
+            return Err(RenderError::InvalidSource(
+                "imported texture lacks TEXTURE_BINDING usage".into(),
+            ));
+        }
+        if texture.dimension() != TextureDimension::D2 || texture.sample_count() != 1 {
+            return Err(RenderError::InvalidSource(format!(
+                "imported texture must be 2D and single-sampled, got {:?} with {} samples",
+                texture.dimension(),
+                texture.sample_count()
+            )));
+        }
+        if format.sample_type(None, Some(device.features()))
+            != Some(TextureSampleType::Float { filterable: true })
+        {
+            return Err(RenderError::InvalidSource(format!(
+                "imported texture format {:?} cannot be filtered",
+                format
+            )));
+        }
+        if format.is_srgb() {
+            color_space.transfer = TransferFunction::Linear;
+        }
+
+        let view =
+            view.unwrap_or_else(|| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        self.retire(id);
+        let res = TextureResource {
+            width: texture.width(),
+            height: texture.height(),
+            texture,
+            format,
+            color_space,
+            pixel_format: None,
+            alpha_mode,
+            planes: vec![],
+            back_buffers: vec![],
+            staging: None,
+            view: Some(view),
+            imported: true,
+            last_used: AtomicU64::new(0),
+        };
+        self.touch(&res);
+        self.resources.insert(id, res);
+        Ok(())
     }
 
+    /// Declares the color space of a resource's pixel data. Resources default
+    /// to sRGB until told otherwise. Returns `false` if the resource is unknown.
+    pub fn set_color_space(&mut self, id: Uuid, color_space: ColorSpace) -> bool {
+        match self.resources.get_mut(&id) {
+            Some(res) => {
+                res.color_space = color_space;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Looks up a resource, marking it as recently used.
     pub fn get_resource(&self, id: &Uuid) -> Option<&TextureResource> {
-        self.resources.get(id)
+        let res = self.resources.get(id)?;
+        self.touch(res);
+        Some(res)
+    }
+
+    fn touch(&self, res: &TextureResource) {
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed) + 1;
+        res.last_used.store(tick, Ordering::Relaxed);
+    }
+
+    /// Key for a resource texture, with a full mip chain when enabled.
+    fn resource_key(
+        &self,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> TextureKey {
+        let levels = if self.mipmaps {
+            mip_level_count(width, height)
+        } else {
+            1
+        };
+        TextureKey::new(width, height, format, usage).with_mip_levels(levels)
+    }
+
+    fn finish_upload(&mut self, device: &Device, queue: &Queue, id: Uuid) {
+        if let Some(res) = self.resources.get(&id) {
+            self.touch(res);
+            if res.texture.mip_level_count() > 1 {
+                let generator = self
+                    .mipmap_generator
+                    .get_or_insert_with(|| MipmapGenerator::new(device));
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Mipmap Encoder"),
+                });
+                generator.encode(device, &mut encoder, &res.texture);
+                queue.submit(std::iter::once(encoder.finish()));
+            }
+        }
+        self.enforce_budget(Some(id));
+    }
+
+    /// Frees memory until usage fits the budget: idle pooled textures go
+    /// first, then least recently used resources other than `keep`.
+    fn enforce_budget(&mut self, keep: Option<Uuid>) {
+        let Some(budget) = self.budget else {
+            return;
+        };
+        let resident = self.memory_stats().resident_bytes;
+        self.pool.shrink_to(budget.saturating_sub(resident));
+
+        let mut usage = resident + self.pool.stats().idle_bytes;
+        while usage > budget {
+            let victim = self
+                .resources
+                .iter()
+                .filter(|(id, res)| Some(**id) != keep && !res.imported)
+                .min_by_key(|(_, res)| res.last_used.load(Ordering::Relaxed))
+                .map(|(id, _)| *id);
+            let Some(victim) = victim else {
+                break;
+            };
+            let res = self.resources.remove(&victim).unwrap();
+            usage -= res.byte_size();
+            self.evictions += 1;
+            if let Some(callback) = &mut self.on_evict {
+                callback(victim);
+            }
+        }
+    }
+
+    /// Removes a resource ahead of reallocation, returning its textures to
+    /// the pool. Yields the state carried over to the new allocation.
+    fn retire(&mut self, id: Uuid) -> Option<(ColorSpace, Option<wgpu::Buffer>)> {
+        let TextureResource {
+            texture,
+            color_space,
+            planes,
+            back_buffers,
+            staging,
+            imported,
+            ..
+        } = self.resources.remove(&id)?;
+        if imported {
+            // The texture stays with the host, and its color space may have
+            // been adjusted for the format, so uploads replacing it start over
+            return Some((ColorSpace::default(), None));
+        }
+        for texture in planes
+            .into_iter()
+            .chain(back_buffers)
+            .chain(std::iter::once(texture))
+        {
+            self.pool.release(texture);
+        }
+        Some((color_space, staging))
+    }
+}
+
+fn texture_bytes(texture: &Texture) -> u64 {
+    TextureKey::of(texture).byte_size()
+}
+
+fn extent(width: u32, height: u32) -> Extent3d {
+    Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
     }
 }
@@ -0,0 +1,251 @@
+use crate::model::ColorSpace;
+use glam::{Mat3, Vec3};
+use wgpu::TextureFormat;
+
+/// Planar / semi-planar layouts produced by video decoders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum YuvFormat {
+    /// 8-bit 4:2:0, Y plane + interleaved CbCr plane.
+    Nv12,
+    /// 8-bit 4:2:0, three planes.
+    I420,
+    /// 8-bit 4:2:2, three planes.
+    I422,
+    /// 10-bit 4:2:0 in the MSBs of 16-bit words, Y plane + interleaved CbCr plane.
+    P010,
+    /// 8-bit 4:4:4, three planes.
+    I444,
+    /// 10-bit 4:2:0 in the LSBs of 16-bit words, three planes (`yuv420p10le`).
+    I420P10,
+    /// 10-bit 4:2:2 in the LSBs of 16-bit words, three planes.
+    I422P10,
+    /// 10-bit 4:4:4 in the LSBs of 16-bit words, three planes.
+    I444P10,
+}
+
+impl YuvFormat {
+    /// Number of planes the decoder hands over.
+    pub fn plane_count(self) -> usize {
+        match self {
+            Self::Nv12 | Self::P010 => 2,
+            _ => 3,
+        }
+    }
+
+    /// Horizontal / vertical chroma subsampling as log2 factors.
+    pub fn chroma_shift(self) -> (u32, u32) {
+        match self {
+            Self::Nv12 | Self::I420 | Self::P010 | Self::I420P10 => (1, 1),
+            Self::I422 | Self::I422P10 => (1, 0),
+            Self::I444 | Self::I444P10 => (0, 0),
+        }
+    }
+
+    /// Significant bits per sample.
+    pub fn bit_depth(self) -> u32 {
+        match self {
+            Self::P010 | Self::I420P10 | Self::I422P10 | Self::I444P10 => 10,
+            _ => 8,
+        }
+    }
+
+    /// Padding bits below each sample: 6 for P010, none otherwise.
+    pub fn code_shift(self) -> u32 {
+        match self {
+            Self::P010 => 6,
+            _ => 0,
+        }
+    }
+
+    /// Whether chroma is stored as interleaved CbCr pairs in plane 1.
+    pub fn is_semi_planar(self) -> bool {
+        matches!(self, Self::Nv12 | Self::P010)
+    }
+
+    /// GPU format of each plane. Integer formats keep 16-bit planes exact
+    /// without requiring `TEXTURE_FORMAT_16BIT_NORM`.
+    pub fn plane_format(self, plane: usize) -> TextureFormat {
+        match (self, plane) {
+            (Self::P010, 0) => TextureFormat::R16Uint,
+            (Self::P010, _) => TextureFormat::Rg16Uint,
+            (Self::I420P10 | Self::I422P10 | Self::I444P10, _) => TextureFormat::R16Uint,
+            (Self::Nv12, 1) => TextureFormat::Rg8Uint,
+            _ => TextureFormat::R8Uint,
+        }
+    }
+
+    /// Dimensions of `plane` for a `width` x `height` frame.
+    pub fn plane_size(self, plane: usize, width: u32, height: u32) -> (u32, u32) {
+        if plane == 0 {
+            return (width, height);
+        }
+        let (sx, sy) = self.chroma_shift();
+        (width.div_ceil(1 << sx), height.div_ceil(1 << sy))
+    }
+
+    /// Tightly packed row size of `plane` in bytes.
+    pub fn plane_row_bytes(self, plane: usize, width: u32) -> u32 {
+        let (plane_width, _) = self.plane_size(plane, width, 1);
+        plane_width * self.plane_format(plane).block_copy_size(None).unwrap()
+    }
+
+    /// Format of the RGBA texture the planes are converted into.
+    pub fn converted_format(self) -> TextureFormat {
+        match self.bit_depth() {
+            8 => TextureFormat::Rgba8Unorm,
+            _ => TextureFormat::Rgba16Float,
+        }
+    }
+}
+
+/// Y'CbCr -> R'G'B' matrix coefficients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum YuvMatrix {
+    Bt601,
+    Bt709,
+    Bt2020,
+}
+
+impl YuvMatrix {
+    /// (Kr, Kb) luma weights.
+    fn weights(self) -> (f32, f32) {
+        match self {
+            Self::Bt601 => (0.299, 0.114),
+            Self::Bt709 => (0.2126, 0.0722),
+            Self::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+
+    /// Matrix applied to (Y', Cb, Cr) with chroma centered on zero.
+    pub fn to_rgb(self) -> Mat3 {
+        let (kr, kb) = self.weights();
+        let kg = 1.0 - kr - kb;
+        Mat3::from_cols(
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(0.0, -2.0 * kb * (1.0 - kb) / kg, 2.0 * (1.0 - kb)),
+            Vec3::new(2.0 * (1.0 - kr), -2.0 * kr * (1.0 - kr) / kg, 0.0),
+        )
+    }
+
+    /// Matrix producing (Y', Cb, Cr) from R'G'B', the inverse of `to_rgb`.
+    pub fn from_rgb(self) -> Mat3 {
+        self.to_rgb().inverse()
+    }
+
+    /// Color space conventionally paired with this matrix, used until the
+    /// host declares something else.
+    pub fn default_color_space(self) -> ColorSpace {
+        match self {
+            Self::Bt601 | Self::Bt709 => ColorSpace::REC709,
+            Self::Bt2020 => ColorSpace::REC2020,
+        }
+    }
+}
+
+/// Quantization range of the coded samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum YuvRange {
+    /// Y' in [16, 235], CbCr in [16, 240] (scaled for higher bit depths).
+    #[default]
+    Limited,
+    /// Full code range, as used by JPEG.
+    Full,
+}
+
+impl YuvRange {
+    /// Per-channel (scale, offset) turning integer codes of `bits` depth
+    /// into Y' in [0, 1] and CbCr in [-0.5, 0.5].
+    pub fn code_scale_offset(self, bits: u32) -> (Vec3, Vec3) {
+        let k = (1u32 << (bits - 8)) as f32;
+        let max = ((1u32 << bits) - 1) as f32;
+        let mid = (1u32 << (bits - 1)) as f32;
+        match self {
+            Self::Limited => (
+                Vec3::new(1.0 / (219.0 * k), 1.0 / (224.0 * k), 1.0 / (224.0 * k)),
+                Vec3::new(-16.0 / 219.0, -mid / (224.0 * k), -mid / (224.0 * k)),
+            ),
+            Self::Full => (
+                Vec3::splat(1.0 / max),
+                Vec3::new(0.0, -mid / max, -mid / max),
+            ),
+        }
+    }
+}
+
+/// Position of chroma samples relative to the luma grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ChromaSiting {
+    /// Co-sited horizontally, centered vertically (MPEG-2, H.264 default).
+    #[default]
+    Left,
+    /// Centered between luma samples (JPEG, MPEG-1).
+    Center,
+    /// Co-sited with the top-left luma sample (BT.2020 / HEVC UHD).
+    TopLeft,
+}
+
+impl ChromaSiting {
+    /// Offset of the first chroma sample from the first luma sample, in luma
+    /// pixels, for the given subsampling shifts.
+    pub fn offset(self, (sx, sy): (u32, u32)) -> (f32, f32) {
+        let centered = |shift: u32| ((1u32 << shift) as f32 - 1.0) * 0.5;
+        match self {
+            Self::Left => (0.0, centered(sy)),
+            Self::Center => (centered(sx), centered(sy)),
+            Self::TopLeft => (0.0, 0.0),
+        }
+    }
+}
+
+/// Filter used when producing subsampled chroma from full-resolution RGB.
+///
+/// The discriminants are shared with the `FILTER_*` constants in `yuv_pack.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(u32)]
+pub enum ChromaFilter {
+    /// Takes the pixel at the chroma site. Fast, aliases on fine detail.
+    Nearest = 0,
+    /// Averages the luma pixels each chroma sample covers, ignoring siting.
+    Box = 1,
+    /// Triangle filter centered on the chroma site.
+    #[default]
+    Tent = 2,
+}
+
+/// How decoded Y'CbCr samples map to R'G'B'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct YuvConversion {
+    pub matrix: YuvMatrix,
+    pub range: YuvRange,
+    pub siting: ChromaSiting,
+}
+
+impl YuvConversion {
+    pub fn new(matrix: YuvMatrix, range: YuvRange) -> Self {
+        Self {
+            matrix,
+            range,
+            siting: ChromaSiting::default(),
+        }
+    }
+}
+
+impl Default for YuvConversion {
+    fn default() -> Self {
+        Self::new(YuvMatrix::Bt709, YuvRange::Limited)
+    }
+}
+
+/// One plane of decoder output. `stride` is the distance between rows in
+/// bytes and may include padding.
+#[derive(Debug, Clone, Copy)]
+pub struct YuvPlane<'a> {
+    pub data: &'a [u8],
+    pub stride: u32,
+}
+
+impl<'a> YuvPlane<'a> {
+    pub fn new(data: &'a [u8], stride: u32) -> Self {
+        Self { data, stride }
+    }
+}
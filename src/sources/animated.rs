@@ -0,0 +1,292 @@
+use super::provider::{FrameClock, FrameData, FrameFormat, FrameProvider, VideoFrame};
+use crate::core::RenderError;
+use crate::model::ColorSpace;
+use crate::resources::{DecodedImage, PixelFormat, PixelLayout, TextureManager};
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, Frame, ImageFormat};
+use std::io::Cursor;
+use std::path::Path;
+use uuid::Uuid;
+use wgpu::{Device, Queue};
+
+/// How many times an animation plays before holding its last frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopCount {
+    Infinite,
+    /// Total number of plays, at least 1.
+    Finite(u32),
+}
+
+/// One fully composited frame of an animation.
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    /// Straight-alpha RGBA8 pixels covering the whole canvas.
+    pub data: Vec<u8>,
+    /// Offset from the start of the loop, in seconds.
+    pub start: f64,
+    pub duration: f64,
+}
+
+/// An animated GIF, APNG or animated WebP, decoded up front.
+///
+/// Frames are stored composited, with each format's disposal and blending
+/// rules already applied, so any frame can be shown without replaying the
+/// ones before it.
+#[derive(Debug, Clone)]
+pub struct AnimatedImage {
+    pub width: u32,
+    pub height: u32,
+    pub loop_count: LoopCount,
+    frames: Vec<AnimationFrame>,
+}
+
+impl AnimatedImage {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RenderError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Decodes an animation. Still PNGs and WebPs load as a single frame.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RenderError> {
+        let format = image::guess_format(bytes)?;
+        let (frames, loop_count) = match format {
+            ImageFormat::Gif => {
+                let frames = GifDecoder::new(Cursor::new(bytes))?
+                    .into_frames()
+                    .collect_frames()?;
+                (frames, gif_loop_count(bytes))
+            }
+            ImageFormat::Png => {
+                let decoder = PngDecoder::new(Cursor::new(bytes))?;
+                if !decoder.is_apng()? {
+                    let image = image::DynamicImage::from_decoder(decoder)?;
+                    (vec![Frame::new(image.into_rgba8())], LoopCount::Infinite)
+                } else {
+                    let frames = decoder.apng()?.into_frames().collect_frames()?;
+                    (frames, apng_loop_count(bytes))
+                }
+            }
+            ImageFormat::WebP => {
+                let decoder = WebPDecoder::new(Cursor::new(bytes))?;
+                if !decoder.has_animation() {
+                    let image = image::DynamicImage::from_decoder(decoder)?;
+                    (vec![Frame::new(image.into_rgba8())], LoopCount::Infinite)
+                } else {
+                    let frames = decoder.into_frames().collect_frames()?;
+                    (frames, webp_loop_count(bytes))
+                }
+            }
+            format => {
+                return Err(RenderError::InvalidSource(format!(
+                    "{:?} images can't be animated",
+                    format
+                )));
+            }
+        };
+
+        let (width, height) = frames
+            .first()
+            .map(|f| f.buffer().dimensions())
+            .ok_or_else(|| RenderError::InvalidSource("animation has no frames".into()))?;
+        let mut start = 0.0;
+        let frames = frames
+            .into_iter()
+            .map(|frame| {
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                let mut duration = numer as f64 / denom.max(1) as f64 / 1000.0;
+                // Browsers bump near-zero GIF delays to 100ms, and assets rely on it
+                if format == ImageFormat::Gif && duration <= 0.01 {
+                    duration = 0.1;
+                }
+                let frame = AnimationFrame {
+                    data: frame.into_buffer().into_raw(),
+                    start,
+                    duration,
+                };
+                start += duration;
+                frame
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            loop_count,
+            frames,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn frame(&self, index: usize) -> &AnimationFrame {
+        &self.frames[index]
+    }
+
+    /// Length of one loop in seconds.
+    pub fn loop_duration(&self) -> f64 {
+        self.frames.last().map_or(0.0, |f| f.start + f.duration)
+    }
+
+    /// Index of the frame shown at `time` seconds after the animation
+    /// started. Finite animations hold their last frame once done.
+    pub fn frame_at(&self, time: f64) -> usize {
+        let duration = self.loop_duration();
+        if duration <= 0.0 || time <= 0.0 {
+            return 0;
+        }
+        if let LoopCount::Finite(plays) = self.loop_count
+            && time >= duration * plays as f64
+        {
+            return self.frames.len() - 1;
+        }
+        // Nudge so times computed as frame starts don't land a frame early
+        let t = (time + 1e-9).rem_euclid(duration);
+        self.frames
+            .partition_point(|f| f.start <= t)
+            .saturating_sub(1)
+    }
+}
+
+impl FrameProvider for AnimatedImage {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Average rate over one loop; frame delays may vary.
+    fn frame_rate(&self) -> f64 {
+        match self.loop_duration() {
+            duration if duration > 0.0 => self.frames.len() as f64 / duration,
+            _ => 0.0,
+        }
+    }
+
+    fn pixel_format(&self) -> FrameFormat {
+        FrameFormat::Pixels(PixelFormat::Rgba8)
+    }
+
+    fn frame_count(&self) -> Option<u64> {
+        Some(self.frames.len() as u64)
+    }
+
+    fn frame_index_at(&self, time: f64) -> u64 {
+        self.frame_at(time) as u64
+    }
+
+    fn clock(&self) -> FrameClock {
+        // Timing only; the pixels stay with the provider
+        let timing = AnimatedImage {
+            frames: self
+                .frames
+                .iter()
+                .map(|f| AnimationFrame {
+                    data: Vec::new(),
+                    start: f.start,
+                    duration: f.duration,
+                })
+                .collect(),
+            ..*self
+        };
+        FrameClock::new(move |time| timing.frame_at(time) as u64)
+    }
+
+    fn decode_frame(&mut self, index: u64) -> Result<VideoFrame, RenderError> {
+        let frame = self.frames.get(index as usize).ok_or_else(|| {
+            RenderError::InvalidSource(format!(
+                "frame {} is past the last of {}",
+                index,
+                self.frames.len()
+            ))
+        })?;
+        Ok(VideoFrame {
+            index,
+            timestamp: frame.start,
+            data: FrameData::Pixels(DecodedImage {
+                layout: PixelLayout::new(PixelFormat::Rgba8, self.width, self.height),
+                data: frame.data.clone(),
+                color_space: ColorSpace::SRGB,
+            }),
+        })
+    }
+}
+
+/// GIF loop counts come from the NETSCAPE2.0 extension and count repeats
+/// after the first play; without one the animation plays once.
+fn gif_loop_count(bytes: &[u8]) -> LoopCount {
+    let repeat = gif::DecodeOptions::new()
+        .read_info(Cursor::new(bytes))
+        .map(|decoder| decoder.repeat());
+    match repeat {
+        Ok(gif::Repeat::Infinite) => LoopCount::Infinite,
+        Ok(gif::Repeat::Finite(repeats)) => LoopCount::Finite(repeats as u32 + 1),
+        Err(_) => LoopCount::Finite(1),
+    }
+}
+
+/// APNG's acTL counts plays, 0 meaning forever.
+fn apng_loop_count(bytes: &[u8]) -> LoopCount {
+    let plays = png::Decoder::new(Cursor::new(bytes))
+        .read_info()
+        .ok()
+        .and_then(|reader| reader.info().animation_control().map(|ac| ac.num_plays));
+    match plays {
+        Some(0) | None => LoopCount::Infinite,
+        Some(plays) => LoopCount::Finite(plays),
+    }
+}
+
+fn webp_loop_count(bytes: &[u8]) -> LoopCount {
+    match image_webp::WebPDecoder::new(Cursor::new(bytes)).map(|d| d.loop_count()) {
+        Ok(image_webp::LoopCount::Times(plays)) => LoopCount::Finite(plays.get() as u32),
+        _ => LoopCount::Infinite,
+    }
+}
+
+/// Shows an `AnimatedImage` through a `TextureManager` resource.
+///
+/// Call `update` with the layer's time before each render; only frame
+/// changes are uploaded.
+pub struct AnimatedImageSource {
+    pub resource_id: Uuid,
+    animation: AnimatedImage,
+    shown: Option<usize>,
+}
+
+impl AnimatedImageSource {
+    pub fn new(resource_id: Uuid, animation: AnimatedImage) -> Self {
+        Self {
+            resource_id,
+            animation,
+            shown: None,
+        }
+    }
+
+    pub fn animation(&self) -> &AnimatedImage {
+        &self.animation
+    }
+
+    /// Uploads the frame for `time` seconds and returns its index.
+    pub fn update(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        texture_manager: &mut TextureManager,
+        time: f64,
+    ) -> Result<usize, RenderError> {
+        let index = self.animation.frame_at(time);
+        if self.shown == Some(index) && texture_manager.get_resource(&self.resource_id).is_some() {
+            return Ok(index);
+        }
+        let layout = PixelLayout::new(
+            PixelFormat::Rgba8,
+            self.animation.width,
+            self.animation.height,
+        );
+        let frame = self.animation.frame(index);
+        texture_manager.upload_pixels(device, queue, self.resource_id, &layout, &frame.data)?;
+        self.shown = Some(index);
+        Ok(index)
+    }
+}
@@ -0,0 +1,11 @@
+pub mod animated;
+pub mod provider;
+pub mod sequence;
+pub mod y4m;
+pub use animated::{AnimatedImage, AnimatedImageSource, AnimationFrame, LoopCount};
+pub use provider::{
+    FrameClock, FrameData, FrameFormat, FramePrefetcher, FrameProvider, PlaneData, StreamInfo,
+    VideoFrame, upload_frame,
+};
+pub use sequence::{ImageSequence, ImageSequenceSource, MissingFramePolicy, SequencePattern};
+pub use y4m::{Y4mHeader, Y4mReader};
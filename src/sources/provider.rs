@@ -0,0 +1,463 @@
+use crate::core::RenderError;
+use crate::model::{FrameDescription, LayerSource};
+use crate::resources::{
+    DecodedImage, PixelFormat, TextureManager, YuvConversion, YuvFormat, YuvPlane,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use uuid::Uuid;
+use wgpu::{Device, Queue};
+
+/// Layout of the frames a provider produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    Pixels(PixelFormat),
+    Yuv(YuvFormat),
+}
+
+/// One plane of a decoded Y'CbCr frame.
+#[derive(Debug, Clone)]
+pub struct PlaneData {
+    pub data: Vec<u8>,
+    /// Bytes between rows, padding included.
+    pub stride: u32,
+}
+
+/// Pixels of a decoded frame, in either of the forms `TextureManager`
+/// uploads.
+#[derive(Debug, Clone)]
+pub enum FrameData {
+    Pixels(DecodedImage),
+    Yuv {
+        format: YuvFormat,
+        width: u32,
+        height: u32,
+        planes: Vec<PlaneData>,
+        conversion: YuvConversion,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct VideoFrame {
+    pub index: u64,
+    /// Presentation time in seconds.
+    pub timestamp: f64,
+    pub data: FrameData,
+}
+
+/// A source of decoded frames, e.g. a video decoder supplied by the host.
+///
+/// Providers are driven from a `FramePrefetcher` worker thread. Frames are
+/// requested by index, mostly in ascending order; `seek` is called before
+/// any request that doesn't follow the previous one.
+pub trait FrameProvider: Send {
+    /// Called once when the provider is registered, before any other
+    /// decoding call.
+    fn open(&mut self) -> Result<(), RenderError> {
+        Ok(())
+    }
+
+    fn dimensions(&self) -> (u32, u32);
+
+    /// Nominal frames per second.
+    fn frame_rate(&self) -> f64;
+
+    fn pixel_format(&self) -> FrameFormat;
+
+    /// Number of frames, if known.
+    fn frame_count(&self) -> Option<u64> {
+        None
+    }
+
+    /// Index of the frame shown at `time` seconds. The default assumes a
+    /// constant frame rate and clamps to the last frame.
+    fn frame_index_at(&self, time: f64) -> u64 {
+        constant_rate_index(self.frame_rate(), self.frame_count(), time)
+    }
+
+    /// Same mapping as `frame_index_at`, detached from the provider so the
+    /// prefetcher can resolve the playhead while a decode is in flight.
+    /// Providers that override `frame_index_at` should override this too.
+    fn clock(&self) -> FrameClock {
+        FrameClock::constant(self.frame_rate(), self.frame_count())
+    }
+
+    /// Repositions the decoder so `decode_frame(index)` can follow.
+    fn seek(&mut self, index: u64) -> Result<(), RenderError> {
+        let _ = index;
+        Ok(())
+    }
+
+    fn decode_frame(&mut self, index: u64) -> Result<VideoFrame, RenderError>;
+
+    /// Decodes the frame shown at `time` seconds.
+    fn decode_frame_at(&mut self, time: f64) -> Result<VideoFrame, RenderError> {
+        let index = self.frame_index_at(time);
+        self.seek(index)?;
+        self.decode_frame(index)
+    }
+}
+
+/// Maps presentation times to frame indices, taken from a provider at
+/// registration.
+#[derive(Clone)]
+pub struct FrameClock(Arc<dyn Fn(f64) -> u64 + Send + Sync>);
+
+impl FrameClock {
+    pub fn new(index_at: impl Fn(f64) -> u64 + Send + Sync + 'static) -> Self {
+        Self(Arc::new(index_at))
+    }
+
+    /// Frames at a constant rate, holding the last one if the count is
+    /// known.
+    pub fn constant(frame_rate: f64, frame_count: Option<u64>) -> Self {
+        Self::new(move |time| constant_rate_index(frame_rate, frame_count, time))
+    }
+
+    pub fn frame_index_at(&self, time: f64) -> u64 {
+        (self.0)(time)
+    }
+}
+
+impl std::fmt::Debug for FrameClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameClock").finish_non_exhaustive()
+    }
+}
+
+fn constant_rate_index(frame_rate: f64, frame_count: Option<u64>, time: f64) -> u64 {
+    // Nudge so times computed as n / fps don't land on frame n - 1
+    let index = (time * frame_rate + 1e-6).floor().max(0.0) as u64;
+    match frame_count {
+        Some(count) => index.min(count.saturating_sub(1)),
+        None => index,
+    }
+}
+
+/// Snapshot of a provider's stream properties, taken at registration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamInfo {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+    pub frame_count: Option<u64>,
+    pub pixel_format: FrameFormat,
+}
+
+struct Decoder {
+    provider: Box<dyn FrameProvider>,
+    // Index the provider will decode without seeking
+    next: Option<u64>,
+}
+
+impl Decoder {
+    fn decode(&mut self, index: u64) -> Result<VideoFrame, RenderError> {
+        if self.next != Some(index) {
+            self.provider.seek(index)?;
+        }
+        self.next = None;
+        let frame = self.provider.decode_frame(index)?;
+        self.next = Some(index + 1);
+        Ok(frame)
+    }
+}
+
+#[derive(Default)]
+struct CacheState {
+    // Frame the host is showing; read-ahead starts here
+    target: Option<u64>,
+    frames: BTreeMap<u64, Arc<VideoFrame>>,
+    // Read-ahead stops at a frame that failed until the target moves
+    failed: Option<u64>,
+    shutdown: bool,
+}
+
+struct Shared {
+    decoder: Mutex<Decoder>,
+    // Resolves times without waiting on a decode that holds `decoder`
+    clock: FrameClock,
+    cache: Mutex<CacheState>,
+    wake: Condvar,
+    read_ahead: u64,
+    last: Option<u64>,
+}
+
+impl Shared {
+    /// Frames worth keeping while `target` is shown.
+    fn window(&self, target: u64) -> std::ops::RangeInclusive<u64> {
+        let end = target.saturating_add(self.read_ahead);
+        target..=self.last.map_or(end, |last| end.min(last))
+    }
+}
+
+struct Registration {
+    info: StreamInfo,
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+    shown: Option<u64>,
+}
+
+/// Pulls frames from registered `FrameProvider`s into `TextureManager`
+/// resources, decoding ahead of the playhead on one worker per provider.
+///
+/// Register a provider under the `resource_id` its layers use, then call
+/// `prepare` with each `FrameDescription` before rendering it.
+pub struct FramePrefetcher {
+    registrations: HashMap<Uuid, Registration>,
+    read_ahead: u64,
+}
+
+impl Default for FramePrefetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FramePrefetcher {
+    /// Reads up to 8 frames ahead.
+    pub fn new() -> Self {
+        Self::with_read_ahead(8)
+    }
+
+    pub fn with_read_ahead(frames: u64) -> Self {
+        Self {
+            registrations: HashMap::new(),
+            read_ahead: frames,
+        }
+    }
+
+    /// Opens `provider` and starts decoding for `resource_id`, replacing any
+    /// provider already registered there.
+    pub fn register(
+        &mut self,
+        resource_id: Uuid,
+        mut provider: impl FrameProvider + 'static,
+    ) -> Result<StreamInfo, RenderError> {
+        provider.open()?;
+        let (width, height) = provider.dimensions();
+        let info = StreamInfo {
+            width,
+            height,
+            frame_rate: provider.frame_rate(),
+            frame_count: provider.frame_count(),
+            pixel_format: provider.pixel_format(),
+        };
+        let clock = provider.clock();
+
+        let shared = Arc::new(Shared {
+            decoder: Mutex::new(Decoder {
+                provider: Box::new(provider),
+                next: None,
+            }),
+            clock,
+            cache: Mutex::new(CacheState::default()),
+            wake: Condvar::new(),
+            read_ahead: self.read_ahead,
+            last: info.frame_count.map(|count| count.saturating_sub(1)),
+        });
+        let worker = {
+            let shared = shared.clone();
+            std::thread::Builder::new()
+                .name(format!("frame-prefetch-{}", resource_id))
+                .spawn(move || prefetch_worker(&shared))?
+        };
+
+        self.unregister(&resource_id);
+        self.registrations.insert(
+            resource_id,
+            Registration {
+                info,
+                shared,
+                worker: Some(worker),
+                shown: None,
+            },
+        );
+        Ok(info)
+    }
+
+    /// Stops decoding for `resource_id`. Returns `false` if nothing was
+    /// registered there. The resource's texture is left in place.
+    pub fn unregister(&mut self, resource_id: &Uuid) -> bool {
+        self.registrations.remove(resource_id).is_some()
+    }
+
+    pub fn info(&self, resource_id: &Uuid) -> Option<&StreamInfo> {
+        self.registrations.get(resource_id).map(|r| &r.info)
+    }
+
+    /// Frames decoded ahead and cached for `resource_id`.
+    pub fn cached_frames(&self, resource_id: &Uuid) -> usize {
+        self.registrations
+            .get(resource_id)
+            .map_or(0, |r| r.shared.cache.lock().unwrap().frames.len())
+    }
+
+    /// Uploads the frame at `frame.time` for every video layer with a
+    /// registered provider.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        texture_manager: &mut TextureManager,
+        frame: &FrameDescription,
+    ) -> Result<(), RenderError> {
+        for layer in &frame.layers {
+            if let LayerSource::Video { resource_id } = layer.source
+                && self.registrations.contains_key(&resource_id)
+            {
+                self.update(device, queue, texture_manager, resource_id, frame.time)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Uploads the frame at `time` seconds for one resource, decoding it on
+    /// the spot if read-ahead hasn't reached it. Returns the frame index,
+    /// or `None` if no provider is registered for `resource_id`.
+    pub fn update(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        texture_manager: &mut TextureManager,
+        resource_id: Uuid,
+        time: f64,
+    ) -> Result<Option<u64>, RenderError> {
+        let Some(registration) = self.registrations.get_mut(&resource_id) else {
+            return Ok(None);
+        };
+        let shared = &registration.shared;
+        let index = shared.clock.frame_index_at(time);
+
+        let cached = {
+            let mut cache = shared.cache.lock().unwrap();
+            let cached = cache.frames.get(&index).cloned();
+            if cache.target != Some(index) {
+                let window = shared.window(index);
+                cache.frames.retain(|i, _| window.contains(i));
+                cache.target = Some(index);
+                cache.failed = None;
+            }
+            cached
+        };
+        shared.wake.notify_all();
+
+        if registration.shown == Some(index) && texture_manager.get_resource(&resource_id).is_some()
+        {
+            return Ok(Some(index));
+        }
+        let frame = match cached {
+            Some(frame) => frame,
+            None => {
+                // The worker may have finished it while we waited for the decoder
+                let mut decoder = shared.decoder.lock().unwrap();
+                let cached = shared.cache.lock().unwrap().frames.get(&index).cloned();
+                match cached {
+                    Some(frame) => frame,
+                    None => {
+                        let frame = Arc::new(decoder.decode(index)?);
+                        let mut cache = shared.cache.lock().unwrap();
+                        cache.frames.insert(index, frame.clone());
+                        frame
+                    }
+                }
+            }
+        };
+
+        upload_frame(device, queue, texture_manager, resource_id, &frame.data)?;
+        registration.shown = Some(index);
+        Ok(Some(index))
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.shared.cache.lock().unwrap().shutdown = true;
+        self.shared.wake.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Uploads decoded frame data to the resource `id`.
+pub fn upload_frame(
+    device: &Device,
+    queue: &Queue,
+    texture_manager: &mut TextureManager,
+    id: Uuid,
+    data: &FrameData,
+) -> Result<(), RenderError> {
+    match data {
+        FrameData::Pixels(image) => {
+            texture_manager.upload_image(device, queue, id, image)?;
+        }
+        FrameData::Yuv {
+            format,
+            width,
+            height,
+            planes,
+            conversion,
+        } => {
+            let planes: Vec<YuvPlane> = planes
+                .iter()
+                .map(|plane| YuvPlane::new(&plane.data, plane.stride))
+                .collect();
+            texture_manager.update_yuv_texture(
+                device,
+                queue,
+                id,
+                *format,
+                *width,
+                *height,
+                &planes,
+                *conversion,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn prefetch_worker(shared: &Shared) {
+    loop {
+        let index = {
+            let mut cache = shared.cache.lock().unwrap();
+            loop {
+                if cache.shutdown {
+                    return;
+                }
+                let missing = cache.target.and_then(|target| {
+                    shared
+                        .window(target)
+                        .take_while(|&i| Some(i) != cache.failed)
+                        .find(|i| !cache.frames.contains_key(i))
+                });
+                match missing {
+                    Some(index) => break index,
+                    None => cache = shared.wake.wait(cache).unwrap(),
+                }
+            }
+        };
+
+        let mut decoder = shared.decoder.lock().unwrap();
+        if shared.cache.lock().unwrap().frames.contains_key(&index) {
+            continue;
+        }
+        let result = decoder.decode(index);
+        drop(decoder);
+
+        let mut cache = shared.cache.lock().unwrap();
+        match result {
+            // The playhead may have moved on while decoding
+            Ok(frame) => {
+                if cache
+                    .target
+                    .is_some_and(|t| shared.window(t).contains(&index))
+                {
+                    cache.frames.insert(index, Arc::new(frame));
+                }
+            }
+            Err(_) => cache.failed = Some(index),
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use super::provider::{FrameData, FrameFormat, FramePrefetcher, FrameProvider, VideoFrame};
+use crate::core::RenderError;
+use crate::model::ColorSpace;
+use crate::resources::{DecodedImage, PixelFormat, PixelLayout, TextureManager};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use wgpu::{Device, Queue};
+
+/// How a sequence's files are named.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequencePattern {
+    /// printf-style, e.g. `shots/plate_%04d.exr`. `%d` matches any number
+    /// of digits, `%0Nd` at least N.
+    Printf(String),
+    /// Shell-style `*` / `?` wildcards in the file name, e.g.
+    /// `shots/plate_*.exr`. The frame number is the last run of digits in
+    /// each matching name.
+    Glob(String),
+}
+
+impl SequencePattern {
+    /// Path of frame `number` under a `Printf` pattern, zero-padded to the
+    /// field width. Glob patterns can't name files and fail.
+    pub fn file_path(&self, number: u32) -> Result<PathBuf, RenderError> {
+        match Matcher::parse(self)? {
+            (
+                dir,
+                Matcher::Printf {
+                    prefix,
+                    width,
+                    suffix,
+                },
+            ) => Ok(dir.join(format!("{}{:0width$}{}", prefix, number, suffix))),
+            (_, Matcher::Glob(_)) => Err(RenderError::InvalidSource(format!(
+                "{:?} can't name frames",
+                self
+            ))),
+        }
+    }
+}
+
+/// What to show for frame numbers with no file on disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingFramePolicy {
+    /// Keep showing the closest earlier frame.
+    #[default]
+    Hold,
+    /// Show opaque black.
+    Black,
+    /// Fail with `RenderError::MissingFrame`.
+    Error,
+}
+
+/// A numbered image sequence resolved against the files on disk.
+#[derive(Debug, Clone)]
+pub struct ImageSequence {
+    pub frame_rate: f64,
+    pub missing: MissingFramePolicy,
+    files: BTreeMap<u32, PathBuf>,
+    start: u32,
+    end: u32,
+    // Probed from the first file
+    layout: PixelLayout,
+}
+
+impl ImageSequence {
+    /// Scans the pattern's directory for frames. The sequence starts at
+    /// `start_number`, or at the lowest frame number found when `None`, and
+    /// ends at the highest one.
+    pub fn open(
+        pattern: &SequencePattern,
+        start_number: Option<u32>,
+        frame_rate: f64,
+    ) -> Result<Self, RenderError> {
+        if !frame_rate.is_finite() || frame_rate <= 0.0 {
+            return Err(RenderError::InvalidSource(format!(
+                "frame rate must be positive, got {}",
+                frame_rate
+            )));
+        }
+        let (dir, matcher) = Matcher::parse(pattern)?;
+        let mut files = BTreeMap::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            if let Some(number) = entry.file_name().to_str().and_then(|n| matcher.number(n)) {
+                files.insert(number, entry.path());
+            }
+        }
+
+        let (Some(&first), Some(&end)) = (files.keys().next(), files.keys().next_back()) else {
+            return Err(RenderError::InvalidSource(format!(
+                "no frames match {:?}",
+                pattern
+            )));
+        };
+        let start = start_number.unwrap_or(first);
+        if start > end {
+            return Err(RenderError::InvalidSource(format!(
+                "start number {} is past the last frame {}",
+                start, end
+            )));
+        }
+
+        let layout = DecodedImage::probe(&files[&first])?;
+
+        Ok(Self {
+            frame_rate,
+            missing: MissingFramePolicy::default(),
+            files,
+            start,
+            end,
+            layout,
+        })
+    }
+
+    pub fn with_missing_frames(mut self, policy: MissingFramePolicy) -> Self {
+        self.missing = policy;
+        self
+    }
+
+    /// Frame numbers covered, gaps included.
+    pub fn frame_range(&self) -> std::ops::RangeInclusive<u32> {
+        self.start..=self.end
+    }
+
+    /// Dimensions of the first frame on disk.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.layout.width, self.layout.height)
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.end - self.start + 1
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.frame_count() as f64 / self.frame_rate
+    }
+
+    /// Frame number shown at `time` seconds. Times outside the sequence
+    /// clamp to its first or last frame.
+    pub fn frame_at(&self, time: f64) -> u32 {
+        // Nudge so times computed as n / fps don't land on frame n - 1
+        let index = (time * self.frame_rate + 1e-6).floor().max(0.0) as u64;
+        self.start + index.min(self.end as u64 - self.start as u64) as u32
+    }
+
+    pub fn path(&self, number: u32) -> Option<&Path> {
+        self.files.get(&number).map(PathBuf::as_path)
+    }
+
+    /// Resolves a frame number against the missing-frame policy: the file
+    /// to show, or `None` for black.
+    pub fn resolve(&self, number: u32) -> Result<Option<(u32, &Path)>, RenderError> {
+        if let Some(path) = self.files.get(&number) {
+            return Ok(Some((number, path)));
+        }
+        match self.missing {
+            MissingFramePolicy::Hold => {
+                // Before the first file there is nothing to hold, so use it
+                let held = self
+                    .files
+                    .range(..number)
+                    .next_back()
+                    .or_else(|| self.files.range(number..).next());
+                Ok(held.map(|(&n, path)| (n, path.as_path())))
+            }
+            MissingFramePolicy::Black => Ok(None),
+            MissingFramePolicy::Error => Err(RenderError::MissingFrame(number)),
+        }
+    }
+}
+
+impl FrameProvider for ImageSequence {
+    fn dimensions(&self) -> (u32, u32) {
+        ImageSequence::dimensions(self)
+    }
+
+    fn frame_rate(&self) -> f64 {
+        self.frame_rate
+    }
+
+    fn pixel_format(&self) -> FrameFormat {
+        FrameFormat::Pixels(self.layout.format)
+    }
+
+    fn frame_count(&self) -> Option<u64> {
+        Some(ImageSequence::frame_count(self) as u64)
+    }
+
+    fn frame_index_at(&self, time: f64) -> u64 {
+        (self.frame_at(time) - self.start) as u64
+    }
+
+    fn decode_frame(&mut self, index: u64) -> Result<VideoFrame, RenderError> {
+        let number = self.start.saturating_add(index.min(u32::MAX as u64) as u32);
+        let image = match self.resolve(number)? {
+            Some((_, path)) => DecodedImage::open(path)?,
+            None => black_frame(self.layout.width, self.layout.height),
+        };
+        Ok(VideoFrame {
+            index,
+            timestamp: index as f64 / self.frame_rate,
+            data: FrameData::Pixels(image),
+        })
+    }
+}
+
+fn black_frame(width: u32, height: u32) -> DecodedImage {
+    DecodedImage {
+        layout: PixelLayout::new(PixelFormat::Rgba8, width, height),
+        data: [0, 0, 0, 255].repeat((width * height) as usize),
+        color_space: ColorSpace::SRGB,
+    }
+}
+
+enum Matcher {
+    Printf {
+        prefix: String,
+        width: usize,
+        suffix: String,
+    },
+    Glob(String),
+}
+
+impl Matcher {
+    fn parse(pattern: &SequencePattern) -> Result<(PathBuf, Self), RenderError> {
+        let text = match pattern {
+            SequencePattern::Printf(text) | SequencePattern::Glob(text) => text,
+        };
+        let path = Path::new(text);
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| RenderError::InvalidSource(format!("bad pattern {:?}", text)))?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let matcher = match pattern {
+            SequencePattern::Glob(_) => Self::Glob(name.to_string()),
+            SequencePattern::Printf(_) => {
+                let start = name.find('%').ok_or_else(|| {
+                    RenderError::InvalidSource(format!("{:?} has no %d field", text))
+                })?;
+                let spec = &name[start + 1..];
+                let digits = spec
+                    .find('d')
+                    .filter(|&end| spec[..end].chars().all(|c| c.is_ascii_digit()));
+                let Some(end) = digits else {
+                    return Err(RenderError::InvalidSource(format!(
+                        "{:?} has no %d field",
+                        text
+                    )));
+                };
+                Self::Printf {
+                    prefix: name[..start].to_string(),
+                    width: spec[..end].parse().unwrap_or(0),
+                    suffix: spec[end + 1..].to_string(),
+                }
+            }
+        };
+        Ok((dir, matcher))
+    }
+
+    /// Frame number of a matching file name.
+    fn number(&self, name: &str) -> Option<u32> {
+        match self {
+            Self::Printf {
+                prefix,
+                width,
+                suffix,
+            } => {
+                let digits = name
+                    .strip_prefix(prefix.as_str())?
+                    .strip_suffix(suffix.as_str())?;
+                if digits.len() < (*width).max(1) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                digits.parse().ok()
+            }
+            Self::Glob(pattern) => {
+                if !wildcard_match(pattern.as_bytes(), name.as_bytes()) {
+                    return None;
+                }
+                let end = name.rfind(|c: char| c.is_ascii_digit())? + 1;
+                let start = name[..end]
+                    .rfind(|c: char| !c.is_ascii_digit())
+                    .map_or(0, |i| i + 1);
+                name[start..end].parse().ok()
+            }
+        }
+    }
+}
+
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            wildcard_match(&pattern[1..], name)
+                || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Plays an `ImageSequence` into a `TextureManager` resource, decoding
+/// upcoming frames in the background through a `FramePrefetcher`.
+///
+/// Call `update` with the composition time before each render; layers
+/// reference the sequence through `LayerSource::Video { resource_id }`.
+pub struct ImageSequenceSource {
+    pub resource_id: Uuid,
+    // Resolves frame numbers; the prefetcher decodes from its own copy
+    sequence: ImageSequence,
+    prefetcher: FramePrefetcher,
+}
+
+impl ImageSequenceSource {
+    /// Reads up to 8 frames ahead.
+    pub fn new(resource_id: Uuid, sequence: ImageSequence) -> Result<Self, RenderError> {
+        Self::with_read_ahead(resource_id, sequence, 8)
+    }
+
+    pub fn with_read_ahead(
+        resource_id: Uuid,
+        sequence: ImageSequence,
+        frames: u64,
+    ) -> Result<Self, RenderError> {
+        let mut prefetcher = FramePrefetcher::with_read_ahead(frames);
+        prefetcher.register(resource_id, sequence.clone())?;
+        Ok(Self {
+            resource_id,
+            sequence,
+            prefetcher,
+        })
+    }
+
+    pub fn sequence(&self) -> &ImageSequence {
+        &self.sequence
+    }
+
+    /// Frames decoded and cached around the one shown.
+    pub fn ready_frames(&self) -> usize {
+        self.prefetcher.cached_frames(&self.resource_id)
+    }
+
+    /// Uploads the frame for `time` seconds, if it isn't on the texture
+    /// already, and moves the read-ahead window there. Blocks when the
+    /// frame hasn't been decoded yet.
+    ///
+    /// Returns the frame number shown, `None` for a black frame.
+    pub fn update(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        texture_manager: &mut TextureManager,
+        time: f64,
+    ) -> Result<Option<u32>, RenderError> {
+        let number = self.sequence.frame_at(time);
+        let resolved = self.sequence.resolve(number)?.map(|(n, _)| n);
+        self.prefetcher
+            .update(device, queue, texture_manager, self.resource_id, time)?;
+        Ok(resolved)
+    }
+}
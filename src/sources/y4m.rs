@@ -0,0 +1,307 @@
+use super::provider::{FrameData, FrameFormat, FrameProvider, PlaneData, VideoFrame};
+use crate::core::RenderError;
+use crate::resources::{ChromaSiting, YuvConversion, YuvFormat, YuvMatrix, YuvRange};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Longest header or frame line accepted; real ones are well under this
+const MAX_LINE: usize = 4096;
+
+/// Stream parameters from a YUV4MPEG2 header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Y4mHeader {
+    pub width: u32,
+    pub height: u32,
+    /// Frame rate as a fraction, e.g. (30000, 1001).
+    pub frame_rate: (u32, u32),
+    pub format: YuvFormat,
+    pub siting: ChromaSiting,
+    /// From ffmpeg's `XCOLORRANGE` extension, if present.
+    pub range: Option<YuvRange>,
+}
+
+impl Y4mHeader {
+    fn parse(line: &str) -> Result<Self, RenderError> {
+        let invalid = |what: String| RenderError::InvalidSource(format!("Y4M header: {}", what));
+        let mut params = line.split(' ');
+        if params.next() != Some("YUV4MPEG2") {
+            return Err(invalid("missing YUV4MPEG2 signature".into()));
+        }
+
+        let (mut width, mut height) = (None, None);
+        let mut frame_rate = None;
+        let mut colorspace = "420jpeg";
+        let mut range = None;
+        for param in params.filter(|p| !p.is_empty()) {
+            // Tags are one character, but the line may hold any UTF-8
+            let tag_len = param.chars().next().map_or(0, char::len_utf8);
+            let (tag, value) = param.split_at(tag_len);
+            let number = |v: &str| {
+                v.parse::<u32>()
+                    .map_err(|_| invalid(format!("bad {}", param)))
+            };
+            match tag {
+                "W" => width = Some(number(value)?),
+                "H" => height = Some(number(value)?),
+                "F" => {
+                    let (num, den) = value
+                        .split_once(':')
+                        .ok_or_else(|| invalid(format!("bad {}", param)))?;
+                    frame_rate = Some((number(num)?, number(den)?));
+                }
+                "C" => colorspace = value,
+                "X" => match value {
+                    "COLORRANGE=FULL" => range = Some(YuvRange::Full),
+                    "COLORRANGE=LIMITED" => range = Some(YuvRange::Limited),
+                    _ => {}
+                },
+                // Interlacing and pixel aspect don't affect decoding
+                _ => {}
+            }
+        }
+
+        let (format, siting) = match colorspace {
+            "420jpeg" | "420" => (YuvFormat::I420, ChromaSiting::Center),
+            "420mpeg2" => (YuvFormat::I420, ChromaSiting::Left),
+            "420paldv" => (YuvFormat::I420, ChromaSiting::TopLeft),
+            "422" => (YuvFormat::I422, ChromaSiting::Left),
+            "444" => (YuvFormat::I444, ChromaSiting::Left),
+            "420p10" => (YuvFormat::I420P10, ChromaSiting::Left),
+            "422p10" => (YuvFormat::I422P10, ChromaSiting::Left),
+            "444p10" => (YuvFormat::I444P10, ChromaSiting::Left),
+            other => return Err(invalid(format!("unsupported colorspace C{}", other))),
+        };
+        let (Some(width), Some(height)) = (width, height) else {
+            return Err(invalid("missing W or H".into()));
+        };
+        let frame_rate = frame_rate.ok_or_else(|| invalid("missing F".into()))?;
+        if width == 0 || height == 0 || frame_rate.0 == 0 || frame_rate.1 == 0 {
+            return Err(invalid(format!(
+                "bad size {}x{} or rate {}:{}",
+                width, height, frame_rate.0, frame_rate.1
+            )));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            frame_rate,
+            format,
+            siting,
+            range,
+        })
+    }
+
+    /// The header line, without the trailing newline. Fails for formats
+    /// Y4M can't carry, like NV12.
+    pub fn to_line(&self) -> Result<String, RenderError> {
+        let colorspace = match (self.format, self.siting) {
+            (YuvFormat::I420, ChromaSiting::Center) => "420jpeg",
+            (YuvFormat::I420, ChromaSiting::TopLeft) => "420paldv",
+            (YuvFormat::I420, _) => "420mpeg2",
+            (YuvFormat::I422, _) => "422",
+            (YuvFormat::I444, _) => "444",
+            (YuvFormat::I420P10, _) => "420p10",
+            (YuvFormat::I422P10, _) => "422p10",
+            (YuvFormat::I444P10, _) => "444p10",
+            (format, _) => {
+                return Err(RenderError::InvalidOutput(format!(
+                    "Y4M can't carry {:?}",
+                    format
+                )));
+            }
+        };
+        let mut line = format!(
+            "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C{}",
+            self.width, self.height, self.frame_rate.0, self.frame_rate.1, colorspace
+        );
+        match self.range {
+            Some(YuvRange::Full) => line.push_str(" XCOLORRANGE=FULL"),
+            Some(YuvRange::Limited) => line.push_str(" XCOLORRANGE=LIMITED"),
+            None => {}
+        }
+        Ok(line)
+    }
+
+    /// Bytes of plane data per frame.
+    pub fn frame_bytes(&self) -> u64 {
+        (0..self.format.plane_count())
+            .map(|plane| {
+                let (_, rows) = self.format.plane_size(plane, self.width, self.height);
+                self.format.plane_row_bytes(plane, self.width) as u64 * rows as u64
+            })
+            .sum()
+    }
+}
+
+/// Dependency-free YUV4MPEG2 reader, usable as a `FrameProvider`.
+///
+/// Supports 4:2:0, 4:2:2 and 4:4:4 at 8 and 10 bit. Frames are located
+/// when the reader is opened, so any frame can be read directly. Planes are
+/// handed over as-is and converted to RGB on the GPU.
+pub struct Y4mReader<R> {
+    reader: R,
+    pub header: Y4mHeader,
+    /// Conversion applied to every frame. Y4M carries no matrix, so it
+    /// defaults to BT.601 up to 576 lines and BT.709 above, with the range
+    /// from the header (limited if absent).
+    pub conversion: YuvConversion,
+    // Start of each frame's plane data
+    frame_offsets: Vec<u64>,
+}
+
+impl Y4mReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RenderError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> Y4mReader<R> {
+    /// Parses the header and indexes the frames. A truncated final frame is
+    /// ignored.
+    pub fn new(mut reader: R) -> Result<Self, RenderError> {
+        reader.seek(SeekFrom::Start(0))?;
+        let line = read_line(&mut reader)?
+            .ok_or_else(|| RenderError::InvalidSource("empty Y4M stream".into()))?;
+        let header = Y4mHeader::parse(&line)?;
+
+        let end = reader.seek(SeekFrom::End(0))?;
+        let frame_bytes = header.frame_bytes();
+        let mut offset = line.len() as u64 + 1;
+        let mut frame_offsets = vec![];
+        loop {
+            reader.seek(SeekFrom::Start(offset))?;
+            let Some(line) = read_line(&mut reader)? else {
+                break;
+            };
+            if !line.starts_with("FRAME") {
+                return Err(RenderError::InvalidSource(format!(
+                    "Y4M frame {} doesn't start with FRAME",
+                    frame_offsets.len()
+                )));
+            }
+            let data = offset + line.len() as u64 + 1;
+            if data + frame_bytes > end {
+                break;
+            }
+            frame_offsets.push(data);
+            offset = data + frame_bytes;
+        }
+
+        let matrix = match header.height {
+            0..=576 => YuvMatrix::Bt601,
+            _ => YuvMatrix::Bt709,
+        };
+        let conversion = YuvConversion {
+            siting: header.siting,
+            ..YuvConversion::new(matrix, header.range.unwrap_or_default())
+        };
+
+        Ok(Self {
+            reader,
+            header,
+            conversion,
+            frame_offsets,
+        })
+    }
+
+    pub fn with_conversion(mut self, conversion: YuvConversion) -> Self {
+        self.conversion = conversion;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.frame_offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frame_offsets.is_empty()
+    }
+
+    /// Reads the planes of frame `index`, tightly packed.
+    pub fn read_planes(&mut self, index: usize) -> Result<Vec<PlaneData>, RenderError> {
+        let offset = *self.frame_offsets.get(index).ok_or_else(|| {
+            RenderError::InvalidSource(format!(
+                "Y4M frame {} is past the last of {}",
+                index,
+                self.len()
+            ))
+        })?;
+        self.reader.seek(SeekFrom::Start(offset))?;
+
+        let Y4mHeader {
+            width,
+            height,
+            format,
+            ..
+        } = self.header;
+        (0..format.plane_count())
+            .map(|plane| {
+                let (_, rows) = format.plane_size(plane, width, height);
+                let stride = format.plane_row_bytes(plane, width);
+                let mut data = vec![0; stride as usize * rows as usize];
+                self.reader.read_exact(&mut data)?;
+                Ok(PlaneData { data, stride })
+            })
+            .collect()
+    }
+}
+
+impl<R: Read + Seek + Send> FrameProvider for Y4mReader<R> {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.header.width, self.header.height)
+    }
+
+    fn frame_rate(&self) -> f64 {
+        let (num, den) = self.header.frame_rate;
+        num as f64 / den as f64
+    }
+
+    fn pixel_format(&self) -> FrameFormat {
+        FrameFormat::Yuv(self.header.format)
+    }
+
+    fn frame_count(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+
+    fn decode_frame(&mut self, index: u64) -> Result<VideoFrame, RenderError> {
+        let planes = self.read_planes(index as usize)?;
+        Ok(VideoFrame {
+            index,
+            timestamp: index as f64 / self.frame_rate(),
+            data: FrameData::Yuv {
+                format: self.header.format,
+                width: self.header.width,
+                height: self.header.height,
+                planes,
+                conversion: self.conversion,
+            },
+        })
+    }
+}
+
+/// Reads up to the next `\n`, or `None` at the end of the stream.
+fn read_line(reader: &mut impl Read) -> Result<Option<String>, RenderError> {
+    let mut line = vec![];
+    let mut byte = [0u8];
+    loop {
+        if reader.read(&mut byte)? == 0 {
+            if line.is_empty() {
+                return Ok(None);
+            }
+            break;
+        }
+        if byte[0] == b'\n' {
+            break;
+        }
+        line.push(byte[0]);
+        if line.len() > MAX_LINE {
+            return Err(RenderError::InvalidSource("Y4M line too long".into()));
+        }
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| RenderError::InvalidSource("Y4M line isn't ASCII".into()))
+}
@@ -0,0 +1,114 @@
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, ImageFormat, Rgba, RgbaImage};
+use std::io::Cursor;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::LayerSource;
+use videomti_render::resources::TextureManager;
+use videomti_render::sources::{AnimatedImage, AnimatedImageSource, LoopCount};
+
+mod common;
+use common::render_center;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+/// Red for 100ms then blue for 200ms, played twice.
+fn red_blue_gif() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = GifEncoder::new(&mut bytes);
+    encoder.set_repeat(Repeat::Finite(1)).unwrap();
+    let frames = [(RED, 100), (BLUE, 200)].map(|(color, ms)| {
+        Frame::from_parts(
+            RgbaImage::from_pixel(4, 4, Rgba(color)),
+            0,
+            0,
+            Delay::from_numer_denom_ms(ms, 1),
+        )
+    });
+    encoder.encode_frames(frames).unwrap();
+    drop(encoder);
+    bytes
+}
+
+#[test]
+fn test_gif_timing() {
+    let animation = AnimatedImage::from_bytes(&red_blue_gif()).expect("Decode failed");
+    assert_eq!((animation.width, animation.height), (4, 4));
+    assert_eq!(animation.frame_count(), 2);
+    // One repeat after the first play
+    assert_eq!(animation.loop_count, LoopCount::Finite(2));
+    assert!((animation.loop_duration() - 0.3).abs() < 1e-9);
+
+    assert_eq!(animation.frame_at(0.0), 0);
+    assert_eq!(animation.frame_at(0.1), 1);
+    assert_eq!(animation.frame_at(0.35), 0, "second play");
+    assert_eq!(animation.frame_at(0.45), 1);
+    assert_eq!(animation.frame_at(5.0), 1, "holds the last frame");
+}
+
+#[test]
+fn test_apng_blending() {
+    // Frame 2 blends a transparent | blue strip over red | red
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_animated(2, 0).unwrap();
+    encoder.set_frame_delay(1, 10).unwrap();
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&[RED, RED].concat()).unwrap();
+    writer.set_blend_op(png::BlendOp::Over).unwrap();
+    writer
+        .write_image_data(&[[0, 0, 0, 0], BLUE].concat())
+        .unwrap();
+    writer.finish().unwrap();
+
+    let animation = AnimatedImage::from_bytes(&bytes).expect("Decode failed");
+    assert_eq!(animation.loop_count, LoopCount::Infinite);
+    assert_eq!(animation.frame(1).data, [RED, BLUE].concat());
+    assert_eq!(animation.frame_at(0.25), 0, "loops forever");
+
+    // Still images and non-animatable formats
+    let still = DynamicImage::ImageRgba8(RgbaImage::from_pixel(3, 3, Rgba(RED)));
+    let mut webp = Cursor::new(Vec::new());
+    still.write_to(&mut webp, ImageFormat::WebP).unwrap();
+    let animation = AnimatedImage::from_bytes(webp.get_ref()).expect("Decode failed");
+    assert_eq!(animation.frame_count(), 1);
+
+    let mut jpeg = Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(still.to_rgb8())
+        .write_to(&mut jpeg, ImageFormat::Jpeg)
+        .unwrap();
+    assert!(matches!(
+        AnimatedImage::from_bytes(jpeg.get_ref()),
+        Err(RenderError::InvalidSource(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_animated_source() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let animation = AnimatedImage::from_bytes(&red_blue_gif()).unwrap();
+    let mut source = AnimatedImageSource::new(Uuid::new_v4(), animation);
+    let id = source.resource_id;
+
+    for (time, index, color) in [(0.05, 0, RED), (0.2, 1, BLUE), (0.32, 0, RED)] {
+        let shown = source
+            .update(&ctx.device, &ctx.queue, &mut texture_manager, time)
+            .expect("Update failed");
+        assert_eq!(shown, index);
+        assert_eq!(
+            render_center(
+                &ctx,
+                &texture_manager,
+                LayerSource::Image { resource_id: id },
+                [0.0, 0.0, 0.0, 1.0],
+            )
+            .await,
+            color
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use glam::Vec2;
+use std::io::{Cursor, Write};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{FrameDescription, Layer, LayerSource, LayerTransform};
+use videomti_render::outputs::{AnimatedImageSink, AnimationFormat};
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::TextureManager;
+use videomti_render::sources::{AnimatedImage, LoopCount};
+
+const SIZE: u32 = 16;
+/// Horizontal centre of the square per frame; frames 2 and 3 repeat.
+const POSITIONS: [f32; 6] = [4.0, 6.0, 8.0, 8.0, 10.0, 12.0];
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Renders a white 4x4 square sliding right over `background` at 10 fps.
+async fn export(
+    ctx: &RenderContext,
+    format: AnimationFormat,
+    background: [f32; 4],
+    loop_count: LoopCount,
+) -> Result<(usize, Vec<u8>), RenderError> {
+    let mut texture_manager = TextureManager::new();
+    let id = Uuid::new_v4();
+    texture_manager.update_texture(&ctx.device, &ctx.queue, id, 4, 4, &[255; 64])?;
+
+    let output = SharedBuffer::default();
+    let mut sink = AnimatedImageSink::new(ctx, SIZE, SIZE, format, (10, 1), output.clone())?
+        .with_loop_count(loop_count);
+    let mut renderer = Renderer::new(ctx);
+    for x in POSITIONS {
+        let mut frame = FrameDescription::new(SIZE, SIZE, background);
+        frame.layers.push(Layer {
+            source: LayerSource::Image { resource_id: id },
+            transform: LayerTransform {
+                position: Vec2::new(x, 8.0),
+                scale: Vec2::splat(4.0),
+                ..Default::default()
+            },
+            ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+        });
+        renderer.render(ctx, &texture_manager, &frame, &mut [&mut sink])?;
+        sink.write_frame(ctx).await?;
+    }
+    assert_eq!(sink.frame_count(), POSITIONS.len());
+    let stored = sink.finish()?;
+    let bytes = output.0.lock().unwrap().clone();
+    Ok((stored, bytes))
+}
+
+fn pixel(animation: &AnimatedImage, frame: usize, x: u32, y: u32) -> [u8; 4] {
+    let offset = ((y * SIZE + x) * 4) as usize;
+    animation.frame(frame).data[offset..offset + 4]
+        .try_into()
+        .unwrap()
+}
+
+/// Checks the decoded animation against the rendered frames, merged repeats
+/// included.
+fn check_animation(animation: &AnimatedImage, background: [u8; 4], tolerance: u8) {
+    let close = |a: [u8; 4], b: [u8; 4]| a.iter().zip(b).all(|(&a, b)| a.abs_diff(b) <= tolerance);
+    assert_eq!(animation.frame_count(), 5);
+    let durations: Vec<f64> = (0..5).map(|i| animation.frame(i).duration).collect();
+    assert_eq!(durations, [0.1, 0.1, 0.2, 0.1, 0.1]);
+
+    let shown = [4, 6, 8, 10, 12];
+    for (frame, centre) in shown.into_iter().enumerate() {
+        for x in 0..SIZE {
+            let expected = match (centre - 2..centre + 2).contains(&x) {
+                true => [255; 4],
+                false => background,
+            };
+            let actual = pixel(animation, frame, x, 8);
+            assert!(
+                close(actual, expected),
+                "frame {} x {}: {:?} != {:?}",
+                frame,
+                x,
+                actual,
+                expected
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_export_apng_and_webp() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    for format in [AnimationFormat::Apng, AnimationFormat::WebP] {
+        let (stored, bytes) = export(&ctx, format, [0.0, 0.0, 0.0, 1.0], LoopCount::Finite(2))
+            .await
+            .expect("Export failed");
+        assert_eq!(stored, 5);
+        let animation = AnimatedImage::from_bytes(&bytes).expect("Decode failed");
+        assert_eq!(animation.loop_count, LoopCount::Finite(2));
+        check_animation(&animation, [0, 0, 0, 255], 0);
+    }
+}
+
+#[tokio::test]
+async fn test_export_apng_crops_changes() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let (_, bytes) = export(
+        &ctx,
+        AnimationFormat::Apng,
+        [0.0, 0.0, 0.0, 1.0],
+        LoopCount::Infinite,
+    )
+    .await
+    .expect("Export failed");
+
+    let mut reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size().unwrap()];
+    let mut sizes = vec![];
+    while reader.next_frame(&mut buf).is_ok() {
+        let control = reader.info().frame_control().unwrap();
+        sizes.push((control.x_offset, control.width, control.height));
+    }
+    // The square moves 2px a frame, touching a 6x4 region
+    assert_eq!(sizes[0], (0, SIZE, SIZE));
+    assert_eq!(sizes[1], (2, 6, 4));
+}
+
+#[tokio::test]
+async fn test_export_gif() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+
+    let (stored, bytes) = export(
+        &ctx,
+        AnimationFormat::Gif,
+        [0.0, 0.0, 0.0, 1.0],
+        LoopCount::Finite(3),
+    )
+    .await
+    .expect("Export failed");
+    assert_eq!(stored, 5);
+    let animation = AnimatedImage::from_bytes(&bytes).expect("Decode failed");
+    assert_eq!(animation.loop_count, LoopCount::Finite(3));
+    check_animation(&animation, [0, 0, 0, 255], 8);
+
+    // Transparency forces whole-canvas frames that clear what came before
+    let (_, bytes) = export(&ctx, AnimationFormat::Gif, [0.0; 4], LoopCount::Infinite)
+        .await
+        .expect("Export failed");
+    let animation = AnimatedImage::from_bytes(&bytes).expect("Decode failed");
+    assert_eq!(animation.loop_count, LoopCount::Infinite);
+    check_animation(&animation, [0; 4], 8);
+}
+
+#[tokio::test]
+async fn test_export_validation() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    assert!(matches!(
+        AnimatedImageSink::new(&ctx, 20000, 16, AnimationFormat::WebP, (10, 1), Vec::new()),
+        Err(RenderError::InvalidOutput(_))
+    ));
+    let sink = AnimatedImageSink::new(&ctx, 16, 16, AnimationFormat::Gif, (10, 1), Vec::new())
+        .expect("Sink creation failed");
+    assert!(matches!(sink.finish(), Err(RenderError::InvalidOutput(_))));
+}
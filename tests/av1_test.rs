@@ -0,0 +1,211 @@
+use glam::Vec2;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{FrameDescription, Layer, LayerSource, LayerTransform};
+use videomti_render::outputs::{Av1Settings, Av1Sink, VideoContainer};
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::TextureManager;
+
+const SIZE: u32 = 64;
+
+/// A `Write` the test can inspect after the encoder thread is done.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Fast settings with a keyframe every 4 frames.
+fn settings(ten_bit: bool) -> Av1Settings {
+    Av1Settings {
+        speed: 10,
+        keyframe_interval: 4,
+        ten_bit,
+        threads: 1,
+        ..Default::default()
+    }
+}
+
+/// Encodes `frames` frames of a texture sliding across the canvas.
+async fn encode(
+    ctx: &RenderContext,
+    container: VideoContainer,
+    settings: Av1Settings,
+    frames: u32,
+) -> Result<Vec<u8>, RenderError> {
+    let mut texture_manager = TextureManager::new();
+    let id = Uuid::new_v4();
+    let pixels: Vec<u8> = (0..16 * 16)
+        .flat_map(|i| [(i * 7) as u8, (i * 3) as u8, 200, 255])
+        .collect();
+    texture_manager.update_texture(&ctx.device, &ctx.queue, id, 16, 16, &pixels)?;
+
+    let output = SharedBuffer::default();
+    let mut sink = Av1Sink::new(
+        ctx,
+        SIZE,
+        SIZE,
+        (30, 1),
+        container,
+        settings,
+        output.clone(),
+    )?;
+    let mut renderer = Renderer::new(ctx);
+    for index in 0..frames {
+        let mut frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+        frame.layers.push(Layer {
+            source: LayerSource::Image { resource_id: id },
+            transform: LayerTransform {
+                position: Vec2::new(16.0 + index as f32 * 3.0, 32.0),
+                scale: Vec2::splat(32.0),
+                ..Default::default()
+            },
+            ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+        });
+        renderer.render(ctx, &texture_manager, &frame, &mut [&mut sink])?;
+        sink.write_frame(ctx).await?;
+    }
+    assert_eq!(sink.finish()?, frames as u64);
+    let bytes = output.0.lock().unwrap().clone();
+    Ok(bytes)
+}
+
+/// Top-level or nested boxes as `(type, body)`.
+fn boxes(mut data: &[u8]) -> Vec<(String, &[u8])> {
+    let mut out = vec![];
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+        let kind = String::from_utf8_lossy(&data[4..8]).into_owned();
+        out.push((kind, &data[8..size]));
+        data = &data[size..];
+    }
+    out
+}
+
+fn child<'a>(parent: &'a [u8], kind: &str) -> &'a [u8] {
+    boxes(parent)
+        .into_iter()
+        .find(|(k, _)| k == kind)
+        .unwrap_or_else(|| panic!("no {} box", kind))
+        .1
+}
+
+#[tokio::test]
+async fn test_av1_ivf() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let bytes = encode(&ctx, VideoContainer::Ivf, settings(false), 6)
+        .await
+        .expect("Encoding failed");
+
+    assert_eq!(&bytes[..4], b"DKIF");
+    assert_eq!(&bytes[8..12], b"AV01");
+    assert_eq!(u16::from_le_bytes([bytes[12], bytes[13]]), SIZE as u16);
+
+    // Frame headers: size, then a pts counting frames
+    let mut pos = 32;
+    let mut pts = vec![];
+    while pos < bytes.len() {
+        let size = u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        pts.push(u64::from_le_bytes(
+            bytes[pos + 4..pos + 12].try_into().unwrap(),
+        ));
+        // Each temporal unit opens with a temporal delimiter OBU
+        assert_eq!(bytes[pos + 12] >> 3 & 0xf, 2);
+        pos += 12 + size;
+    }
+    assert_eq!(pos, bytes.len());
+    assert_eq!(pts, (0..6).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_av1_fragmented_mp4() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let bytes = encode(&ctx, VideoContainer::Mp4, settings(true), 10)
+        .await
+        .expect("Encoding failed");
+
+    let top = boxes(&bytes);
+    let kinds: Vec<&str> = top.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(&kinds[..2], ["ftyp", "moov"]);
+    assert!(kinds[2..].chunks(2).all(|pair| pair == ["moof", "mdat"]));
+
+    // av1C: 10-bit 4:2:0, followed by the sequence header OBU
+    let stbl = child(
+        child(child(child(top[1].1, "trak"), "mdia"), "minf"),
+        "stbl",
+    );
+    let av01 = &child(stbl, "stsd")[8..];
+    let av1c = child(&av01[8 + 78..], "av1C");
+    assert_eq!(av1c[0], 0x81);
+    assert_eq!(av1c[2] & 0x40, 0x40, "high bit depth");
+    assert_eq!(av1c[4] >> 3 & 0xf, 1, "sequence header OBU");
+
+    // A fragment per keyframe interval; each opens with a sync sample
+    let mut samples = 0;
+    let mut decode_times = vec![];
+    for pair in top[2..].chunks(2) {
+        let traf = child(pair[0].1, "traf");
+        let tfdt = child(traf, "tfdt");
+        decode_times.push(u64::from_be_bytes(tfdt[4..12].try_into().unwrap()));
+        let trun = child(traf, "trun");
+        let count = u32::from_be_bytes(trun[4..8].try_into().unwrap());
+        let first_flags = u32::from_be_bytes(trun[16..20].try_into().unwrap());
+        assert_eq!(first_flags, 0x0200_0000);
+        let sizes: usize = (0..count as usize)
+            .map(|i| u32::from_be_bytes(trun[12 + i * 8..16 + i * 8].try_into().unwrap()) as usize)
+            .sum();
+        assert_eq!(sizes, pair[1].1.len());
+        samples += count;
+    }
+    assert_eq!(samples, 10);
+    assert_eq!(decode_times, [0, 4, 8]);
+}
+
+#[tokio::test]
+async fn test_av1_invalid_settings() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let bad = Av1Settings {
+        keyframe_interval: 0,
+        ..settings(false)
+    };
+    assert!(matches!(
+        Av1Sink::new(
+            &ctx,
+            SIZE,
+            SIZE,
+            (30, 1),
+            VideoContainer::Ivf,
+            bad,
+            Vec::new()
+        ),
+        Err(RenderError::InvalidOutput(_))
+    ));
+    assert!(matches!(
+        Av1Sink::new(
+            &ctx,
+            SIZE,
+            SIZE,
+            (0, 1),
+            VideoContainer::Ivf,
+            settings(false),
+            Vec::new()
+        ),
+        Err(RenderError::InvalidOutput(_))
+    ));
+}
@@ -0,0 +1,251 @@
+use glam::{Vec2, Vec3};
+use uuid::Uuid;
+use videomti_render::core::RenderContext;
+use videomti_render::model::{
+    ColorPrimaries, ColorSpace, FrameDescription, InverseToneMapping, Layer, LayerSource,
+    LayerTransform, ToneMapOperator, ToneMapping, TransferFunction,
+};
+use videomti_render::outputs::BufferSink;
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::TextureManager;
+
+#[test]
+fn test_transfer_round_trip() {
+    let transfers = [
+        TransferFunction::Linear,
+        TransferFunction::Srgb,
+        TransferFunction::Rec709,
+        TransferFunction::SLog3,
+        TransferFunction::LogC3,
+        TransferFunction::Pq,
+        TransferFunction::Hlg,
+    ];
+
+    for tf in transfers {
+        for i in 0..=20 {
+            let linear = i as f32 / 10.0; // 0.0 ..= 2.0
+            let back = tf.to_linear(tf.from_linear(linear));
+            assert!(
+                (back - linear).abs() < 1e-3,
+                "{:?}: {} -> {}",
+                tf,
+                linear,
+                back
+            );
+        }
+    }
+
+    // 18% grey lands on the documented code values
+    assert!((TransferFunction::SLog3.from_linear(0.18) * 1023.0 - 420.0).abs() < 0.5);
+    assert!((TransferFunction::LogC3.from_linear(0.18) - 0.391).abs() < 1e-3);
+
+    // BT.2408: 203 nit reference white is PQ 58%, HLG 1.0 is full signal
+    assert!((TransferFunction::Pq.from_linear(0.0203) - 0.58).abs() < 2e-3);
+    assert!((TransferFunction::Hlg.from_linear(1.0) - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_gamut_matrices() {
+    // Known BT.709 -> BT.2020 matrix (ITU-R BT.2087)
+    let m = ColorPrimaries::Rec709.conversion_to(ColorPrimaries::Rec2020);
+    let red = m * Vec3::X;
+    assert!(
+        (red - Vec3::new(0.6274, 0.0691, 0.0164))
+            .abs()
+            .max_element()
+            < 1e-3
+    );
+
+    // White is preserved by every conversion
+    let gamuts = [
+        ColorPrimaries::Rec709,
+        ColorPrimaries::Rec2020,
+        ColorPrimaries::DisplayP3,
+        ColorPrimaries::SGamut3Cine,
+        ColorPrimaries::ArriWideGamut3,
+    ];
+    for from in gamuts {
+        for to in gamuts {
+            let white = from.conversion_to(to) * Vec3::ONE;
+            assert!((white - Vec3::ONE).abs().max_element() < 1e-4);
+        }
+    }
+}
+
+fn fullscreen_layer(id: Uuid, width: u32, height: u32) -> Layer {
+    Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::new(width as f32 / 2.0, height as f32 / 2.0),
+            scale: Vec2::new(width as f32, height as f32),
+            ..Default::default()
+        },
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    }
+}
+
+#[tokio::test]
+async fn test_input_color_space_conversion() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+
+    let (width, height) = (16, 16);
+    let mut sink = BufferSink::new(&ctx, width, height);
+
+    // Solid P3 color
+    let id = Uuid::new_v4();
+    let texel = [200u8, 120, 40, 255];
+    let data: Vec<u8> = texel.iter().copied().cycle().take(4 * 4 * 4).collect();
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, 4, 4, &data)
+        .expect("Upload failed");
+    assert!(texture_manager.set_color_space(id, ColorSpace::DISPLAY_P3));
+
+    let mut frame = FrameDescription::new(width, height, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(fullscreen_layer(id, width, height));
+
+    renderer
+        .render(&ctx, &texture_manager, &frame, &mut [&mut sink])
+        .expect("Render failed");
+    let pixels = sink.read_pixels(&ctx).await.expect("Failed to read pixels");
+
+    let expected = ColorSpace::DISPLAY_P3.convert_rgb(
+        ColorSpace::SRGB,
+        [texel[0], texel[1], texel[2]].map(|c| c as f32 / 255.0),
+    );
+    let offset = (8 * width + 8) as usize * 4;
+    for c in 0..3 {
+        let got = pixels[offset + c] as f32 / 255.0;
+        assert!(
+            (got - expected[c].clamp(0.0, 1.0)).abs() < 2.5 / 255.0,
+            "channel {}: expected {}, got {}",
+            c,
+            expected[c],
+            got
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_hdr_pq_output() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+
+    let (width, height) = (16, 16);
+    let mut sink = BufferSink::with_format(
+        &ctx,
+        width,
+        height,
+        wgpu::TextureFormat::Rgb10a2Unorm,
+        ColorSpace::REC2100_PQ,
+    );
+
+    // SDR white graphic
+    let id = Uuid::new_v4();
+    let data = vec![255u8; 4 * 4 * 4];
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, 4, 4, &data)
+        .expect("Upload failed");
+
+    let mut frame = FrameDescription::new(width, height, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(fullscreen_layer(id, width, height));
+
+    let read_center = |pixels: Vec<u8>| {
+        let offset = (8 * width + 8) as usize * 4;
+        let texel = u32::from_le_bytes(pixels[offset..offset + 4].try_into().unwrap());
+        [texel & 0x3ff, (texel >> 10) & 0x3ff, (texel >> 20) & 0x3ff]
+    };
+
+    // SDR white sits at reference white
+    renderer
+        .render(&ctx, &texture_manager, &frame, &mut [&mut sink])
+        .expect("Render failed");
+    let rgb = read_center(sink.read_pixels(&ctx).await.unwrap());
+    let expected = TransferFunction::Pq.from_linear(203.0 / 10000.0) * 1023.0;
+    for c in rgb {
+        assert!((c as f32 - expected).abs() <= 2.0, "{} vs {}", c, expected);
+    }
+
+    // Inverse tone mapping pushes SDR white up to the requested peak
+    frame.inverse_tone_mapping = Some(InverseToneMapping {
+        peak_nits: 1000.0,
+        knee: 0.6,
+    });
+    renderer
+        .render(&ctx, &texture_manager, &frame, &mut [&mut sink])
+        .expect("Render failed");
+    let rgb = read_center(sink.read_pixels(&ctx).await.unwrap());
+    let expected = TransferFunction::Pq.from_linear(1000.0 / 10000.0) * 1023.0;
+    for c in rgb {
+        assert!((c as f32 - expected).abs() <= 3.0, "{} vs {}", c, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_tone_mapping_operators() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+
+    let (width, height) = (16, 16);
+    let mut sink = BufferSink::new(&ctx, width, height);
+
+    // ~400 nit PQ grey
+    let id = Uuid::new_v4();
+    let code = 166u8;
+    let data: Vec<u8> = [code, code, code, 255].repeat(16);
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, 4, 4, &data)
+        .expect("Upload failed");
+    texture_manager.set_color_space(id, ColorSpace::REC2100_PQ);
+
+    let mut frame = FrameDescription::new(width, height, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(fullscreen_layer(id, width, height));
+
+    let offset = (8 * width + 8) as usize * 4;
+    let mut render_grey = async |tone_mapping: Option<ToneMapping>| {
+        sink.tone_mapping = tone_mapping;
+        renderer
+            .render(&ctx, &texture_manager, &frame, &mut [&mut sink])
+            .expect("Render failed");
+        sink.read_pixels(&ctx).await.unwrap()[offset]
+    };
+
+    // Without a curve, and with a hard clip, highlights saturate
+    assert_eq!(render_grey(None).await, 255);
+    let clip = ToneMapping::new(ToneMapOperator::Clip, 1000.0);
+    assert_eq!(render_grey(Some(clip)).await, 255);
+
+    // Reinhard keeps the highlight below white
+    let nits = TransferFunction::Pq.to_linear(code as f32 / 255.0) * 10000.0;
+    let x = nits / 203.0;
+    let peak = 1000.0f32 / 203.0;
+    let expected = TransferFunction::Srgb.from_linear(x * (1.0 + x / (peak * peak)) / (1.0 + x));
+    let reinhard = ToneMapping::new(ToneMapOperator::Reinhard, 1000.0);
+    let got = render_grey(Some(reinhard)).await;
+    assert!(
+        (got as f32 - expected * 255.0).abs() <= 2.0,
+        "expected {}, got {}",
+        expected * 255.0,
+        got
+    );
+
+    for operator in [
+        ToneMapOperator::Hable,
+        ToneMapOperator::Aces,
+        ToneMapOperator::Bt2390,
+    ] {
+        let mut tm = ToneMapping::new(operator, 1000.0);
+        tm.gamut_compression = true;
+        let got = render_grey(Some(tm)).await;
+        assert!(got > 128 && got < 255, "{:?} produced {}", operator, got);
+    }
+}
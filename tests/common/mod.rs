@@ -0,0 +1,84 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use glam::{Vec2, Vec3};
+use uuid::Uuid;
+use videomti_render::core::RenderContext;
+use videomti_render::model::{FrameDescription, Layer, LayerSource, LayerTransform};
+use videomti_render::outputs::BufferSink;
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::{TextureManager, YuvConversion};
+
+pub const SIZE: u32 = 16;
+
+/// A `SIZE` square frame cleared to `clear`, with `source` covering it.
+pub fn full_frame(source: LayerSource, clear: [f32; 4]) -> FrameDescription {
+    let mut frame = FrameDescription::new(SIZE, SIZE, clear);
+    frame.layers.push(Layer {
+        source,
+        transform: LayerTransform {
+            position: Vec2::splat(SIZE as f32 / 2.0),
+            scale: Vec2::splat(SIZE as f32),
+            ..Default::default()
+        },
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+    frame
+}
+
+/// A black frame at `time` seconds showing video resource `id`.
+pub fn video_frame(id: Uuid, time: f64) -> FrameDescription {
+    let mut frame = full_frame(LayerSource::Video { resource_id: id }, [0.0, 0.0, 0.0, 1.0]);
+    frame.time = time;
+    frame
+}
+
+/// Renders `frame` and returns the first `N` channels of its center pixel.
+pub async fn render_frame_center<const N: usize>(
+    ctx: &RenderContext,
+    texture_manager: &TextureManager,
+    frame: &FrameDescription,
+) -> [u8; N] {
+    let mut renderer = Renderer::new(ctx);
+    let (width, height) = frame.dimensions;
+    let mut sink = BufferSink::new(ctx, width, height);
+    renderer
+        .render(ctx, texture_manager, frame, &mut [&mut sink])
+        .expect("Render failed");
+    let image = sink.read_image(ctx).await.unwrap();
+    let pixel = image.get_pixel(width / 2, height / 2).0;
+    std::array::from_fn(|c| pixel[c])
+}
+
+/// Renders `source` over `clear` and returns the first `N` channels of the
+/// center pixel.
+pub async fn render_center<const N: usize>(
+    ctx: &RenderContext,
+    texture_manager: &TextureManager,
+    source: LayerSource,
+    clear: [f32; 4],
+) -> [u8; N] {
+    render_frame_center(ctx, texture_manager, &full_frame(source, clear)).await
+}
+
+/// Quantizes an R'G'B' color to Y'CbCr codes.
+pub fn encode_yuv(rgb: [f32; 3], conversion: YuvConversion, bits: u32) -> [u32; 3] {
+    let ycbcr = conversion.matrix.from_rgb() * Vec3::from_array(rgb);
+    let (scale, offset) = conversion.range.code_scale_offset(bits);
+    let codes = (ycbcr - offset) / scale;
+    codes.to_array().map(|c| c.round() as u32)
+}
+
+/// Checks an 8-bit color against `rgb` in 0..1, within 3 codes per channel.
+pub fn assert_close(got: [u8; 3], rgb: [f32; 3]) {
+    for c in 0..3 {
+        let expected = rgb[c] * 255.0;
+        assert!(
+            (got[c] as f32 - expected).abs() <= 3.0,
+            "channel {}: expected {}, got {}",
+            c,
+            expected,
+            got[c]
+        );
+    }
+}
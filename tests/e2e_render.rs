@@ -1,11 +1,12 @@
 use glam::vec2;
 use std::path::Path;
 use uuid::Uuid;
-use videomti_render::core::{RenderContext, RenderError}; // Import RenderError
+use videomti_render::core::RenderContext;
 use videomti_render::model::{FrameDescription as Composition, Layer, LayerSource, LayerTransform};
-use videomti_render::outputs::BufferSink;
+use videomti_render::outputs::{ImageFileFormat, ImageSequenceSink, OverwritePolicy};
 use videomti_render::renderer::Renderer;
 use videomti_render::resources::TextureManager;
+use videomti_render::sources::SequencePattern;
 
 fn create_test_rgba_pattern(width: u32, height: u32) -> Vec<u8> {
     let mut buffer = Vec::with_capacity((width * height * 4) as usize);
@@ -34,19 +35,33 @@ async fn test_full_composition_render() {
 
     let width = 1280;
     let height = 720;
-    // Explict type for sink to help compiler?
-    let mut sink = BufferSink::new(&context, width, height);
+    let mut sink = ImageSequenceSink::new(
+        &context,
+        width,
+        height,
+        SequencePattern::Printf(
+            Path::new(env!("CARGO_TARGET_TMPDIR"))
+                .join("output_audit_%04d.png")
+                .to_string_lossy()
+                .into(),
+        ),
+        ImageFileFormat::Png8,
+    )
+    .expect("Failed to create sink")
+    .with_overwrite(OverwritePolicy::Overwrite);
 
     let video_uuid = Uuid::new_v4();
     let test_pattern = create_test_rgba_pattern(640, 360);
-    texture_manager.update_texture(
-        &context.device,
-        &context.queue,
-        video_uuid,
-        640,
-        360,
-        &test_pattern,
-    );
+    texture_manager
+        .update_texture(
+            &context.device,
+            &context.queue,
+            video_uuid,
+            640,
+            360,
+            &test_pattern,
+        )
+        .expect("Upload failed");
 
     let composition = Composition::new(width, height, [0.1, 0.1, 0.1, 1.0]);
     let mut frame = composition.clone();
@@ -65,20 +80,19 @@ async fn test_full_composition_render() {
         opacity: 0.8,
         effect_stack: vec![],
         blend_mode: Default::default(),
+        resampling: Default::default(),
     });
 
     renderer
-        .render(&context, &texture_manager, &frame, &mut sink)
+        .render(&context, &texture_manager, &frame, &mut [&mut sink])
         .expect("Render failed");
 
-    context.instance.poll_all(true);
-
-    // Explicitly handle Result
-    let result: Result<Vec<u8>, RenderError> = sink.read_pixels(&context).await;
-    let image_data = result.expect("Failed to read pixels");
-
-    let path = Path::new("output_audit.png");
-    image::save_buffer(path, &image_data, width, height, image::ColorType::Rgba8).unwrap();
+    let path = sink
+        .write_frame(&context, 0)
+        .await
+        .expect("Failed to queue frame")
+        .unwrap();
+    assert_eq!(sink.flush().expect("Failed to write frame"), 1);
 
-    assert!(path.exists());
+    assert_eq!(image::image_dimensions(&path).unwrap(), (width, height));
 }
@@ -0,0 +1,203 @@
+use glam::Vec2;
+use std::path::PathBuf;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{FrameDescription, Layer, LayerSource, LayerTransform};
+use videomti_render::outputs::{ImageFileFormat, ImageSequenceSink, OverwritePolicy};
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::TextureManager;
+use videomti_render::sources::{ImageSequence, SequencePattern};
+use wgpu::{Features, TextureFormat};
+
+// Rows of 40 bytes exercise the readback padding
+const WIDTH: u32 = 10;
+const HEIGHT: u32 = 6;
+
+/// Orange texture for `half_covered`.
+fn solid_texture(ctx: &RenderContext) -> (TextureManager, Uuid) {
+    let mut texture_manager = TextureManager::new();
+    let id = Uuid::new_v4();
+    texture_manager
+        .update_texture(
+            &ctx.device,
+            &ctx.queue,
+            id,
+            2,
+            2,
+            &[200, 100, 30, 255].repeat(4),
+        )
+        .expect("Upload failed");
+    (texture_manager, id)
+}
+
+/// Transparent canvas with the texture `id` over the left half.
+fn half_covered(id: Uuid) -> FrameDescription {
+    let mut frame = FrameDescription::new(WIDTH, HEIGHT, [0.0; 4]);
+    frame.layers.push(Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::new(WIDTH as f32 / 4.0, HEIGHT as f32 / 2.0),
+            scale: Vec2::new(WIDTH as f32 / 2.0, HEIGHT as f32),
+            ..Default::default()
+        },
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+    frame
+}
+
+fn temp_pattern(name: &str) -> (PathBuf, SequencePattern) {
+    let dir = std::env::temp_dir().join(format!("videomti-export-{}", Uuid::new_v4()));
+    let pattern = dir.join(name).to_string_lossy().into_owned();
+    (dir, SequencePattern::Printf(pattern))
+}
+
+async fn export(ctx: &RenderContext, format: ImageFileFormat, name: &str) -> image::DynamicImage {
+    let (dir, pattern) = temp_pattern(name);
+    let mut sink = ImageSequenceSink::new(ctx, WIDTH, HEIGHT, pattern, format).unwrap();
+    let (texture_manager, id) = solid_texture(ctx);
+    Renderer::new(ctx)
+        .render(ctx, &texture_manager, &half_covered(id), &mut [&mut sink])
+        .expect("Render failed");
+    let path = sink.write_frame(ctx, 7).await.unwrap().unwrap();
+    assert_eq!(sink.flush().expect("Write failed"), 1);
+
+    let image = image::open(&path).expect("Exported file unreadable");
+    std::fs::remove_dir_all(dir).unwrap();
+    image
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[tokio::test]
+async fn test_export_formats() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let (left, right) = ((1, 3), (WIDTH - 2, 3));
+
+    let png = export(&ctx, ImageFileFormat::Png8, "f_%04d.png")
+        .await
+        .into_rgba8();
+    assert_eq!(png.dimensions(), (WIDTH, HEIGHT));
+    let color = png.get_pixel(left.0, left.1).0;
+    assert_eq!(color[3], 255);
+    assert_eq!(color, [200, 100, 30, 255]);
+    assert_eq!(png.get_pixel(right.0, right.1).0[3], 0, "alpha kept");
+
+    let tiff = export(&ctx, ImageFileFormat::Tiff8, "f_%04d.tif")
+        .await
+        .into_rgba8();
+    assert_eq!(tiff, png);
+
+    for (format, name) in [
+        (ImageFileFormat::Png16, "f_%04d.png"),
+        (ImageFileFormat::Tiff16, "f_%04d.tiff"),
+    ] {
+        let deep = export(&ctx, format, name).await;
+        let deep = deep.as_rgba16().expect("16-bit output");
+        let got = deep.get_pixel(left.0, left.1).0;
+        for (got, expected) in got.iter().zip(color) {
+            let expected = expected as f32 * 257.0;
+            assert!(
+                (*got as f32 - expected).abs() <= 2.0 * 257.0,
+                "{:?}",
+                format
+            );
+        }
+        assert_eq!(deep.get_pixel(right.0, right.1).0[3], 0);
+    }
+
+    // EXR holds scene-linear values
+    let exr = export(&ctx, ImageFileFormat::Exr, "f_%04d.exr").await;
+    let exr = exr.as_rgba32f().expect("float output");
+    let linear = exr.get_pixel(left.0, left.1).0;
+    for c in 0..3 {
+        let expected = srgb_to_linear(color[c] as f32 / 255.0);
+        assert!(
+            (linear[c] - expected).abs() < 0.01,
+            "{} vs {}",
+            linear[c],
+            expected
+        );
+    }
+    assert_eq!(linear[3], 1.0);
+    assert_eq!(exr.get_pixel(right.0, right.1).0[3], 0.0);
+}
+
+#[tokio::test]
+async fn test_export_overwrite() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut renderer = Renderer::new(&ctx);
+    let (texture_manager, id) = solid_texture(&ctx);
+    let (dir, pattern) = temp_pattern("shot.%03d.png");
+    let mut sink = ImageSequenceSink::with_threads(
+        &ctx,
+        WIDTH,
+        HEIGHT,
+        pattern.clone(),
+        ImageFileFormat::Png8,
+        3,
+    )
+    .unwrap();
+
+    for number in 1..=5 {
+        renderer
+            .render(&ctx, &texture_manager, &half_covered(id), &mut [&mut sink])
+            .expect("Render failed");
+        sink.write_frame(&ctx, number).await.unwrap();
+    }
+    assert_eq!(sink.flush().unwrap(), 5);
+    assert!(sink.path(3).ends_with("shot.003.png"));
+
+    // The files read back as a sequence
+    let sequence = ImageSequence::open(&pattern, None, 24.0).unwrap();
+    assert_eq!(sequence.frame_range(), 1..=5);
+    assert_eq!(sequence.dimensions(), (WIDTH, HEIGHT));
+
+    // Existing files fail by default, and can be skipped or replaced
+    let err = sink.write_frame(&ctx, 2).await.unwrap_err();
+    assert!(matches!(err, RenderError::Io(e) if e.kind() == std::io::ErrorKind::AlreadyExists));
+    sink.overwrite = OverwritePolicy::Skip;
+    assert_eq!(sink.write_frame(&ctx, 2).await.unwrap(), None);
+    sink.overwrite = OverwritePolicy::Overwrite;
+    assert_eq!(sink.write_frame(&ctx, 2).await.unwrap(), Some(sink.path(2)));
+    assert_eq!(sink.flush().unwrap(), 6);
+
+    drop(sink);
+    std::fs::remove_dir_all(dir).unwrap();
+
+    // Glob patterns can't name output files
+    let glob = SequencePattern::Glob("out/*.png".into());
+    assert!(matches!(
+        ImageSequenceSink::new(&ctx, WIDTH, HEIGHT, glob, ImageFileFormat::Png8),
+        Err(RenderError::InvalidSource(_))
+    ));
+}
+
+#[test]
+fn test_export_texture_formats() {
+    let norm16 = Features::TEXTURE_FORMAT_16BIT_NORM;
+    for format in [ImageFileFormat::Png16, ImageFileFormat::Tiff16] {
+        assert_eq!(format.texture_format(norm16), TextureFormat::Rgba16Unorm);
+        assert_eq!(
+            format.texture_format(Features::empty()),
+            TextureFormat::Rgba16Float
+        );
+    }
+    assert_eq!(
+        ImageFileFormat::Exr.texture_format(norm16),
+        TextureFormat::Rgba16Float
+    );
+    assert_eq!(
+        ImageFileFormat::Png8.texture_format(norm16),
+        TextureFormat::Rgba8Unorm
+    );
+}
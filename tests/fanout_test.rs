@@ -0,0 +1,166 @@
+use glam::Vec2;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{
+    FrameDescription, Layer, LayerSource, LayerTransform, TransferFunction,
+};
+use videomti_render::outputs::{BufferSink, ReadbackSink, RenderSink};
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::TextureManager;
+use wgpu::{TextureFormat, TextureView};
+
+const SIZE: u32 = 64;
+
+/// sRGB code of a linear grey.
+fn srgb_code(linear: f32) -> u8 {
+    (TransferFunction::Srgb.from_linear(linear) * 255.0).round() as u8
+}
+
+/// A sink that records how its frames end, or fails to prepare one.
+struct TrackingSink {
+    buffer: BufferSink,
+    fail: bool,
+    prepared: u32,
+    discarded: u32,
+}
+
+impl TrackingSink {
+    fn new(ctx: &RenderContext, fail: bool) -> Self {
+        Self {
+            buffer: BufferSink::new(ctx, SIZE, SIZE),
+            fail,
+            prepared: 0,
+            discarded: 0,
+        }
+    }
+}
+
+impl RenderSink for TrackingSink {
+    fn prepare_frame(&mut self) -> Result<TextureView, RenderError> {
+        if self.fail {
+            return Err(RenderError::InvalidOutput("test sink failed".into()));
+        }
+        self.prepared += 1;
+        self.buffer.prepare_frame()
+    }
+
+    fn present(&mut self, ctx: &RenderContext) {
+        self.buffer.present(ctx);
+    }
+
+    fn discard_frame(&mut self) {
+        self.discarded += 1;
+    }
+
+    fn format(&self) -> TextureFormat {
+        self.buffer.format()
+    }
+}
+
+#[tokio::test]
+async fn test_fan_out_renditions() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+
+    // One white column in every four, a quarter of the light on average
+    let id = Uuid::new_v4();
+    let stripes: Vec<u8> = (0..SIZE)
+        .flat_map(|x| match x % 4 {
+            0 => [255; 4],
+            _ => [0, 0, 0, 255],
+        })
+        .collect();
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, SIZE, 1, &stripes)
+        .expect("Upload failed");
+
+    let mut frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::splat(SIZE as f32 / 2.0),
+            scale: Vec2::splat(SIZE as f32),
+            ..Default::default()
+        },
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+
+    let mut master = BufferSink::new(&ctx, SIZE, SIZE);
+    let mut half = ReadbackSink::new(&ctx, SIZE / 2, SIZE / 2, 2);
+    let mut eighth = BufferSink::new(&ctx, SIZE / 8, SIZE / 8);
+    let sinks: &mut [&mut dyn RenderSink] = &mut [&mut master, &mut half, &mut eighth];
+    renderer
+        .render(&ctx, &texture_manager, &frame, sinks)
+        .expect("Render failed");
+
+    let image = master.read_image(&ctx).await.unwrap();
+    for (x, _, pixel) in image.enumerate_pixels() {
+        let expected = if x % 4 == 0 { 255 } else { 0 };
+        assert_eq!(pixel.0[0], expected, "master column {}", x);
+    }
+
+    // 2x: pairs of columns average to half or nothing
+    let frame = half.next_frame(&ctx).await.unwrap().expect("No frame");
+    assert_eq!(frame.index, 0);
+    for (i, pixel) in frame.data.chunks(4).enumerate() {
+        let expected = if i % 2 == 0 { srgb_code(0.5) } else { 0 };
+        assert!(
+            pixel[0].abs_diff(expected) <= 1,
+            "half-size pixel {}: {} vs {}",
+            i,
+            pixel[0],
+            expected
+        );
+    }
+
+    // 8x: the whole footprint is filtered, not just the texels nearest the
+    // centre, which would give half
+    let image = eighth.read_image(&ctx).await.unwrap();
+    for pixel in image.pixels() {
+        assert!(
+            pixel.0[0].abs_diff(srgb_code(0.25)) <= 2,
+            "{} vs {}",
+            pixel.0[0],
+            srgb_code(0.25)
+        );
+        assert_eq!(pixel.0[3], 255);
+    }
+}
+
+#[tokio::test]
+async fn test_fan_out_without_sinks() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+    let frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+    renderer
+        .render(&ctx, &texture_manager, &frame, &mut [])
+        .expect("Render failed");
+}
+
+#[tokio::test]
+async fn test_fan_out_discards_prepared_frames_on_failure() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+    let frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+
+    let mut first = TrackingSink::new(&ctx, false);
+    let mut second = TrackingSink::new(&ctx, true);
+    let mut third = TrackingSink::new(&ctx, false);
+    let sinks: &mut [&mut dyn RenderSink] = &mut [&mut first, &mut second, &mut third];
+    let result = renderer.render(&ctx, &texture_manager, &frame, sinks);
+    assert!(matches!(result, Err(RenderError::InvalidOutput(_))));
+
+    // Only the sink prepared before the failure has a frame to give back
+    assert_eq!((first.prepared, first.discarded), (1, 1));
+    assert_eq!((second.prepared, second.discarded), (0, 0));
+    assert_eq!((third.prepared, third.discarded), (0, 0));
+}
@@ -0,0 +1,234 @@
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::LayerSource;
+use videomti_render::resources::{AlphaMode, PixelFormat, PixelLayout, TextureManager};
+
+mod common;
+use common::render_center;
+
+/// Fills a `width` x `height` image with `texel`, leaving `padding` bytes
+/// at the end of each row.
+fn build_image(texel: &[u8], width: u32, height: u32, padding: u32) -> (Vec<u8>, u32) {
+    let stride = width * texel.len() as u32 + padding;
+    let mut data = vec![0xaa; (stride * height) as usize];
+    for row in 0..height {
+        for col in 0..width as usize {
+            let start = (row * stride) as usize + col * texel.len();
+            data[start..start + texel.len()].copy_from_slice(texel);
+        }
+    }
+    (data, stride)
+}
+
+fn assert_close(got: [u8; 4], expected: [u8; 3]) {
+    for c in 0..3 {
+        assert!(
+            got[c].abs_diff(expected[c]) <= 2,
+            "channel {}: expected {}, got {}",
+            c,
+            expected[c],
+            got[c]
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_pixel_formats() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let rgb = [200u8, 100, 50];
+    let float = rgb.map(|c| c as f32 / 255.0);
+    // Truncating f32 -> f16 conversion, enough for normal values
+    let half = |v: f32| -> [u8; 2] {
+        let bits = v.to_bits();
+        let exp = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+        let mantissa = (bits >> 13) & 0x3ff;
+        ((((exp as u32) << 10) | mantissa) as u16).to_le_bytes()
+    };
+
+    let cases: Vec<(PixelFormat, Vec<u8>, [u8; 3])> = vec![
+        (
+            PixelFormat::Rgba8,
+            [rgb[0], rgb[1], rgb[2], 255].to_vec(),
+            rgb,
+        ),
+        (
+            PixelFormat::Bgra8,
+            [rgb[2], rgb[1], rgb[0], 255].to_vec(),
+            rgb,
+        ),
+        (PixelFormat::Rgb8, rgb.to_vec(), rgb),
+        (
+            PixelFormat::Rgba16,
+            [rgb[0], rgb[1], rgb[2], 255]
+                .iter()
+                .flat_map(|&c| (c as u16 * 257).to_le_bytes())
+                .collect(),
+            rgb,
+        ),
+        (
+            PixelFormat::Rgba16Float,
+            [float[0], float[1], float[2], 1.0]
+                .iter()
+                .flat_map(|&c| half(c))
+                .collect(),
+            rgb,
+        ),
+        (
+            PixelFormat::Rgba32Float,
+            [float[0], float[1], float[2], 1.0]
+                .iter()
+                .flat_map(|c| c.to_le_bytes())
+                .collect(),
+            rgb,
+        ),
+        (PixelFormat::Gray8, vec![rgb[1]], [rgb[1]; 3]),
+    ];
+
+    for (format, texel, expected) in cases {
+        let mut texture_manager = TextureManager::new();
+        let id = Uuid::new_v4();
+        // 12 bytes of padding keeps wide formats word aligned while
+        // breaking any assumption of tightly packed rows
+        let (data, stride) = build_image(&texel, 5, 3, 12);
+        let layout = PixelLayout::new(format, 5, 3).with_stride(stride);
+        texture_manager
+            .upload_pixels(&ctx.device, &ctx.queue, id, &layout, &data)
+            .unwrap_or_else(|e| panic!("{:?} upload failed: {}", format, e));
+
+        let got: [u8; 4] = render_center(
+            &ctx,
+            &texture_manager,
+            LayerSource::Image { resource_id: id },
+            [0.0, 0.0, 0.0, 1.0],
+        )
+        .await;
+        assert!(
+            (0..3).all(|c| got[c].abs_diff(expected[c]) <= 2),
+            "{:?}: expected {:?}, got {:?}",
+            format,
+            expected,
+            got
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_alpha_modes() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+
+    // Half-covered white over black, as straight and premultiplied data
+    let straight = [255u8, 255, 255, 128];
+    let premultiplied = [128u8, 128, 128, 128];
+
+    let mut results = vec![];
+    for (texel, alpha) in [
+        (straight, AlphaMode::Straight),
+        (premultiplied, AlphaMode::Premultiplied),
+    ] {
+        let mut texture_manager = TextureManager::new();
+        let id = Uuid::new_v4();
+        let layout = PixelLayout::new(PixelFormat::Rgba8, 2, 2).with_alpha(alpha);
+        texture_manager
+            .upload_pixels(&ctx.device, &ctx.queue, id, &layout, &texel.repeat(4))
+            .expect("Upload failed");
+        results.push(
+            render_center(
+                &ctx,
+                &texture_manager,
+                LayerSource::Image { resource_id: id },
+                [0.0, 0.0, 0.0, 1.0],
+            )
+            .await,
+        );
+    }
+    let half_white = [results[0][0], results[0][1], results[0][2]];
+    assert!(half_white[0] > 64 && half_white[0] < 255);
+    assert_close(results[1], half_white);
+
+    // Alpha-only masks show white with that coverage
+    let mut texture_manager = TextureManager::new();
+    let id = Uuid::new_v4();
+    let layout = PixelLayout::new(PixelFormat::Alpha8, 2, 2);
+    texture_manager
+        .upload_pixels(&ctx.device, &ctx.queue, id, &layout, &[128; 4])
+        .expect("Upload failed");
+    let mask = render_center(
+        &ctx,
+        &texture_manager,
+        LayerSource::Image { resource_id: id },
+        [0.0, 0.0, 0.0, 1.0],
+    )
+    .await;
+    assert_close(mask, half_white);
+}
+
+#[tokio::test]
+async fn test_rejects_bad_layouts() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+
+    // Stride shorter than a row
+    let layout = PixelLayout::new(PixelFormat::Rgb8, 4, 4).with_stride(10);
+    let result =
+        texture_manager.upload_pixels(&ctx.device, &ctx.queue, Uuid::new_v4(), &layout, &[0; 64]);
+    assert!(matches!(result, Err(RenderError::InvalidUpload(_))));
+
+    // Truncated data
+    let layout = PixelLayout::new(PixelFormat::Rgba32Float, 4, 4);
+    let result =
+        texture_manager.upload_pixels(&ctx.device, &ctx.queue, Uuid::new_v4(), &layout, &[0; 64]);
+    assert!(matches!(result, Err(RenderError::InvalidUpload(_))));
+
+    // Empty image
+    let layout = PixelLayout::new(PixelFormat::Rgba8, 4, 0);
+    let result =
+        texture_manager.upload_pixels(&ctx.device, &ctx.queue, Uuid::new_v4(), &layout, &[0; 64]);
+    assert!(matches!(result, Err(RenderError::InvalidUpload(_))));
+}
+
+#[tokio::test]
+async fn test_reallocates_on_resize() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let id = Uuid::new_v4();
+
+    // Proxy, full-res, then proxy again with a format switch in between
+    let steps: [(u32, u32, PixelFormat, [u8; 3]); 4] = [
+        (4, 4, PixelFormat::Rgba8, [200, 0, 0]),
+        (12, 8, PixelFormat::Rgba8, [0, 200, 0]),
+        (12, 8, PixelFormat::Rgb8, [0, 0, 200]),
+        (4, 4, PixelFormat::Rgba8, [100, 100, 100]),
+    ];
+    for (width, height, format, rgb) in steps {
+        let texel = match format {
+            PixelFormat::Rgb8 => rgb.to_vec(),
+            _ => [rgb[0], rgb[1], rgb[2], 255].to_vec(),
+        };
+        let (data, stride) = build_image(&texel, width, height, 0);
+        let layout = PixelLayout::new(format, width, height).with_stride(stride);
+        texture_manager
+            .upload_pixels(&ctx.device, &ctx.queue, id, &layout, &data)
+            .expect("Upload failed");
+
+        let res = texture_manager.get_resource(&id).unwrap();
+        assert_eq!((res.width, res.height), (width, height));
+        assert_close(
+            render_center(
+                &ctx,
+                &texture_manager,
+                LayerSource::Image { resource_id: id },
+                [0.0, 0.0, 0.0, 1.0],
+            )
+            .await,
+            rgb,
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use glam::Vec2;
+use image::{DynamicImage, ImageEncoder, ImageFormat, Rgb, RgbImage, Rgba, Rgba32FImage};
+use std::io::Cursor;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{
+    ColorSpace, FrameDescription, Layer, LayerSource, LayerTransform, ResamplingQuality,
+};
+use videomti_render::outputs::BufferSink;
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::{PixelFormat, TextureManager};
+
+const SIZE: u32 = 16;
+
+async fn render(ctx: &RenderContext, texture_manager: &TextureManager, id: Uuid) -> Vec<[u8; 3]> {
+    let mut renderer = Renderer::new(ctx);
+    let mut sink = BufferSink::new(ctx, SIZE, SIZE);
+
+    let mut frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::splat(SIZE as f32 / 2.0),
+            scale: Vec2::splat(SIZE as f32),
+            ..Default::default()
+        },
+        resampling: ResamplingQuality::Nearest,
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+
+    renderer
+        .render(ctx, texture_manager, &frame, &mut [&mut sink])
+        .expect("Render failed");
+    let mapped = sink.read_rows(ctx).await.unwrap();
+    let offset = SIZE as usize / 2 * 4;
+    mapped
+        .rows()
+        .map(|row| [row[offset], row[offset + 1], row[offset + 2]])
+        .collect()
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat) -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, format).expect("Encode failed");
+    bytes.into_inner()
+}
+
+/// Minimal ICC profile carrying only a v2 `desc` tag.
+fn icc_profile(description: &str) -> Vec<u8> {
+    let mut tag = b"desc\0\0\0\0".to_vec();
+    tag.extend((description.len() as u32 + 1).to_be_bytes());
+    tag.extend(description.as_bytes());
+    tag.push(0);
+
+    let mut profile = vec![0u8; 128];
+    profile.extend(1u32.to_be_bytes());
+    profile.extend(b"desc");
+    profile.extend(144u32.to_be_bytes());
+    profile.extend((tag.len() as u32).to_be_bytes());
+    profile.extend(tag);
+    profile
+}
+
+#[tokio::test]
+async fn test_load_formats() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let rgb = [200u8, 100, 50];
+    let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(6, 4, Rgb(rgb)));
+
+    for (format, tolerance) in [
+        (ImageFormat::Png, 2),
+        (ImageFormat::Jpeg, 6),
+        (ImageFormat::WebP, 2),
+        (ImageFormat::Tiff, 2),
+        (ImageFormat::Bmp, 2),
+    ] {
+        let mut texture_manager = TextureManager::new();
+        let loaded = texture_manager
+            .load_image_bytes(&ctx.device, &ctx.queue, &encode(&image, format))
+            .unwrap_or_else(|e| panic!("{:?} load failed: {}", format, e));
+        assert_eq!((loaded.width, loaded.height), (6, 4));
+        assert_eq!(loaded.color_space, ColorSpace::SRGB);
+
+        let got = render(&ctx, &texture_manager, loaded.id).await[SIZE as usize / 2];
+        assert!(
+            (0..3).all(|c| got[c].abs_diff(rgb[c]) <= tolerance),
+            "{:?}: expected {:?}, got {:?}",
+            format,
+            rgb,
+            got
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_load_high_bit_depth() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+
+    // 16-bit PNG keeps its precision
+    let deep =
+        DynamicImage::ImageRgb16(image::ImageBuffer::from_pixel(3, 3, Rgb([0, 32768, 65535])));
+    let loaded = texture_manager
+        .load_image_bytes(&ctx.device, &ctx.queue, &encode(&deep, ImageFormat::Png))
+        .expect("16-bit PNG load failed");
+    let res = texture_manager.get_resource(&loaded.id).unwrap();
+    assert_eq!(res.pixel_format, Some(PixelFormat::Rgba16));
+
+    // EXR is float and scene-linear
+    let linear = Rgba32FImage::from_pixel(3, 3, Rgba([0.5, 0.25, 1.0, 1.0]));
+    let exr = encode(&DynamicImage::ImageRgba32F(linear), ImageFormat::OpenExr);
+    let loaded = texture_manager
+        .load_image_bytes(&ctx.device, &ctx.queue, &exr)
+        .expect("EXR load failed");
+    assert_eq!(loaded.color_space, ColorSpace::LINEAR_REC709);
+    let res = texture_manager.get_resource(&loaded.id).unwrap();
+    assert_eq!(res.pixel_format, Some(PixelFormat::Rgba32Float));
+    assert_eq!(res.color_space, ColorSpace::LINEAR_REC709);
+
+    // Linear 0.5 comes out sRGB encoded
+    let got = render(&ctx, &texture_manager, loaded.id).await[SIZE as usize / 2];
+    assert!(got[0].abs_diff(188) <= 2, "got {:?}", got);
+}
+
+#[tokio::test]
+async fn test_load_metadata() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+
+    // Red | blue, tagged "rotate 90° clockwise"
+    let mut image = RgbImage::new(2, 1);
+    image.put_pixel(0, 0, Rgb([255, 0, 0]));
+    image.put_pixel(1, 0, Rgb([0, 0, 255]));
+    let mut exif = b"MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+    exif.extend([0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0, 0]);
+
+    let mut bytes = Vec::new();
+    let mut encoder = image::codecs::png::PngEncoder::new(&mut bytes);
+    encoder.set_exif_metadata(exif).unwrap();
+    encoder.set_icc_profile(icc_profile("Display P3")).unwrap();
+    encoder
+        .write_image(&image, 2, 1, image::ExtendedColorType::Rgb8)
+        .unwrap();
+
+    let loaded = texture_manager
+        .load_image_bytes(&ctx.device, &ctx.queue, &bytes)
+        .expect("Load failed");
+    assert_eq!((loaded.width, loaded.height), (1, 2));
+    assert_eq!(loaded.color_space, ColorSpace::DISPLAY_P3);
+
+    // Same as an untagged image stored red-over-blue
+    let mut upright = RgbImage::new(1, 2);
+    upright.put_pixel(0, 0, Rgb([255, 0, 0]));
+    upright.put_pixel(0, 1, Rgb([0, 0, 255]));
+    let reference = texture_manager
+        .load_image_bytes(
+            &ctx.device,
+            &ctx.queue,
+            &encode(&DynamicImage::ImageRgb8(upright), ImageFormat::Png),
+        )
+        .expect("Load failed");
+    assert_eq!(reference.color_space, ColorSpace::SRGB);
+    texture_manager.set_color_space(reference.id, ColorSpace::DISPLAY_P3);
+    assert_eq!(
+        render(&ctx, &texture_manager, loaded.id).await,
+        render(&ctx, &texture_manager, reference.id).await
+    );
+}
+
+#[tokio::test]
+async fn test_load_errors() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+
+    let result = texture_manager.load_image_bytes(&ctx.device, &ctx.queue, b"not an image");
+    assert!(matches!(result, Err(RenderError::ImageDecode(_))));
+
+    let result = texture_manager.load_image(&ctx.device, &ctx.queue, "/nonexistent/image.png");
+    assert!(matches!(result, Err(RenderError::Io(_))));
+    assert!(texture_manager.resources.is_empty());
+}
+
+#[tokio::test]
+async fn test_malformed_icc_tag_table() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let image = RgbImage::from_pixel(2, 2, Rgb([10, 20, 30]));
+
+    // A tag count far past the table, with and without a usable entry
+    let mut oversized = icc_profile("Display P3");
+    oversized[128..132].copy_from_slice(&u32::MAX.to_be_bytes());
+    let mut truncated = oversized.clone();
+    truncated[132..136].copy_from_slice(b"wtpt");
+
+    for (profile, expected) in [
+        (oversized, ColorSpace::DISPLAY_P3),
+        (truncated, ColorSpace::SRGB),
+    ] {
+        let mut bytes = Vec::new();
+        let mut encoder = image::codecs::png::PngEncoder::new(&mut bytes);
+        encoder.set_icc_profile(profile).unwrap();
+        encoder
+            .write_image(&image, 2, 2, image::ExtendedColorType::Rgb8)
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let loaded = texture_manager
+            .load_image_bytes(&ctx.device, &ctx.queue, &bytes)
+            .expect("Load failed");
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+        assert_eq!(loaded.color_space, expected);
+    }
+}
@@ -0,0 +1,165 @@
+use glam::Vec2;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{
+    ColorSpace, FrameDescription, Layer, LayerSource, LayerTransform, TransferFunction,
+};
+use videomti_render::outputs::{BufferSink, TextureSink};
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::{ImportedTexture, TextureManager};
+use wgpu::{TextureFormat, TextureUsages};
+
+const SIZE: u32 = 16;
+
+/// A frame drawing `id` over the whole canvas.
+fn full_frame(id: Uuid) -> FrameDescription {
+    let mut frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::splat(SIZE as f32 / 2.0),
+            scale: Vec2::splat(SIZE as f32),
+            ..Default::default()
+        },
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+    frame
+}
+
+#[tokio::test]
+async fn test_import_rendered_texture() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+
+    // Another pipeline's output: a frame rendered into a sampled texture
+    let source = Uuid::new_v4();
+    texture_manager
+        .update_texture(
+            &ctx.device,
+            &ctx.queue,
+            source,
+            2,
+            2,
+            &[200, 100, 50, 255].repeat(4),
+        )
+        .expect("Upload failed");
+    let mut host = TextureSink::new(&ctx, SIZE, SIZE);
+    renderer
+        .render(
+            &ctx,
+            &texture_manager,
+            &full_frame(source),
+            &mut [&mut host],
+        )
+        .expect("Render failed");
+
+    let id = Uuid::new_v4();
+    texture_manager
+        .import_texture(&ctx.device, id, ImportedTexture::new(host.texture.clone()))
+        .expect("Import failed");
+    let res = texture_manager.get_resource(&id).unwrap();
+    assert!(res.is_imported());
+    assert!(res.texture == host.texture);
+    assert_eq!((res.width, res.height), (SIZE, SIZE));
+    assert_eq!(res.format, TextureFormat::Rgba8UnormSrgb);
+    // The sampler decodes sRGB formats already
+    assert_eq!(res.color_space.transfer, TransferFunction::Linear);
+
+    let mut output = BufferSink::new(&ctx, SIZE, SIZE);
+    renderer
+        .render(&ctx, &texture_manager, &full_frame(id), &mut [&mut output])
+        .expect("Render failed");
+    let pixels = output.read_pixels(&ctx).await.unwrap();
+    for pixel in pixels.chunks(4) {
+        assert!(
+            pixel[0].abs_diff(200) <= 1 && pixel[1].abs_diff(100) <= 1,
+            "{:?}",
+            pixel
+        );
+    }
+
+    // Host memory is neither budgeted nor pooled
+    let stats = texture_manager.memory_stats();
+    assert_eq!(stats.resident_bytes, (2 * 2 * 4) as u64);
+    texture_manager.set_memory_budget(Some(0));
+    assert!(texture_manager.get_resource(&id).is_some());
+    assert!(texture_manager.remove(&id));
+    assert_eq!(texture_manager.pool().stats().idle_bytes, 0);
+}
+
+#[tokio::test]
+async fn test_upload_replaces_import() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let host = TextureSink::with_format(
+        &ctx,
+        2,
+        2,
+        TextureFormat::Rgba8Unorm,
+        ColorSpace::SRGB,
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+    );
+
+    let id = Uuid::new_v4();
+    texture_manager
+        .import_texture(
+            &ctx.device,
+            id,
+            ImportedTexture::new(host.texture.clone())
+                .with_view(host.view.clone())
+                .with_color_space(ColorSpace::DISPLAY_P3),
+        )
+        .expect("Import failed");
+    let res = texture_manager.get_resource(&id).unwrap();
+    assert_eq!(res.color_space, ColorSpace::DISPLAY_P3);
+
+    // Uploads never write into the host's texture
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, 2, 2, &[0; 16])
+        .expect("Upload failed");
+    let res = texture_manager.get_resource(&id).unwrap();
+    assert!(!res.is_imported());
+    assert!(res.texture != host.texture);
+    assert_eq!(res.color_space, ColorSpace::SRGB);
+}
+
+#[tokio::test]
+async fn test_import_validation() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let create = |format, usage| {
+        ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 4,
+                height: 4,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        })
+    };
+
+    let unsampled = create(TextureFormat::Rgba8Unorm, TextureUsages::COPY_SRC);
+    let integer = create(TextureFormat::Rgba8Uint, TextureUsages::TEXTURE_BINDING);
+    for texture in [unsampled, integer] {
+        let result = texture_manager.import_texture(
+            &ctx.device,
+            Uuid::new_v4(),
+            ImportedTexture::new(texture),
+        );
+        assert!(matches!(result, Err(RenderError::InvalidSource(_))));
+    }
+    assert_eq!(texture_manager.memory_stats().resource_count, 0);
+}
@@ -0,0 +1,126 @@
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+use videomti_render::core::RenderContext;
+use videomti_render::model::FrameDescription;
+use videomti_render::outputs::BufferSink;
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::{TextureManager, TexturePool};
+
+const SIZE: u32 = 16;
+const RESOURCE_BYTES: u64 = (SIZE * SIZE * 4) as u64;
+
+fn upload(ctx: &RenderContext, texture_manager: &mut TextureManager, id: Uuid) {
+    let data = vec![128u8; RESOURCE_BYTES as usize];
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, SIZE, SIZE, &data)
+        .expect("Upload failed");
+}
+
+#[tokio::test]
+async fn test_lru_eviction() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    texture_manager.set_memory_budget(Some(2 * RESOURCE_BYTES));
+
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let log = evicted.clone();
+    texture_manager.set_eviction_callback(move |id| log.lock().unwrap().push(id));
+
+    let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+    upload(&ctx, &mut texture_manager, ids[0]);
+    upload(&ctx, &mut texture_manager, ids[1]);
+    assert_eq!(
+        texture_manager.memory_stats().resident_bytes,
+        2 * RESOURCE_BYTES
+    );
+
+    // Drawing the first resource makes the second the least recently used
+    assert!(texture_manager.get_resource(&ids[0]).is_some());
+    upload(&ctx, &mut texture_manager, ids[2]);
+
+    assert_eq!(*evicted.lock().unwrap(), vec![ids[1]]);
+    assert!(texture_manager.get_resource(&ids[1]).is_none());
+    let stats = texture_manager.memory_stats();
+    assert_eq!(stats.resource_count, 2);
+    assert_eq!(stats.evictions, 1);
+    assert!(stats.resident_bytes + stats.pooled_bytes <= 2 * RESOURCE_BYTES);
+
+    // Shrinking the budget evicts immediately
+    texture_manager.set_memory_budget(Some(RESOURCE_BYTES));
+    assert_eq!(texture_manager.memory_stats().resource_count, 1);
+    assert_eq!(*evicted.lock().unwrap(), vec![ids[1], ids[0]]);
+}
+
+#[tokio::test]
+async fn test_remove_and_clear() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+
+    let a = Uuid::new_v4();
+    let b = Uuid::new_v4();
+    upload(&ctx, &mut texture_manager, a);
+    upload(&ctx, &mut texture_manager, b);
+
+    assert!(texture_manager.remove(&a));
+    assert!(!texture_manager.remove(&a));
+    assert_eq!(
+        texture_manager.memory_stats().resident_bytes,
+        RESOURCE_BYTES
+    );
+
+    texture_manager.clear();
+    assert_eq!(
+        texture_manager.memory_stats(),
+        Default::default(),
+        "clear should release everything"
+    );
+}
+
+#[tokio::test]
+async fn test_texture_pool_reuse() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let pool = Arc::new(TexturePool::new().with_max_idle_frames(2));
+    let mut texture_manager = TextureManager::with_pool(pool.clone());
+    let mut renderer = Renderer::new(&ctx);
+    let id = Uuid::new_v4();
+
+    // Proxy -> full-res -> proxy reuses the proxy allocation
+    for size in [4u32, 8, 4] {
+        let data = vec![255u8; (size * size * 4) as usize];
+        texture_manager
+            .update_texture(&ctx.device, &ctx.queue, id, size, size, &data)
+            .expect("Upload failed");
+    }
+    let stats = pool.stats();
+    assert_eq!((stats.misses, stats.hits), (2, 1));
+    assert_eq!(stats.idle_textures, 1);
+
+    // The renderer's master frame comes from the same pool
+    for size in [SIZE, SIZE * 2, SIZE] {
+        let mut sink = BufferSink::new(&ctx, size, size);
+        let frame = FrameDescription::new(size, size, [0.0, 0.0, 0.0, 1.0]);
+        renderer
+            .render(&ctx, &texture_manager, &frame, &mut [&mut sink])
+            .expect("Render failed");
+    }
+    let stats = pool.stats();
+    assert_eq!((stats.misses, stats.hits), (4, 2));
+
+    // Idle textures are trimmed after a few frames
+    let frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+    let mut sink = BufferSink::new(&ctx, SIZE, SIZE);
+    for _ in 0..3 {
+        renderer
+            .render(&ctx, &texture_manager, &frame, &mut [&mut sink])
+            .expect("Render failed");
+    }
+    let stats = pool.stats();
+    assert_eq!(stats.idle_textures, 0);
+    assert_eq!(stats.trimmed, 2);
+}
@@ -0,0 +1,179 @@
+use glam::Vec2;
+use std::io::{Cursor, Write};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{FrameDescription, Layer, LayerSource, LayerTransform};
+use videomti_render::outputs::{PipeFormat, PipeSink};
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::{TextureManager, YuvFormat};
+use videomti_render::sources::{FrameProvider, Y4mReader};
+
+// Rows of 40 bytes exercise the readback padding
+const WIDTH: u32 = 10;
+const HEIGHT: u32 = 6;
+
+/// A `Write` the test can inspect after the sink's writer thread is done.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Renders `frames` full-canvas frames of a gray texture into `sink`.
+async fn stream(ctx: &RenderContext, sink: &mut PipeSink, frames: u32) -> Result<(), RenderError> {
+    let mut texture_manager = TextureManager::new();
+    let id = Uuid::new_v4();
+    texture_manager
+        .update_texture(
+            &ctx.device,
+            &ctx.queue,
+            id,
+            2,
+            2,
+            &[128, 128, 128, 255].repeat(4),
+        )
+        .expect("Upload failed");
+    let mut frame = FrameDescription::new(WIDTH, HEIGHT, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::new(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0),
+            scale: Vec2::new(WIDTH as f32, HEIGHT as f32),
+            ..Default::default()
+        },
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+
+    let mut renderer = Renderer::new(ctx);
+    for _ in 0..frames {
+        renderer.render(ctx, &texture_manager, &frame, &mut [sink])?;
+        sink.write_frame(ctx).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pipe_y4m() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let output = SharedBuffer::default();
+    let mut sink = PipeSink::new(
+        &ctx,
+        WIDTH,
+        HEIGHT,
+        PipeFormat::Y4m(YuvFormat::I420),
+        (30000, 1001),
+        output.clone(),
+    )
+    .unwrap();
+    stream(&ctx, &mut sink, 3).await.expect("Streaming failed");
+    assert_eq!(sink.finish().unwrap(), 3);
+
+    // The stream reads back with our own Y4M reader
+    let bytes = output.0.lock().unwrap().clone();
+    let mut reader = Y4mReader::new(Cursor::new(bytes)).expect("Invalid Y4M");
+    assert_eq!((reader.header.width, reader.header.height), (WIDTH, HEIGHT));
+    assert_eq!(reader.header.frame_rate, (30000, 1001));
+    assert_eq!(reader.frame_count(), Some(3));
+    let planes = reader.read_planes(2).unwrap();
+    // sRGB mid gray re-encoded for BT.709 is 0.452, code 115 in limited range
+    assert!(planes[0].data.iter().all(|&y| y.abs_diff(115) <= 1));
+    assert!(planes[1].data.iter().all(|&c| c.abs_diff(128) <= 1));
+
+    // Y4M can't carry semi-planar formats
+    assert!(matches!(
+        PipeSink::new(
+            &ctx,
+            WIDTH,
+            HEIGHT,
+            PipeFormat::Y4m(YuvFormat::Nv12),
+            (25, 1),
+            Vec::new()
+        ),
+        Err(RenderError::InvalidOutput(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_pipe_raw_rgba() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let output = SharedBuffer::default();
+    let mut sink = PipeSink::new(
+        &ctx,
+        WIDTH,
+        HEIGHT,
+        PipeFormat::RawRgba,
+        (25, 1),
+        output.clone(),
+    )
+    .unwrap();
+    stream(&ctx, &mut sink, 2).await.expect("Streaming failed");
+    sink.finish().unwrap();
+
+    let bytes = output.0.lock().unwrap();
+    assert_eq!(
+        bytes.len(),
+        (2 * WIDTH * HEIGHT * 4) as usize,
+        "no row padding"
+    );
+    assert!(bytes.chunks(4).all(|px| px == [128, 128, 128, 255]));
+}
+
+#[tokio::test]
+async fn test_pipe_child_process() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let path = std::env::temp_dir().join(format!("videomti-pipe-{}.nv12", Uuid::new_v4()));
+    let mut sink = PipeSink::spawn(
+        &ctx,
+        WIDTH,
+        HEIGHT,
+        PipeFormat::RawYuv(YuvFormat::Nv12),
+        (25, 1),
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("cat > {}", path.display())),
+    )
+    .expect("Spawn failed");
+    stream(&ctx, &mut sink, 4).await.expect("Streaming failed");
+    assert_eq!(sink.finish().unwrap(), 4);
+    let frame_bytes = WIDTH * HEIGHT * 3 / 2;
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().len(),
+        (4 * frame_bytes) as u64
+    );
+    std::fs::remove_file(path).unwrap();
+
+    // A consumer that quits surfaces its exit status
+    let mut sink = PipeSink::spawn(
+        &ctx,
+        WIDTH,
+        HEIGHT,
+        PipeFormat::RawRgba,
+        (25, 1),
+        Command::new("sh").arg("-c").arg("exit 3"),
+    )
+    .expect("Spawn failed");
+    let error = match stream(&ctx, &mut sink, 4).await {
+        Err(error) => error,
+        Ok(()) => sink.finish().unwrap_err(),
+    };
+    match error {
+        RenderError::ProcessExited(status) => assert_eq!(status.code(), Some(3)),
+        other => panic!("unexpected error: {}", other),
+    }
+}
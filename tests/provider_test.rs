@@ -0,0 +1,242 @@
+use image::{Rgb, RgbImage};
+use std::sync::mpsc::{Receiver, channel};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::ColorSpace;
+use videomti_render::resources::{DecodedImage, PixelFormat, PixelLayout, TextureManager};
+use videomti_render::sources::{
+    FrameData, FrameFormat, FramePrefetcher, FrameProvider, ImageSequence, SequencePattern,
+    VideoFrame,
+};
+
+mod common;
+use common::{render_frame_center, video_frame};
+
+/// Solid frames whose red channel encodes the index, logging every call.
+struct CountingProvider {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl FrameProvider for CountingProvider {
+    fn dimensions(&self) -> (u32, u32) {
+        (2, 2)
+    }
+
+    fn frame_rate(&self) -> f64 {
+        10.0
+    }
+
+    fn pixel_format(&self) -> FrameFormat {
+        FrameFormat::Pixels(PixelFormat::Rgba8)
+    }
+
+    fn frame_count(&self) -> Option<u64> {
+        Some(20)
+    }
+
+    fn seek(&mut self, index: u64) -> Result<(), RenderError> {
+        self.log.lock().unwrap().push(format!("seek {}", index));
+        Ok(())
+    }
+
+    fn decode_frame(&mut self, index: u64) -> Result<VideoFrame, RenderError> {
+        self.log.lock().unwrap().push(format!("decode {}", index));
+        Ok(VideoFrame {
+            index,
+            timestamp: index as f64 / 10.0,
+            data: FrameData::Pixels(DecodedImage {
+                layout: PixelLayout::new(PixelFormat::Rgba8, 2, 2),
+                data: [index as u8 * 10, 0, 0, 255].repeat(4),
+                color_space: ColorSpace::SRGB,
+            }),
+        })
+    }
+}
+
+/// Counts like `CountingProvider` but stalls decoding frame 2 until
+/// released, or for five seconds.
+struct StallingProvider {
+    inner: CountingProvider,
+    release: Mutex<Receiver<()>>,
+}
+
+impl FrameProvider for StallingProvider {
+    fn dimensions(&self) -> (u32, u32) {
+        self.inner.dimensions()
+    }
+
+    fn frame_rate(&self) -> f64 {
+        self.inner.frame_rate()
+    }
+
+    fn pixel_format(&self) -> FrameFormat {
+        self.inner.pixel_format()
+    }
+
+    fn frame_count(&self) -> Option<u64> {
+        self.inner.frame_count()
+    }
+
+    fn seek(&mut self, index: u64) -> Result<(), RenderError> {
+        self.inner.seek(index)
+    }
+
+    fn decode_frame(&mut self, index: u64) -> Result<VideoFrame, RenderError> {
+        let frame = self.inner.decode_frame(index);
+        if index == 2 {
+            let _ = self
+                .release
+                .lock()
+                .unwrap()
+                .recv_timeout(Duration::from_secs(5));
+        }
+        frame
+    }
+}
+
+fn wait_for_cache(prefetcher: &FramePrefetcher, id: &Uuid, frames: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while prefetcher.cached_frames(id) < frames && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(prefetcher.cached_frames(id), frames);
+}
+
+#[tokio::test]
+async fn test_prefetch_read_ahead() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut prefetcher = FramePrefetcher::with_read_ahead(4);
+    let id = Uuid::new_v4();
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let info = prefetcher
+        .register(id, CountingProvider { log: log.clone() })
+        .expect("Register failed");
+    assert_eq!(
+        (info.width, info.height, info.frame_count),
+        (2, 2, Some(20))
+    );
+
+    // The first frame is decoded on demand, the next four in the background
+    let frame = video_frame(id, 0.0);
+    prefetcher
+        .prepare(&ctx.device, &ctx.queue, &mut texture_manager, &frame)
+        .expect("Prepare failed");
+    wait_for_cache(&prefetcher, &id, 5);
+    let decodes = |log: &[String]| log.iter().filter(|l| l.starts_with("decode")).count();
+    assert_eq!(log.lock().unwrap()[0], "seek 0");
+    assert_eq!(decodes(&log.lock().unwrap()), 5);
+
+    // Playing on is served from the cache and only extends the window
+    let frame = video_frame(id, 0.1);
+    prefetcher
+        .prepare(&ctx.device, &ctx.queue, &mut texture_manager, &frame)
+        .expect("Prepare failed");
+    assert_eq!(
+        render_frame_center(&ctx, &texture_manager, &frame).await,
+        [10, 0, 0]
+    );
+    wait_for_cache(&prefetcher, &id, 5);
+    assert_eq!(decodes(&log.lock().unwrap()), 6);
+
+    // Jumping ahead seeks; times past the end hold the last frame
+    for (time, red) in [(1.5, 150), (9.0, 190)] {
+        let frame = video_frame(id, time);
+        prefetcher
+            .prepare(&ctx.device, &ctx.queue, &mut texture_manager, &frame)
+            .expect("Prepare failed");
+        assert_eq!(
+            render_frame_center(&ctx, &texture_manager, &frame).await,
+            [red, 0, 0]
+        );
+    }
+    assert!(log.lock().unwrap().contains(&"seek 15".to_string()));
+    assert!(prefetcher.unregister(&id));
+}
+
+#[tokio::test]
+async fn test_sequence_provider() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let dir = std::env::temp_dir().join(format!("videomti-provider-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let colors = [[200, 0, 0], [0, 200, 0], [0, 0, 200]];
+    for (number, color) in colors.iter().enumerate() {
+        RgbImage::from_pixel(4, 4, Rgb(*color))
+            .save(dir.join(format!("shot.{}.png", number + 1001)))
+            .unwrap();
+    }
+
+    let pattern = SequencePattern::Printf(dir.join("shot.%d.png").to_string_lossy().into());
+    let sequence = ImageSequence::open(&pattern, None, 24.0).unwrap();
+    let mut texture_manager = TextureManager::new();
+    let mut prefetcher = FramePrefetcher::new();
+    let id = Uuid::new_v4();
+    let info = prefetcher.register(id, sequence).expect("Register failed");
+    assert_eq!(info.pixel_format, FrameFormat::Pixels(PixelFormat::Rgb8));
+    assert_eq!((info.width, info.height), (4, 4));
+
+    for (index, color) in colors.iter().enumerate() {
+        let frame = video_frame(id, index as f64 / 24.0);
+        prefetcher
+            .prepare(&ctx.device, &ctx.queue, &mut texture_manager, &frame)
+            .expect("Prepare failed");
+        assert_eq!(
+            render_frame_center(&ctx, &texture_manager, &frame).await,
+            *color
+        );
+    }
+
+    drop(prefetcher);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_cached_frame_during_decode() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut prefetcher = FramePrefetcher::with_read_ahead(4);
+    let id = Uuid::new_v4();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let (release, receiver) = channel();
+    let provider = StallingProvider {
+        inner: CountingProvider { log: log.clone() },
+        release: Mutex::new(receiver),
+    };
+    prefetcher.register(id, provider).expect("Register failed");
+
+    prefetcher
+        .prepare(
+            &ctx.device,
+            &ctx.queue,
+            &mut texture_manager,
+            &video_frame(id, 0.0),
+        )
+        .expect("Prepare failed");
+    wait_for_cache(&prefetcher, &id, 2);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !log.lock().unwrap().contains(&"decode 2".to_string()) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    // Frame 1 is cached, so showing it mustn't wait for the stalled decode
+    let started = Instant::now();
+    let frame = video_frame(id, 0.1);
+    prefetcher
+        .prepare(&ctx.device, &ctx.queue, &mut texture_manager, &frame)
+        .expect("Prepare failed");
+    assert!(started.elapsed() < Duration::from_secs(2));
+    release.send(()).unwrap();
+    assert_eq!(
+        render_frame_center(&ctx, &texture_manager, &frame).await,
+        [10, 0, 0]
+    );
+}
@@ -0,0 +1,178 @@
+use glam::Vec2;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{FrameDescription, Layer, LayerSource, LayerTransform};
+use videomti_render::outputs::ReadbackSink;
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::TextureManager;
+
+const SIZE: u32 = 16;
+const COLORS: [[u8; 4]; 5] = [
+    [255, 0, 0, 255],
+    [0, 255, 0, 255],
+    [0, 0, 255, 255],
+    [255, 255, 255, 255],
+    [255, 0, 255, 255],
+];
+
+/// Renders a full-frame solid `color` into the sink.
+fn render_solid(
+    ctx: &RenderContext,
+    renderer: &mut Renderer,
+    texture_manager: &mut TextureManager,
+    sink: &mut ReadbackSink,
+    color: [u8; 4],
+) {
+    try_render_solid(ctx, renderer, texture_manager, sink, color).expect("Render failed");
+}
+
+fn try_render_solid(
+    ctx: &RenderContext,
+    renderer: &mut Renderer,
+    texture_manager: &mut TextureManager,
+    sink: &mut ReadbackSink,
+    color: [u8; 4],
+) -> Result<(), RenderError> {
+    let id = Uuid::new_v4();
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, 4, 4, &color.repeat(16))
+        .expect("Upload failed");
+    let mut frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::splat(SIZE as f32 / 2.0),
+            scale: Vec2::splat(SIZE as f32),
+            ..Default::default()
+        },
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+    renderer.render(ctx, texture_manager, &frame, &mut [sink])
+}
+
+#[tokio::test]
+async fn test_readback_ring_in_order() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+    let mut sink = ReadbackSink::new(&ctx, SIZE, SIZE, 2);
+
+    // More frames than buffers: the oldest are held until taken, up to
+    // one more ring's worth
+    let mut tickets = vec![];
+    for color in &COLORS[..4] {
+        render_solid(&ctx, &mut renderer, &mut texture_manager, &mut sink, *color);
+        tickets.push(sink.latest_ticket().expect("No ticket"));
+    }
+    assert_eq!(
+        tickets.iter().map(|t| t.index).collect::<Vec<_>>(),
+        [0, 1, 2, 3]
+    );
+    assert_eq!((sink.pending(), sink.frames_held()), (2, 2));
+    assert!(matches!(
+        try_render_solid(
+            &ctx,
+            &mut renderer,
+            &mut texture_manager,
+            &mut sink,
+            COLORS[4]
+        ),
+        Err(RenderError::InvalidOutput(_))
+    ));
+    assert_eq!(sink.frames_presented(), 4);
+
+    // Taking a frame makes room again
+    let first = sink.next_frame(&ctx).await.unwrap().expect("Frame missing");
+    assert_eq!(first.index, 0);
+    render_solid(
+        &ctx,
+        &mut renderer,
+        &mut texture_manager,
+        &mut sink,
+        COLORS[4],
+    );
+    assert_eq!(sink.latest_ticket().unwrap().index, 4);
+    tickets.push(sink.latest_ticket().unwrap());
+
+    for (index, color) in COLORS.into_iter().enumerate().skip(1) {
+        let frame = sink
+            .next_frame(&ctx)
+            .await
+            .expect("Readback failed")
+            .expect("Frame missing");
+        assert_eq!(frame.index, index as u64);
+        assert_eq!(frame.data.len(), (SIZE * SIZE * 4) as usize);
+        assert!(
+            frame.data.chunks(4).all(|p| p == color),
+            "frame {} is not {:?}",
+            index,
+            color
+        );
+        assert!(tickets[index].is_ready());
+    }
+    assert!(sink.next_frame(&ctx).await.unwrap().is_none());
+    assert_eq!((sink.pending(), sink.frames_held()), (0, 0));
+}
+
+#[tokio::test]
+async fn test_readback_ticket() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+    let mut sink = ReadbackSink::new(&ctx, SIZE, SIZE, 3);
+    assert!(sink.try_next_frame(&ctx).unwrap().is_none());
+    assert!(sink.latest_ticket().is_none());
+
+    render_solid(
+        &ctx,
+        &mut renderer,
+        &mut texture_manager,
+        &mut sink,
+        COLORS[2],
+    );
+    render_solid(
+        &ctx,
+        &mut renderer,
+        &mut texture_manager,
+        &mut sink,
+        COLORS[4],
+    );
+    let ticket = sink.latest_ticket().unwrap();
+    assert_eq!(ticket.index, 1);
+
+    // Once the newest frame is ready, so is everything before it
+    ticket.wait(&ctx);
+    let first = sink.try_next_frame(&ctx).unwrap().expect("Frame not ready");
+    let second = sink.try_next_frame(&ctx).unwrap().expect("Frame not ready");
+    assert_eq!((first.index, second.index), (0, 1));
+    assert_eq!(&second.data[..4], COLORS[4]);
+    assert_eq!(sink.frames_presented(), 2);
+}
+
+#[tokio::test]
+async fn test_readback_ticket_awaits_frame() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+    let mut sink = ReadbackSink::new(&ctx, SIZE, SIZE, 2);
+
+    render_solid(
+        &ctx,
+        &mut renderer,
+        &mut texture_manager,
+        &mut sink,
+        COLORS[1],
+    );
+    let ticket = sink.latest_ticket().unwrap();
+
+    // The ticket resolves from the map callback while `next_frame` polls
+    let (_, frame) = tokio::join!(ticket.ready(), sink.next_frame(&ctx));
+    assert!(ticket.is_ready());
+    assert_eq!(&frame.unwrap().expect("Frame missing").data[..4], COLORS[1]);
+}
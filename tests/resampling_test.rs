@@ -0,0 +1,115 @@
+use glam::Vec2;
+use uuid::Uuid;
+use videomti_render::core::RenderContext;
+use videomti_render::model::{
+    FrameDescription, Layer, LayerSource, LayerTransform, ResamplingQuality,
+};
+use videomti_render::outputs::BufferSink;
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::TextureManager;
+
+const SIZE: u32 = 16;
+
+/// Renders `id` stretched over a `size` x `size` square at the frame center
+/// and returns the red channel of the middle row.
+async fn render_row(
+    ctx: &RenderContext,
+    texture_manager: &TextureManager,
+    id: Uuid,
+    size: f32,
+    resampling: ResamplingQuality,
+) -> Vec<u8> {
+    let mut renderer = Renderer::new(ctx);
+    let mut sink = BufferSink::new(ctx, SIZE, SIZE);
+
+    let mut frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::splat(SIZE as f32 / 2.0),
+            scale: Vec2::splat(size),
+            ..Default::default()
+        },
+        resampling,
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+
+    renderer
+        .render(ctx, texture_manager, &frame, &mut [&mut sink])
+        .expect("Render failed");
+    let pixels = sink.read_pixels(ctx).await.unwrap();
+    let row = (SIZE / 2 * SIZE * 4) as usize;
+    (0..SIZE as usize).map(|x| pixels[row + x * 4]).collect()
+}
+
+#[tokio::test]
+async fn test_upscale_filters() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+
+    // Black left half, white right half
+    let id = Uuid::new_v4();
+    let data = [[0u8, 0, 0, 255], [255, 255, 255, 255]].concat().repeat(2);
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, 2, 2, &data)
+        .expect("Upload failed");
+
+    let size = SIZE as f32;
+    let nearest = render_row(&ctx, &texture_manager, id, size, ResamplingQuality::Nearest).await;
+    assert_eq!(nearest, [[0u8; 8], [255; 8]].concat());
+
+    for quality in [
+        ResamplingQuality::Bilinear,
+        ResamplingQuality::Bicubic,
+        ResamplingQuality::Lanczos,
+    ] {
+        let row = render_row(&ctx, &texture_manager, id, size, quality).await;
+        assert_eq!((row[0], row[15]), (0, 255), "{:?}: {:?}", quality, row);
+        assert!(
+            row[7] > 0 && row[8] < 255,
+            "{:?} should blend across the edge: {:?}",
+            quality,
+            row
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_mipmapped_downscale() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    texture_manager.set_mipmaps(true);
+
+    // One-pixel checkerboard, shrunk 16x
+    let id = Uuid::new_v4();
+    let size = 64u32;
+    let data: Vec<u8> = (0..size * size)
+        .flat_map(|i| {
+            let on = ((i % size) + (i / size)).is_multiple_of(2);
+            let v = if on { 255 } else { 0 };
+            [v, v, v, 255]
+        })
+        .collect();
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, size, size, &data)
+        .expect("Upload failed");
+    let res = texture_manager.get_resource(&id).unwrap();
+    assert_eq!(res.texture.mip_level_count(), 7);
+
+    // Every filter sees the average instead of aliasing to black or white
+    for quality in [
+        ResamplingQuality::Nearest,
+        ResamplingQuality::Bilinear,
+        ResamplingQuality::Bicubic,
+        ResamplingQuality::Lanczos,
+    ] {
+        let row = render_row(&ctx, &texture_manager, id, 4.0, quality).await;
+        for &v in &row[6..10] {
+            assert!(v.abs_diff(128) <= 4, "{:?}: {:?}", quality, row);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use image::{Rgb, RgbImage};
+use std::path::PathBuf;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::LayerSource;
+use videomti_render::resources::TextureManager;
+use videomti_render::sources::{
+    ImageSequence, ImageSequenceSource, MissingFramePolicy, SequencePattern,
+};
+
+mod common;
+use common::render_center;
+
+const FPS: f64 = 10.0;
+
+/// Writes frames 1, 2 and 4 of `plate_%04d.png` (3 is missing), each a
+/// solid color, plus a file that must not match.
+fn write_sequence() -> (PathBuf, Vec<[u8; 3]>) {
+    let dir = std::env::temp_dir().join(format!("videomti-seq-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let colors = vec![[200, 0, 0], [0, 200, 0], [0, 0, 0], [0, 0, 200]];
+    for number in [1u32, 2, 4] {
+        let color = colors[number as usize - 1];
+        RgbImage::from_pixel(4, 4, Rgb(color))
+            .save(dir.join(format!("plate_{:04}.png", number)))
+            .unwrap();
+    }
+    std::fs::write(dir.join("plate_notes.txt"), "not a frame").unwrap();
+    (dir, colors)
+}
+
+#[test]
+fn test_sequence_patterns() {
+    let (dir, _) = write_sequence();
+
+    let printf = SequencePattern::Printf(dir.join("plate_%04d.png").to_string_lossy().into());
+    let sequence = ImageSequence::open(&printf, None, FPS).expect("Open failed");
+    assert_eq!(sequence.frame_range(), 1..=4);
+    assert!(sequence.path(3).is_none());
+    assert_eq!(sequence.frame_at(0.0), 1);
+    assert_eq!(sequence.frame_at(0.3), 4);
+    assert_eq!(sequence.frame_at(10.0), 4, "holds the last frame");
+
+    let glob = SequencePattern::Glob(dir.join("plate_*.png").to_string_lossy().into());
+    let globbed = ImageSequence::open(&glob, Some(0), FPS).expect("Open failed");
+    assert_eq!(globbed.frame_range(), 0..=4);
+    assert_eq!(globbed.path(4), sequence.path(4));
+
+    let none = SequencePattern::Glob(dir.join("*.exr").to_string_lossy().into());
+    assert!(matches!(
+        ImageSequence::open(&none, None, FPS),
+        Err(RenderError::InvalidSource(_))
+    ));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_sequence_playback() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let (dir, colors) = write_sequence();
+    let pattern = SequencePattern::Printf(dir.join("plate_%04d.png").to_string_lossy().into());
+
+    for policy in [
+        MissingFramePolicy::Hold,
+        MissingFramePolicy::Black,
+        MissingFramePolicy::Error,
+    ] {
+        let sequence = ImageSequence::open(&pattern, None, FPS)
+            .unwrap()
+            .with_missing_frames(policy);
+        let mut texture_manager = TextureManager::new();
+        let id = Uuid::new_v4();
+        let mut source = ImageSequenceSource::new(id, sequence).unwrap();
+
+        for number in 1..=4u32 {
+            let time = (number - 1) as f64 / FPS;
+            let shown = source.update(&ctx.device, &ctx.queue, &mut texture_manager, time);
+            let expected = match (number, policy) {
+                (3, MissingFramePolicy::Hold) => colors[1],
+                (3, MissingFramePolicy::Error) => {
+                    assert!(matches!(shown, Err(RenderError::MissingFrame(3))));
+                    continue;
+                }
+                _ => colors[number as usize - 1],
+            };
+            shown.unwrap_or_else(|e| panic!("{:?} frame {}: {}", policy, number, e));
+            let got = render_center(
+                &ctx,
+                &texture_manager,
+                LayerSource::Video { resource_id: id },
+                [1.0, 1.0, 1.0, 1.0],
+            )
+            .await;
+            assert_eq!(got, expected, "{:?} frame {}", policy, number);
+        }
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_sequence_read_ahead() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let (dir, _) = write_sequence();
+    let pattern = SequencePattern::Glob(dir.join("plate_*.png").to_string_lossy().into());
+    let sequence = ImageSequence::open(&pattern, None, FPS).unwrap();
+    let mut texture_manager = TextureManager::new();
+    let mut source = ImageSequenceSource::with_read_ahead(Uuid::new_v4(), sequence, 8).unwrap();
+
+    assert_eq!(
+        source
+            .update(&ctx.device, &ctx.queue, &mut texture_manager, 0.0)
+            .unwrap(),
+        Some(1)
+    );
+    // The rest of the sequence, held frame included, is decoded in the
+    // background
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while source.ready_frames() < 4 && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert_eq!(source.ready_frames(), 4);
+
+    // Seeking to the last frame drops the earlier ones from the cache
+    assert_eq!(
+        source
+            .update(&ctx.device, &ctx.queue, &mut texture_manager, 0.35)
+            .unwrap(),
+        Some(4)
+    );
+    assert_eq!(source.ready_frames(), 1);
+
+    drop(source);
+    std::fs::remove_dir_all(dir).unwrap();
+}
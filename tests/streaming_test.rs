@@ -0,0 +1,96 @@
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::LayerSource;
+use videomti_render::resources::{PixelFormat, PixelLayout, TextureManager, UploadRing};
+
+mod common;
+use common::render_center;
+
+#[tokio::test]
+async fn test_streaming_uploads() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    texture_manager.set_frame_buffers(3);
+    let id = Uuid::new_v4();
+
+    // Decoder-style output: 24-bit RGB (GPU expanded) and padded RGBA rows
+    let formats = [PixelFormat::Rgba8, PixelFormat::Rgb8];
+    let features = ctx.device.features();
+    let slot_size = formats
+        .iter()
+        .map(|&f| UploadRing::staged_size(&PixelLayout::new(f, 24, 12), features))
+        .max()
+        .unwrap();
+    let ring = UploadRing::new(&ctx.device, 2, slot_size);
+
+    for frame in 0..6u8 {
+        let format = formats[frame as usize % 2];
+        let bpp = format.bytes_per_pixel();
+        let layout = PixelLayout::new(format, 24, 12).with_stride(24 * bpp + 4);
+        let rgb = [frame * 40, 255 - frame * 40, 128];
+
+        // Fill the slot on a worker thread
+        let mut slot = ring.acquire_wait(&ctx).expect("Ring has slots");
+        let slot = std::thread::spawn(move || {
+            let mut texel = rgb.to_vec();
+            if bpp == 4 {
+                texel.push(255);
+            }
+            let mut row = texel.repeat(24);
+            row.extend([0; 4]);
+            slot.write(&layout, &row.repeat(12))
+                .expect("Slot write failed");
+            slot
+        })
+        .join()
+        .unwrap();
+
+        let fence = texture_manager
+            .submit_upload(&ctx.device, &ctx.queue, id, slot)
+            .expect("Submit failed");
+        assert_eq!(
+            render_center(
+                &ctx,
+                &texture_manager,
+                LayerSource::Video { resource_id: id },
+                [0.0, 0.0, 0.0, 1.0],
+            )
+            .await,
+            rgb
+        );
+        fence.wait(&ctx);
+        assert!(fence.is_complete());
+    }
+
+    let res = texture_manager.get_resource(&id).unwrap();
+    assert_eq!(res.back_buffers.len(), 2);
+    assert_eq!(ring.free_slots(), 2);
+}
+
+#[tokio::test]
+async fn test_upload_ring_slots() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let ring = UploadRing::new(&ctx.device, 1, 1024);
+
+    // Frames larger than a slot are rejected
+    let mut slot = ring.acquire().expect("Slot available");
+    assert!(ring.acquire().is_none());
+    let layout = PixelLayout::new(PixelFormat::Rgba8, 32, 32);
+    let result = slot.write(&layout, &vec![0; 32 * 32 * 4]);
+    assert!(matches!(result, Err(RenderError::InvalidUpload(_))));
+
+    // Dropping an unsubmitted slot hands it back
+    drop(slot);
+    assert_eq!(ring.free_slots(), 1);
+
+    // Submitting an empty slot is an error, and the slot is not lost
+    let mut texture_manager = TextureManager::new();
+    let slot = ring.acquire().unwrap();
+    let result = texture_manager.submit_upload(&ctx.device, &ctx.queue, Uuid::new_v4(), slot);
+    assert!(matches!(result, Err(RenderError::InvalidUpload(_))));
+    assert_eq!(ring.free_slots(), 1);
+}
@@ -0,0 +1,122 @@
+use glam::Vec2;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{ColorSpace, FrameDescription, Layer, LayerSource, LayerTransform};
+use videomti_render::outputs::{BufferSink, TextureSink};
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::TextureManager;
+use wgpu::{TextureFormat, TextureUsages};
+
+const SIZE: u32 = 16;
+
+/// Copies the sink's texture out the way a host with `COPY_SRC` could.
+fn copy_out(ctx: &RenderContext, sink: &TextureSink) -> Vec<u8> {
+    let row = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: (row * SIZE) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = ctx
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.copy_texture_to_buffer(
+        sink.texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(row),
+                rows_per_image: None,
+            },
+        },
+        sink.texture.size(),
+    );
+    ctx.queue.submit(Some(encoder.finish()));
+    buffer.slice(..).map_async(wgpu::MapMode::Read, |_| {});
+    ctx.instance.poll_all(true);
+    let data = buffer.slice(..).get_mapped_range();
+    data.chunks(row as usize)
+        .flat_map(|r| &r[..(SIZE * 4) as usize])
+        .copied()
+        .collect()
+}
+
+#[tokio::test]
+async fn test_texture_sink() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+    let mut renderer = Renderer::new(&ctx);
+
+    let id = Uuid::new_v4();
+    texture_manager
+        .update_texture(
+            &ctx.device,
+            &ctx.queue,
+            id,
+            2,
+            2,
+            &[200, 100, 50, 255].repeat(4),
+        )
+        .expect("Upload failed");
+    let mut frame = FrameDescription::new(SIZE, SIZE, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::splat(SIZE as f32 / 2.0),
+            scale: Vec2::splat(SIZE as f32),
+            ..Default::default()
+        },
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+
+    // Same bytes as an 8-bit export; the sRGB view encodes on write
+    let mut sink = TextureSink::with_format(
+        &ctx,
+        SIZE,
+        SIZE,
+        TextureFormat::Rgba8UnormSrgb,
+        ColorSpace::SRGB,
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_SRC,
+    );
+    assert!(sink.usage().contains(TextureUsages::RENDER_ATTACHMENT));
+    let texture = sink.texture.clone();
+    let mut buffer = BufferSink::new(&ctx, SIZE, SIZE);
+    renderer
+        .render(
+            &ctx,
+            &texture_manager,
+            &frame,
+            &mut [&mut sink, &mut buffer],
+        )
+        .expect("Render failed");
+    let exported = buffer.read_pixels(&ctx).await.unwrap();
+    let rendered = copy_out(&ctx, &sink);
+    assert!(
+        rendered
+            .iter()
+            .zip(&exported)
+            .all(|(a, b)| a.abs_diff(*b) <= 1)
+    );
+    assert!(rendered.chunks(4).all(|p| p[0].abs_diff(200) <= 1));
+
+    // The texture lives across frames until the size changes
+    renderer
+        .render(&ctx, &texture_manager, &frame, &mut [&mut sink])
+        .expect("Render failed");
+    assert_eq!(sink.frames_rendered(), 2);
+    assert!(sink.texture == texture);
+    assert!(!sink.resize(&ctx, SIZE, SIZE).unwrap());
+    assert!(sink.resize(&ctx, SIZE * 2, SIZE).unwrap());
+    assert_eq!(sink.dimensions(), (SIZE * 2, SIZE));
+    assert!(matches!(
+        sink.resize(&ctx, 0, SIZE),
+        Err(RenderError::InvalidOutput(_))
+    ));
+    assert_eq!(sink.dimensions(), (SIZE * 2, SIZE));
+    assert!(sink.texture != texture);
+    assert_eq!(sink.texture.usage(), sink.usage());
+}
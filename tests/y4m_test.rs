@@ -0,0 +1,160 @@
+use std::io::Cursor;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::ColorSpace;
+use videomti_render::resources::{
+    ChromaSiting, TextureManager, YuvConversion, YuvFormat, YuvMatrix, YuvRange,
+};
+use videomti_render::sources::{FrameFormat, FramePrefetcher, FrameProvider, Y4mReader};
+
+mod common;
+use common::{SIZE, assert_close, encode_yuv, render_frame_center, video_frame};
+
+/// A Y4M stream of solid frames, one per color.
+fn y4m(header: &str, format: YuvFormat, conversion: YuvConversion, colors: &[[f32; 3]]) -> Vec<u8> {
+    let bits = format.bit_depth();
+    let mut bytes = format!("YUV4MPEG2 {}\n", header).into_bytes();
+    for rgb in colors {
+        let codes = encode_yuv(*rgb, conversion, bits);
+        bytes.extend_from_slice(b"FRAME\n");
+        for (plane, code) in codes.iter().enumerate() {
+            let (w, h) = format.plane_size(plane, SIZE, SIZE);
+            for _ in 0..w * h {
+                match bits {
+                    8 => bytes.push(*code as u8),
+                    _ => bytes.extend_from_slice(&(*code as u16).to_le_bytes()),
+                }
+            }
+        }
+    }
+    bytes
+}
+
+#[test]
+fn test_y4m_header() {
+    let limited = YuvConversion::new(YuvMatrix::Bt601, YuvRange::Limited);
+    let bytes = y4m(
+        "W16 H16 F30000:1001 Ip A1:1 C420mpeg2 XYSCSS=420MPEG2",
+        YuvFormat::I420,
+        limited,
+        &[[0.5; 3]; 3],
+    );
+    let reader = Y4mReader::new(Cursor::new(bytes.clone())).expect("Open failed");
+    assert_eq!(reader.header.frame_rate, (30000, 1001));
+    assert_eq!(reader.pixel_format(), FrameFormat::Yuv(YuvFormat::I420));
+    assert_eq!(reader.frame_count(), Some(3));
+    assert_eq!(reader.conversion.matrix, YuvMatrix::Bt601);
+    assert_eq!(reader.conversion.range, YuvRange::Limited);
+    assert_eq!(reader.conversion.siting, ChromaSiting::Left);
+
+    // A truncated last frame is dropped
+    let reader = Y4mReader::new(Cursor::new(bytes[..bytes.len() - 10].to_vec())).unwrap();
+    assert_eq!(reader.len(), 2);
+
+    let full = y4m(
+        "W16 H16 F25:1 C444p10 XCOLORRANGE=FULL",
+        YuvFormat::I444P10,
+        limited,
+        &[],
+    );
+    let reader = Y4mReader::new(Cursor::new(full)).unwrap();
+    assert_eq!(reader.header.format, YuvFormat::I444P10);
+    assert_eq!(reader.conversion.range, YuvRange::Full);
+    assert!(reader.is_empty());
+
+    // Unknown tags are skipped, whatever their first character
+    let bytes = y4m("W16 H16 F25:1 été ☃", YuvFormat::I420, limited, &[]);
+    assert!(Y4mReader::new(Cursor::new(bytes)).is_ok());
+
+    for header in [
+        "YUV4MPEG W16 H16 F25:1",
+        "YUV4MPEG2 W16 F25:1",
+        "YUV4MPEG2 W16 H16 F25:1 Cmono",
+        "YUV4MPEG2 W16 H16 F25:1 Cé",
+        "YUV4MPEG2 Wé16 H16 F25:1",
+    ] {
+        let bytes = format!("{}\n", header).into_bytes();
+        assert!(
+            matches!(
+                Y4mReader::new(Cursor::new(bytes)),
+                Err(RenderError::InvalidSource(_))
+            ),
+            "{}",
+            header
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_y4m_formats() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let rgb = [0.8, 0.35, 0.2];
+    let conversion = YuvConversion::new(YuvMatrix::Bt601, YuvRange::Limited);
+
+    for (tag, format) in [
+        ("420jpeg", YuvFormat::I420),
+        ("422", YuvFormat::I422),
+        ("444", YuvFormat::I444),
+        ("420p10", YuvFormat::I420P10),
+        ("422p10", YuvFormat::I422P10),
+        ("444p10", YuvFormat::I444P10),
+    ] {
+        let header = format!("W{} H{} F25:1 C{}", SIZE, SIZE, tag);
+        let bytes = y4m(&header, format, conversion, &[rgb]);
+        let reader = Y4mReader::new(Cursor::new(bytes)).expect("Open failed");
+        assert_eq!(reader.header.format, format);
+
+        let mut texture_manager = TextureManager::new();
+        let mut prefetcher = FramePrefetcher::new();
+        let id = Uuid::new_v4();
+        prefetcher.register(id, reader).expect("Register failed");
+        let frame = video_frame(id, 0.0);
+        prefetcher
+            .prepare(&ctx.device, &ctx.queue, &mut texture_manager, &frame)
+            .expect("Prepare failed");
+        // Compare in the encoded domain
+        texture_manager.set_color_space(id, ColorSpace::SRGB);
+        assert_close(
+            render_frame_center(&ctx, &texture_manager, &frame).await,
+            rgb,
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_y4m_playback() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let colors = [[0.8, 0.1, 0.1], [0.1, 0.8, 0.1], [0.1, 0.1, 0.8]];
+    let conversion = YuvConversion::new(YuvMatrix::Bt601, YuvRange::Limited);
+    let path = std::env::temp_dir().join(format!("videomti-{}.y4m", Uuid::new_v4()));
+    let header = format!("W{} H{} F24:1 C420jpeg", SIZE, SIZE);
+    std::fs::write(&path, y4m(&header, YuvFormat::I420, conversion, &colors)).unwrap();
+
+    let reader = Y4mReader::open(&path).expect("Open failed");
+    let mut texture_manager = TextureManager::new();
+    let mut prefetcher = FramePrefetcher::new();
+    let id = Uuid::new_v4();
+    let info = prefetcher.register(id, reader).expect("Register failed");
+    assert_eq!(info.frame_count, Some(3));
+
+    // Out of order, then past the end holding the last frame
+    for (time, color) in [(2.0 / 24.0, 2), (0.0, 0), (1.0 / 24.0, 1), (5.0, 2)] {
+        let frame = video_frame(id, time);
+        prefetcher
+            .prepare(&ctx.device, &ctx.queue, &mut texture_manager, &frame)
+            .expect("Prepare failed");
+        // Compare in the encoded domain; later uploads keep the tag
+        texture_manager.set_color_space(id, ColorSpace::SRGB);
+        assert_close(
+            render_frame_center(&ctx, &texture_manager, &frame).await,
+            colors[color],
+        );
+    }
+
+    drop(prefetcher);
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,218 @@
+use glam::Vec2;
+use uuid::Uuid;
+use videomti_render::core::{RenderContext, RenderError};
+use videomti_render::model::{ColorSpace, FrameDescription, Layer, LayerSource, LayerTransform};
+use videomti_render::outputs::YuvSink;
+use videomti_render::renderer::Renderer;
+use videomti_render::resources::{
+    TextureManager, YuvConversion, YuvFormat, YuvMatrix, YuvPlane, YuvRange,
+};
+
+mod common;
+use common::{SIZE, assert_close, encode_yuv, render_center};
+
+#[tokio::test]
+async fn test_yuv_uploads() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let rgb = [0.8, 0.35, 0.2];
+
+    let cases = [
+        (
+            YuvFormat::Nv12,
+            YuvConversion::new(YuvMatrix::Bt709, YuvRange::Limited),
+        ),
+        (
+            YuvFormat::I420,
+            YuvConversion::new(YuvMatrix::Bt601, YuvRange::Full),
+        ),
+        (
+            YuvFormat::I422,
+            YuvConversion::new(YuvMatrix::Bt709, YuvRange::Full),
+        ),
+        (
+            YuvFormat::P010,
+            YuvConversion::new(YuvMatrix::Bt2020, YuvRange::Limited),
+        ),
+    ];
+
+    for (format, conversion) in cases {
+        let mut texture_manager = TextureManager::new();
+        let id = Uuid::new_v4();
+        let bits = format.bit_depth();
+        let [y, cb, cr] = encode_yuv(rgb, conversion, bits);
+
+        // Build planes with 32 bytes of row padding to exercise strides
+        let sample = |code: u32| -> Vec<u8> {
+            if bits > 8 {
+                ((code << (16 - bits)) as u16).to_le_bytes().to_vec()
+            } else {
+                vec![code as u8]
+            }
+        };
+        let build_plane = |index: usize, texel: Vec<u8>| {
+            let (w, h) = format.plane_size(index, SIZE, SIZE);
+            let stride = format.plane_row_bytes(index, SIZE) + 32;
+            let mut data = vec![0u8; (stride * h) as usize];
+            for row in 0..h {
+                for col in 0..w as usize {
+                    let start = (row * stride) as usize + col * texel.len();
+                    data[start..start + texel.len()].copy_from_slice(&texel);
+                }
+            }
+            (data, stride)
+        };
+
+        let mut plane_data = vec![build_plane(0, sample(y))];
+        if format.is_semi_planar() {
+            plane_data.push(build_plane(1, [sample(cb), sample(cr)].concat()));
+        } else {
+            plane_data.push(build_plane(1, sample(cb)));
+            plane_data.push(build_plane(2, sample(cr)));
+        }
+        let planes: Vec<_> = plane_data
+            .iter()
+            .map(|(data, stride)| YuvPlane::new(data, *stride))
+            .collect();
+
+        texture_manager
+            .update_yuv_texture(
+                &ctx.device,
+                &ctx.queue,
+                id,
+                format,
+                SIZE,
+                SIZE,
+                &planes,
+                conversion,
+            )
+            .expect("YUV upload failed");
+        // Compare in the encoded domain
+        texture_manager.set_color_space(id, ColorSpace::SRGB);
+
+        let got = render_center(
+            &ctx,
+            &texture_manager,
+            LayerSource::Video { resource_id: id },
+            [0.0, 0.0, 0.0, 1.0],
+        )
+        .await;
+        assert_close(got, rgb);
+    }
+}
+
+#[tokio::test]
+async fn test_yuv_rejects_short_planes() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut texture_manager = TextureManager::new();
+
+    let luma = vec![0u8; (SIZE * SIZE) as usize];
+    let chroma = vec![0u8; 8];
+    let result = texture_manager.update_yuv_texture(
+        &ctx.device,
+        &ctx.queue,
+        Uuid::new_v4(),
+        YuvFormat::Nv12,
+        SIZE,
+        SIZE,
+        &[YuvPlane::new(&luma, SIZE), YuvPlane::new(&chroma, SIZE)],
+        YuvConversion::default(),
+    );
+    assert!(matches!(result, Err(RenderError::InvalidUpload(_))));
+
+    for (width, height) in [(SIZE, 0), (0, SIZE)] {
+        let result = texture_manager.update_yuv_texture(
+            &ctx.device,
+            &ctx.queue,
+            Uuid::new_v4(),
+            YuvFormat::Nv12,
+            width,
+            height,
+            &[YuvPlane::new(&luma, SIZE), YuvPlane::new(&luma, SIZE)],
+            YuvConversion::default(),
+        );
+        assert!(matches!(result, Err(RenderError::InvalidUpload(_))));
+    }
+}
+
+#[tokio::test]
+async fn test_yuv_sink_output() {
+    let ctx = RenderContext::new(None)
+        .await
+        .expect("Failed to create context");
+    let mut renderer = Renderer::new(&ctx);
+    let mut texture_manager = TextureManager::new();
+
+    // Solid sRGB color
+    let id = Uuid::new_v4();
+    let texel = [204u8, 89, 51, 255];
+    texture_manager
+        .update_texture(&ctx.device, &ctx.queue, id, 2, 2, &texel.repeat(4))
+        .expect("Upload failed");
+    let rgb = [texel[0], texel[1], texel[2]].map(|c| c as f32 / 255.0);
+
+    // Odd sizes exercise word padding in the packed planes
+    let (width, height) = (18, 10);
+    let mut frame = FrameDescription::new(width, height, [0.0, 0.0, 0.0, 1.0]);
+    frame.layers.push(Layer {
+        source: LayerSource::Image { resource_id: id },
+        transform: LayerTransform {
+            position: Vec2::new(width as f32 / 2.0, height as f32 / 2.0),
+            scale: Vec2::new(width as f32, height as f32),
+            ..Default::default()
+        },
+        ..Layer::new_color(Uuid::new_v4(), [0.0; 4])
+    });
+
+    for format in [
+        YuvFormat::Nv12,
+        YuvFormat::I420,
+        YuvFormat::I422,
+        YuvFormat::P010,
+    ] {
+        let conversion = YuvConversion::new(YuvMatrix::Bt709, YuvRange::Limited);
+        let mut sink = YuvSink::new(&ctx, width, height, format, conversion);
+        sink.color_space = ColorSpace::SRGB;
+
+        renderer
+            .render(&ctx, &texture_manager, &frame, &mut [&mut sink])
+            .expect("Render failed");
+        let yuv = sink.read_planes(&ctx).await.expect("Failed to read planes");
+
+        assert_eq!(yuv.planes.len(), format.plane_count());
+        for (index, plane) in yuv.planes.iter().enumerate() {
+            let (_, rows) = format.plane_size(index, width, height);
+            assert_eq!(plane.len(), (yuv.stride(index) * rows) as usize);
+        }
+
+        let bits = format.bit_depth();
+        let read = |plane: &[u8], i: usize| -> u32 {
+            if bits > 8 {
+                (u16::from_le_bytes([plane[2 * i], plane[2 * i + 1]]) >> (16 - bits)) as u32
+            } else {
+                plane[i] as u32
+            }
+        };
+        let y = read(&yuv.planes[0], 5);
+        let (cb, cr) = if format.is_semi_planar() {
+            (read(&yuv.planes[1], 2), read(&yuv.planes[1], 3))
+        } else {
+            (read(&yuv.planes[1], 1), read(&yuv.planes[2], 1))
+        };
+
+        let expected = encode_yuv(rgb, conversion, bits);
+        let tolerance = 1 << (bits - 8);
+        for (got, want) in [y, cb, cr].into_iter().zip(expected) {
+            assert!(
+                got.abs_diff(want) <= tolerance,
+                "{:?}: expected {}, got {}",
+                format,
+                want,
+                got
+            );
+        }
+    }
+}